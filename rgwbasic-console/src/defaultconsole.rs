@@ -3,23 +3,56 @@ use std::io;
 use std::io::prelude::*;
 use std::io::BufReader;
 use std::process::exit;
-use rgwbasic::eval::context::Console;
+use rgwbasic::eval::GwError;
+use rgwbasic::eval::context::{ Console, advance_column_position };
+use rgwbasic::eval::files::FileSystem;
 
+/// Files used by `OPEN`, relative to the current directory
+pub struct DiskFileSystem;
+
+fn to_gw_error(error: io::Error) -> GwError {
+    match error.kind() {
+        io::ErrorKind::NotFound => GwError::FileNotFound,
+        io::ErrorKind::PermissionDenied => GwError::PermissionDenied,
+        _ => GwError::DeviceIoError
+    }
+}
+
+impl FileSystem for DiskFileSystem {
+    fn read_file(&self, file_name: &str) -> Result<Vec<u8>, GwError> {
+        std::fs::read(file_name).map_err(to_gw_error)
+    }
+
+    fn write_file(&mut self, file_name: &str, contents: &[u8]) -> Result<(), GwError> {
+        std::fs::write(file_name, contents).map_err(to_gw_error)
+    }
+
+    fn append_file(&mut self, file_name: &str, contents: &[u8]) -> Result<(), GwError> {
+        std::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(file_name)
+            .and_then(|mut file| file.write_all(contents))
+            .map_err(to_gw_error)
+    }
+}
 
 pub struct DefaultConsole {
-    column_position: usize
+    column_position: usize,
+    files: DiskFileSystem
 }
 
 impl DefaultConsole {
     pub fn new() -> DefaultConsole {
-        DefaultConsole {  column_position: 0 }
+        DefaultConsole {  column_position: 0, files: DiskFileSystem }
     }
 }
 
 impl Console for DefaultConsole {
     fn print(&mut self, value: &str) {
         print!("{}", value);
-        self.column_position += value.len();
+        self.column_position =
+            advance_column_position(self.column_position, value, self.text_width());
     }
 
     fn print_line(&mut self, value: &str) {
@@ -57,6 +90,9 @@ impl Console for DefaultConsole {
         exit(0);
     }
     fn clone(&self) -> Box<dyn Console> {
-        Box::new(DefaultConsole { column_position: self.column_position } )
+        Box::new(DefaultConsole { column_position: self.column_position, files: DiskFileSystem } )
+    }
+    fn file_system(&mut self) -> Option<&mut dyn FileSystem> {
+        Some(&mut self.files)
     }
 }
@@ -25,20 +25,21 @@ fn main() -> io::Result<()> {
     let mut success = read_stdin_line(&mut uline);
     while success {
         if !uline.is_empty() && uline.chars().next().unwrap().is_ascii_digit() {
-            match parser::parse_instruction_line_from_string(uline) {
-                parser::ParserResult::Success(parsed_line) => program.add_line(parsed_line),
-                parser::ParserResult::Error(error) => println!("Error: {}", error),
-                parser::ParserResult::Nothing=> println!("Nothing")       
+            if let Err(error) = program.enter_line(uline) {
+                println!("{}", error);
             }
         } else {
             match parser::parse_repl_instruction_string (uline) {
                 parser::ParserResult::Success(parsed_instr) => {
                     let mut context = eval::EvaluationContext::with_program(&mut program, Box::new(DefaultConsole::new()));
-                    parsed_instr.eval(-1, eval::LineExecutionArgument::Empty, &mut context, &mut program);
+                    let result = parsed_instr.eval(-1, eval::LineExecutionArgument::Empty, &mut context, &mut program);
+                    if let eval::InstructionResult::EvaluateToError(error) = result {
+                        context.console.print_line(error.to_string().as_str());
+                    }
                     context.console.flush();
                 }
-                parser::ParserResult::Error(msg) => {
-                    println!("Error parsing command {}", msg);
+                parser::ParserResult::Error(error) => {
+                    println!("{}", error);
                 }
                 _ => {
                     println!("Error processing command");
@@ -6,7 +6,9 @@ use rgwbasic::eval::context::{AsyncAction,
                               StepExecutionInfo,
                               EvaluationContext,
                               EvalFragmentAsyncResult,
-                              InstructionResult };
+                              InstructionResult,
+                              advance_column_position };
+use rgwbasic::eval::files::{FileSystem, MemoryFileSystem};
 use wasm_bindgen::prelude::*;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -189,14 +191,21 @@ impl GwInterpreterWrapper {
 }
 
 
+thread_local! {
+    /// Files used by `OPEN`, kept for the lifetime of the page
+    static PAGE_FILES: MemoryFileSystem = MemoryFileSystem::new();
+}
+
 struct HtmlDivConsole {
-    column_position: usize
+    column_position: usize,
+    files: MemoryFileSystem
 }
 
 impl HtmlDivConsole {
     fn new() -> HtmlDivConsole {
         HtmlDivConsole {
-            column_position: 0
+            column_position: 0,
+            files: PAGE_FILES.with(|files| files.clone())
         }
     }
 }
@@ -204,7 +213,8 @@ impl HtmlDivConsole {
 impl Console for HtmlDivConsole {
     fn print(&mut self, value: &str) {
         appendElementLd(value);
-        self.column_position += value.len();
+        self.column_position =
+            advance_column_position(self.column_position, value, self.text_width());
     }
 
     
@@ -223,7 +233,7 @@ impl Console for HtmlDivConsole {
     }
 
     fn current_text_column(&self) -> usize {
-       self.column_position
+       self.column_position + 1
     }
 
     fn read_file_lines(&self, file_name: &str) -> Box<dyn Iterator<Item=String>> {
@@ -245,9 +255,20 @@ impl Console for HtmlDivConsole {
         Box::new(HtmlDivConsole::new())
 
     }
+    fn file_system(&mut self) -> Option<&mut dyn FileSystem> {
+        Some(&mut self.files)
+    }
     fn log(&self, msg: &str) {
         log(msg);
     }
+
+    fn seconds_since_midnight(&self) -> f64 {
+        let now = js_sys::Date::new_0();
+        f64::from(now.get_hours()) * 3600.0
+            + f64::from(now.get_minutes()) * 60.0
+            + f64::from(now.get_seconds())
+            + f64::from(now.get_milliseconds()) / 1000.0
+    }
 }
 
 
@@ -354,15 +375,9 @@ impl GwWsmInterpreter {
         log("1. eval in interpreter");
         let mut uline = command.to_string();
         if !uline.is_empty() && uline.chars().next().unwrap().is_ascii_digit() {
-            match parser::parse_instruction_line_from_string(uline) {
-                parser::ParserResult::Success(parsed_line) => { self.program.add_line(parsed_line); }
-                parser::ParserResult::Error(error) => {
-                    HtmlDivConsole::new().print_line(&format!("Error: {}", error));
-                }
-                parser::ParserResult::Nothing => {
-                    HtmlDivConsole::new().print_line("Nothing");
-                }
-            };
+            if let Err(error) = self.program.enter_line(uline) {
+                HtmlDivConsole::new().print_line(error.to_string().as_str());
+            }
         } else {
             log("2. ???");
             match parser::parse_repl_instruction_string (uline) {
@@ -379,15 +394,15 @@ impl GwWsmInterpreter {
                         InstructionResult::RequestAsyncAction(async_action)  => {
                             return Some(async_action);
                         }
-                        InstructionResult::EvaluateToError(ref message) => {
-                           context.console.print_line(message)
+                        InstructionResult::EvaluateToError(ref error) => {
+                           context.console.print_line(error.to_string().as_str())
                         }                       
                         _ => {}// todo!("Not implemented result of instruction")
                     }
                     context.console.flush();
                 }
-                parser::ParserResult::Error(msg) => {
-                    self.console.print_line("Error parsing command");
+                parser::ParserResult::Error(error) => {
+                    self.console.print_line(error.to_string().as_str());
                 }
                 _ => {
                     self.console.print_line("Error processing command");
@@ -2,6 +2,7 @@ use crate::eval::GwExpression;
 use crate::eval::EvaluationContext;
 use crate::eval::ExpressionEvalResult;
 use crate::eval::EvaluationError;
+use crate::eval::GwError;
 
 
 pub enum GwBinaryOperationKind {
@@ -25,19 +26,40 @@ pub enum GwBinaryOperationKind {
     Implication
 }
 
+/// Evaluates a binary operator from the values of both operands
 trait BinaryOperationEvaluator {
-    fn perform_int_operation(&self, left: i16, right: i16) -> i16;
+    fn evaluate(&self,
+                left_result : &ExpressionEvalResult,
+                right_result : &ExpressionEvalResult)
+                -> Result<ExpressionEvalResult, GwError>;
+}
+
+impl<T: NumericOperationEvaluator> BinaryOperationEvaluator for T {
+    fn evaluate(&self,
+                left_result : &ExpressionEvalResult,
+                right_result : &ExpressionEvalResult)
+                -> Result<ExpressionEvalResult, GwError> {
+        NumericOperationEvaluator::evaluate(self, left_result, right_result)
+    }
+}
+
+/// Operators computed in the type of their operands, promoting integer
+/// and single precision operands to the precision of the other one
+trait NumericOperationEvaluator {
+    /// Integer result of the operation, `None` when it does not fit in
+    /// an integer and is computed in single precision as in GW-BASIC
+    fn perform_int_operation(&self, left: i16, right: i16) -> Option<i16>;
     fn perform_single_operation(&self, left: f32, right: f32) -> f32;
     fn perform_double_operation(&self, left: f64, right: f64) -> f64;
     fn perform_string_operation(&self, _left: &String, _right: &String)
-                                -> Result<ExpressionEvalResult, &'static str> {
-        Err("Type mismatch")
+                                -> Result<ExpressionEvalResult, GwError> {
+        Err(GwError::TypeMismatch)
     }
 
     fn evaluate(&self,
                 left_result : &ExpressionEvalResult,
                 right_result : &ExpressionEvalResult)
-                -> Result<ExpressionEvalResult, &'static str> {
+                -> Result<ExpressionEvalResult, GwError> {
         match (left_result, right_result) {
             (ExpressionEvalResult::IntegerResult(left), right) => self.evaluate_int_vs(*left, right),
             (ExpressionEvalResult::SingleResult(left), right) =>  self.evaluate_single_vs(*left, right),            
@@ -45,62 +67,89 @@ trait BinaryOperationEvaluator {
             (ExpressionEvalResult::StringResult(left),
              ExpressionEvalResult::StringResult(right)) =>
                 self.perform_string_operation(left, right),
-            (_, _) => Err("Type mismatch")
+            (_, _) => Err(GwError::TypeMismatch)
         }
     }
 
     fn evaluate_int_vs(&self,
                 left: i16,
                 right_result: &ExpressionEvalResult)
-                -> Result<ExpressionEvalResult, &'static str> {
+                -> Result<ExpressionEvalResult, GwError> {
         match right_result {
              ExpressionEvalResult::IntegerResult(right) =>
-                Ok(ExpressionEvalResult::IntegerResult(self.perform_int_operation(left, *right))),
+                match self.perform_int_operation(left, *right) {
+                    Some(result) => Ok(ExpressionEvalResult::IntegerResult(result)),
+                    None => single_result(self.perform_single_operation(f32::from(left), f32::from(*right)))
+                },
             ExpressionEvalResult::DoubleResult(right) =>
-                Ok(ExpressionEvalResult::DoubleResult(self.perform_double_operation(f64::from(left), *right))),
+                double_result(self.perform_double_operation(f64::from(left), *right)),
             ExpressionEvalResult::SingleResult(right) =>
-                Ok(ExpressionEvalResult::SingleResult(self.perform_single_operation(f32::from(left), *right))),
-            _  => Err("Type mismatch")
+                single_result(self.perform_single_operation(f32::from(left), *right)),
+            _  => Err(GwError::TypeMismatch)
         }
     }
 
     fn evaluate_single_vs(&self,
                           left: f32,
                           right_result : &ExpressionEvalResult)
-                -> Result<ExpressionEvalResult, &'static str> {
+                -> Result<ExpressionEvalResult, GwError> {
         match right_result {
              ExpressionEvalResult::IntegerResult(right) =>
-                Ok(ExpressionEvalResult::SingleResult(self.perform_single_operation(left, f32::from(*right)))),
+                single_result(self.perform_single_operation(left, f32::from(*right))),
             ExpressionEvalResult::DoubleResult(right) =>
-                Ok(ExpressionEvalResult::DoubleResult(self.perform_double_operation(f64::from(left), *right))),
+                double_result(self.perform_double_operation(f64::from(left), *right)),
             ExpressionEvalResult::SingleResult(right) =>
-                Ok(ExpressionEvalResult::SingleResult(self.perform_single_operation(left, *right))),
-            _  => Err("Type mismatch")
+                single_result(self.perform_single_operation(left, *right)),
+            _  => Err(GwError::TypeMismatch)
         }
     }
 
     fn evaluate_double_vs(&self,
                           left: f64,
                           right_result : &ExpressionEvalResult)
-                -> Result<ExpressionEvalResult, &'static str> {
+                -> Result<ExpressionEvalResult, GwError> {
         match right_result {
              ExpressionEvalResult::IntegerResult(right) =>
-                Ok(ExpressionEvalResult::DoubleResult(self.perform_double_operation(left, f64::from(*right)))),
+                double_result(self.perform_double_operation(left, f64::from(*right))),
             ExpressionEvalResult::DoubleResult(right) =>
-                Ok(ExpressionEvalResult::DoubleResult(self.perform_double_operation(f64::from(left), *right))),
+                double_result(self.perform_double_operation(left, *right)),
             ExpressionEvalResult::SingleResult(right) =>
-                Ok(ExpressionEvalResult::DoubleResult(self.perform_double_operation(left , f64::from(*right)))),
-            _  => Err("Type mismatch")
+                double_result(self.perform_double_operation(left , f64::from(*right))),
+            _  => Err(GwError::TypeMismatch)
         }
     }
 }
 
+/// Single precision result of an operation. GW-BASIC reports results
+/// out of range as `Overflow` and has no infinite or NaN values, which
+/// only come from fractional powers of negative numbers
+pub fn single_result(value: f32) -> Result<ExpressionEvalResult, GwError> {
+    if value.is_nan() {
+        Err(GwError::IllegalFunctionCall)
+    } else if value.is_infinite() {
+        Err(GwError::Overflow)
+    } else {
+        Ok(ExpressionEvalResult::SingleResult(value))
+    }
+}
+
+/// Double precision result of an operation, checked as `single_result`
+pub fn double_result(value: f64) -> Result<ExpressionEvalResult, GwError> {
+    if value.is_nan() {
+        Err(GwError::IllegalFunctionCall)
+    } else if value.is_infinite() {
+        Err(GwError::Overflow)
+    } else {
+        Ok(ExpressionEvalResult::DoubleResult(value))
+    }
+}
+
 struct PlusEvaluator {
 }
 
-impl BinaryOperationEvaluator for PlusEvaluator {
-    fn perform_int_operation(&self, left : i16, right : i16) -> i16 {
-        left + right
+impl NumericOperationEvaluator for PlusEvaluator {
+    fn perform_int_operation(&self, left : i16, right : i16) -> Option<i16> {
+        left.checked_add(right)
     }
 
     fn perform_single_operation(&self, left : f32, right : f32) -> f32 {
@@ -110,14 +159,24 @@ impl BinaryOperationEvaluator for PlusEvaluator {
     fn perform_double_operation(&self, left : f64, right : f64) -> f64 {
         left + right
     }
+
+    /// Concatenates strings up to the 255 characters of a GW-BASIC string
+    fn perform_string_operation(&self, left: &String, right: &String)
+                                -> Result<ExpressionEvalResult, GwError> {
+        if left.chars().count() + right.chars().count() > 255 {
+            Err(GwError::StringTooLong)
+        } else {
+            Ok(ExpressionEvalResult::StringResult(format!("{}{}", left, right)))
+        }
+    }
 }
 
 struct MinusEvaluator {
 }
 
-impl BinaryOperationEvaluator for MinusEvaluator {
-    fn perform_int_operation(&self, left : i16, right : i16) -> i16 {
-        left - right
+impl NumericOperationEvaluator for MinusEvaluator {
+    fn perform_int_operation(&self, left : i16, right : i16) -> Option<i16> {
+        left.checked_sub(right)
     }
 
     fn perform_single_operation(&self, left : f32, right : f32) -> f32 {
@@ -132,13 +191,9 @@ impl BinaryOperationEvaluator for MinusEvaluator {
 struct EqualEvaluator {
 }
 
-impl BinaryOperationEvaluator for EqualEvaluator {
-    fn perform_int_operation(&self, left : i16, right : i16) -> i16 {
-        if left == right {
-            -1
-        } else {
-            0
-        }
+impl NumericOperationEvaluator for EqualEvaluator {
+    fn perform_int_operation(&self, left : i16, right : i16) -> Option<i16> {
+        Some(bool_to_basic_value(left == right))
     }
 
     fn perform_single_operation(&self, left : f32, right : f32) -> f32 {
@@ -158,7 +213,7 @@ impl BinaryOperationEvaluator for EqualEvaluator {
     }
     
     fn perform_string_operation(&self, left: &String, right: &String)
-                                -> Result<ExpressionEvalResult, &'static str> {
+                                -> Result<ExpressionEvalResult, GwError> {
         Ok(
             if left == right {
                 ExpressionEvalResult::SingleResult(-1.0)
@@ -172,13 +227,9 @@ impl BinaryOperationEvaluator for EqualEvaluator {
 struct DifferentEvaluator {
 }
 
-impl BinaryOperationEvaluator for DifferentEvaluator {
-    fn perform_int_operation(&self, left : i16, right : i16) -> i16 {
-        if left != right {
-            -1
-        } else {
-            0
-        }
+impl NumericOperationEvaluator for DifferentEvaluator {
+    fn perform_int_operation(&self, left : i16, right : i16) -> Option<i16> {
+        Some(bool_to_basic_value(left != right))
     }
 
     fn perform_single_operation(&self, left : f32, right : f32) -> f32 {
@@ -198,7 +249,7 @@ impl BinaryOperationEvaluator for DifferentEvaluator {
     }
 
     fn perform_string_operation(&self, left: &String, right: &String)
-                                -> Result<ExpressionEvalResult, &'static str> {
+                                -> Result<ExpressionEvalResult, GwError> {
         Ok(ExpressionEvalResult::IntegerResult(bool_to_basic_value(left.cmp(right).is_ne())))
     }
 }
@@ -206,9 +257,9 @@ impl BinaryOperationEvaluator for DifferentEvaluator {
 struct LessThanEvaluator {
 }
 
-impl BinaryOperationEvaluator for LessThanEvaluator {
-    fn perform_int_operation(&self, left: i16, right: i16) -> i16 {
-        bool_to_basic_value(left < right)
+impl NumericOperationEvaluator for LessThanEvaluator {
+    fn perform_int_operation(&self, left: i16, right: i16) -> Option<i16> {
+        Some(bool_to_basic_value(left < right))
     }
 
     fn perform_single_operation(&self, left: f32, right: f32) -> f32 {
@@ -220,7 +271,7 @@ impl BinaryOperationEvaluator for LessThanEvaluator {
     }
 
     fn perform_string_operation(&self, left: &String, right: &String)
-                                -> Result<ExpressionEvalResult, &'static str> {
+                                -> Result<ExpressionEvalResult, GwError> {
         Ok(ExpressionEvalResult::IntegerResult(
             bool_to_basic_value(left.cmp(right).is_lt())))
     }
@@ -229,9 +280,9 @@ impl BinaryOperationEvaluator for LessThanEvaluator {
 struct GreaterThanEvaluator {
 }
 
-impl BinaryOperationEvaluator for GreaterThanEvaluator {
-    fn perform_int_operation(&self, left: i16, right: i16) -> i16 {
-        bool_to_basic_value(left > right)
+impl NumericOperationEvaluator for GreaterThanEvaluator {
+    fn perform_int_operation(&self, left: i16, right: i16) -> Option<i16> {
+        Some(bool_to_basic_value(left > right))
     }
 
     fn perform_single_operation(&self, left: f32, right: f32) -> f32 {
@@ -243,7 +294,7 @@ impl BinaryOperationEvaluator for GreaterThanEvaluator {
     }
 
     fn perform_string_operation(&self, left: &String, right: &String)
-                                -> Result<ExpressionEvalResult, &'static str> {
+                                -> Result<ExpressionEvalResult, GwError> {
         Ok(ExpressionEvalResult::IntegerResult(
             bool_to_basic_value(left.cmp(right).is_gt())))
     }
@@ -253,9 +304,9 @@ impl BinaryOperationEvaluator for GreaterThanEvaluator {
 struct LessEqualThanEvaluator {
 }
 
-impl BinaryOperationEvaluator for LessEqualThanEvaluator {
-    fn perform_int_operation(&self, left: i16, right: i16) -> i16 {
-        bool_to_basic_value(left <= right)
+impl NumericOperationEvaluator for LessEqualThanEvaluator {
+    fn perform_int_operation(&self, left: i16, right: i16) -> Option<i16> {
+        Some(bool_to_basic_value(left <= right))
     }
 
     fn perform_double_operation(&self, left: f64, right: f64) -> f64 {
@@ -267,7 +318,7 @@ impl BinaryOperationEvaluator for LessEqualThanEvaluator {
     }
 
     fn perform_string_operation(&self, left: &String, right: &String)
-                                -> Result<ExpressionEvalResult, &'static str> {
+                                -> Result<ExpressionEvalResult, GwError> {
         Ok(ExpressionEvalResult::IntegerResult(
             bool_to_basic_value(left.cmp(right).is_le())))
     }
@@ -277,9 +328,9 @@ impl BinaryOperationEvaluator for LessEqualThanEvaluator {
 struct GreaterEqualThanEvaluator {
 }
 
-impl BinaryOperationEvaluator for GreaterEqualThanEvaluator {
-    fn perform_int_operation(&self, left: i16, right: i16) -> i16 {
-        bool_to_basic_value(left >= right)
+impl NumericOperationEvaluator for GreaterEqualThanEvaluator {
+    fn perform_int_operation(&self, left: i16, right: i16) -> Option<i16> {
+        Some(bool_to_basic_value(left >= right))
     }
 
     fn perform_double_operation(&self, left: f64, right: f64) -> f64 {
@@ -291,7 +342,7 @@ impl BinaryOperationEvaluator for GreaterEqualThanEvaluator {
     }
 
     fn perform_string_operation(&self, left: &String, right: &String)
-                                -> Result<ExpressionEvalResult, &'static str> {
+                                -> Result<ExpressionEvalResult, GwError> {
         Ok(ExpressionEvalResult::IntegerResult(
             bool_to_basic_value(left.cmp(right).is_ge())))
     }
@@ -300,9 +351,9 @@ impl BinaryOperationEvaluator for GreaterEqualThanEvaluator {
 struct TimesEvaluator {
 }
 
-impl BinaryOperationEvaluator for TimesEvaluator {
-    fn perform_int_operation(&self, left : i16, right : i16) -> i16 {
-        left * right
+impl NumericOperationEvaluator for TimesEvaluator {
+    fn perform_int_operation(&self, left : i16, right : i16) -> Option<i16> {
+        left.checked_mul(right)
     }
 
     fn perform_double_operation(&self, left : f64, right : f64) -> f64 {
@@ -317,14 +368,14 @@ impl BinaryOperationEvaluator for TimesEvaluator {
 struct PowEvaluator {
 }
 
-impl BinaryOperationEvaluator for PowEvaluator {
-    fn perform_int_operation(&self, left : i16, right : i16) -> i16 {
-        let mut result = 1;
-
-        for _i in 1..right + 1  {
-            result *= left;
+impl NumericOperationEvaluator for PowEvaluator {
+    fn perform_int_operation(&self, left : i16, right : i16) -> Option<i16> {
+        // Negative exponents give fractions computed in single precision
+        if right < 0 {
+            None
+        } else {
+            left.checked_pow(right as u32)
         }
-        result
     }
 
     fn perform_double_operation(&self, left : f64, right : f64) -> f64 {
@@ -336,7 +387,7 @@ impl BinaryOperationEvaluator for PowEvaluator {
     }
 }
 
-fn get_double_value(value : &ExpressionEvalResult) -> Option<f64> {
+pub fn get_double_value(value : &ExpressionEvalResult) -> Option<f64> {
     match value {
         ExpressionEvalResult::DoubleResult(val) => Some(*val),
         ExpressionEvalResult::IntegerResult(int_value) => Some(f64::from(*int_value)),
@@ -348,9 +399,9 @@ fn get_double_value(value : &ExpressionEvalResult) -> Option<f64> {
 struct DivEvaluator {
 }
 
-impl BinaryOperationEvaluator for DivEvaluator {
-    fn perform_int_operation(&self, left : i16, right : i16) -> i16 {
-        left / right
+impl NumericOperationEvaluator for DivEvaluator {
+    fn perform_int_operation(&self, left : i16, right : i16) -> Option<i16> {
+        left.checked_div(right)
     }
 
     fn perform_single_operation(&self, left : f32, right : f32) -> f32 {
@@ -363,10 +414,73 @@ impl BinaryOperationEvaluator for DivEvaluator {
 
     fn evaluate(&self,
                 left_result : &ExpressionEvalResult,
-                right_result : &ExpressionEvalResult) -> Result<ExpressionEvalResult, &'static str> {
-        let left_double_value = get_double_value(left_result).unwrap();
-        let right_double_value = get_double_value(right_result).unwrap();
-        Ok(ExpressionEvalResult::DoubleResult(left_double_value / right_double_value))
+                right_result : &ExpressionEvalResult) -> Result<ExpressionEvalResult, GwError> {
+        match (get_double_value(left_result), get_double_value(right_result)) {
+            (Some(_), Some(0.0)) => Err(GwError::DivisionByZero),
+            // Only double operands produce a double quotient
+            (Some(left_double_value), Some(right_double_value))
+                if matches!(left_result, ExpressionEvalResult::DoubleResult(_))
+                || matches!(right_result, ExpressionEvalResult::DoubleResult(_)) =>
+                double_result(left_double_value / right_double_value),
+            (Some(left_double_value), Some(right_double_value)) =>
+                single_result(self.perform_single_operation(left_double_value as f32, right_double_value as f32)),
+            _ => Err(GwError::TypeMismatch)
+        }
+    }
+}
+
+/// Converts an operand to the 16-bit integer used by integer operators,
+/// rounding non-integer values
+pub fn to_integer_operand(value: &ExpressionEvalResult) -> Result<i16, GwError> {
+    match value {
+        ExpressionEvalResult::IntegerResult(int_value) => Ok(*int_value),
+        ExpressionEvalResult::StringResult(_) => Err(GwError::TypeMismatch),
+        _ => {
+            let rounded = get_double_value(value).unwrap_or(0.0).round();
+            if rounded < f64::from(i16::MIN) || rounded > f64::from(i16::MAX) {
+                Err(GwError::Overflow)
+            } else {
+                Ok(rounded as i16)
+            }
+        }
+    }
+}
+
+/// Evaluator for the integer division (`\`) and `MOD` operators.
+/// Operands are rounded to integers and the result is truncated
+struct IntegerDivisionEvaluator {
+    operation: fn(i16, i16) -> Option<i16>
+}
+
+impl BinaryOperationEvaluator for IntegerDivisionEvaluator {
+    fn evaluate(&self,
+                left_result : &ExpressionEvalResult,
+                right_result : &ExpressionEvalResult) -> Result<ExpressionEvalResult, GwError> {
+        let left = to_integer_operand(left_result)?;
+        let right = to_integer_operand(right_result)?;
+        if right == 0 {
+            Err(GwError::DivisionByZero)
+        } else if let Some(result) = (self.operation)(left, right) {
+            Ok(ExpressionEvalResult::IntegerResult(result))
+        } else {
+            Err(GwError::Overflow)
+        }
+    }
+}
+
+/// Evaluator for the logical operators (`AND`, `OR`, `XOR`, `EQV`, `IMP`)
+/// which work bitwise on the 16-bit integer value of both operands
+struct LogicalEvaluator {
+    operation: fn(i16, i16) -> i16
+}
+
+impl BinaryOperationEvaluator for LogicalEvaluator {
+    fn evaluate(&self,
+                left_result : &ExpressionEvalResult,
+                right_result : &ExpressionEvalResult) -> Result<ExpressionEvalResult, GwError> {
+        let left = to_integer_operand(left_result)?;
+        let right = to_integer_operand(right_result)?;
+        Ok(ExpressionEvalResult::IntegerResult((self.operation)(left, right)))
     }
 }
 
@@ -396,7 +510,13 @@ impl GwBinaryOperation {
             GwBinaryOperationKind::LessThan => Box::new(LessThanEvaluator {}),
             GwBinaryOperationKind::GreaterEqualThan => Box::new(GreaterEqualThanEvaluator {}),
             GwBinaryOperationKind::LessEqualThan => Box::new(LessEqualThanEvaluator {}),
-            _ => panic!("evaluator not implemented")
+            GwBinaryOperationKind::IntDiv => Box::new(IntegerDivisionEvaluator { operation: i16::checked_div }),
+            GwBinaryOperationKind::Mod => Box::new(IntegerDivisionEvaluator { operation: i16::checked_rem }),
+            GwBinaryOperationKind::And => Box::new(LogicalEvaluator { operation: |l, r| l & r }),
+            GwBinaryOperationKind::Or => Box::new(LogicalEvaluator { operation: |l, r| l | r }),
+            GwBinaryOperationKind::Xor => Box::new(LogicalEvaluator { operation: |l, r| l ^ r }),
+            GwBinaryOperationKind::Eqv => Box::new(LogicalEvaluator { operation: |l, r| !(l ^ r) }),
+            GwBinaryOperationKind::Implication => Box::new(LogicalEvaluator { operation: |l, r| !l | r }),
         };
 
         GwBinaryOperation {
@@ -420,7 +540,13 @@ impl GwBinaryOperation {
             GwBinaryOperationKind::LessThan => buffer.push_str(" < "),
             GwBinaryOperationKind::GreaterEqualThan => buffer.push_str(" >= "),
             GwBinaryOperationKind::LessEqualThan => buffer.push_str(" <= "),
-            _ => buffer.push_str(" ?? ")
+            GwBinaryOperationKind::IntDiv => buffer.push_str(" \\ "),
+            GwBinaryOperationKind::Mod => buffer.push_str(" MOD "),
+            GwBinaryOperationKind::And => buffer.push_str(" AND "),
+            GwBinaryOperationKind::Or => buffer.push_str(" OR "),
+            GwBinaryOperationKind::Xor => buffer.push_str(" XOR "),
+            GwBinaryOperationKind::Eqv => buffer.push_str(" EQV "),
+            GwBinaryOperationKind::Implication => buffer.push_str(" IMP "),
         }
     }
 }
@@ -429,16 +555,9 @@ impl GwBinaryOperation {
 impl GwExpression for GwBinaryOperation {
     fn eval (&self, context : &mut EvaluationContext) 
                       -> Result<ExpressionEvalResult, EvaluationError> {
-        match (self.left.eval(context), self.right.eval(context)) {
-            (Ok(left_result), Ok(right_result)) => {
-               match self.evaluator.evaluate(&left_result, &right_result) {
-                   Ok(result) => Ok(result),
-                   Err(err) => Err(err.to_string())
-               }
-            }
-            _ => Err("Error on binary operation".to_string())
-
-        }
+        let left_result = self.left.eval(context)?;
+        let right_result = self.right.eval(context)?;
+        self.evaluator.evaluate(&left_result, &right_result)
     }
     fn fill_structure_string(&self,   val : &mut String) {
         val.push_str("(");
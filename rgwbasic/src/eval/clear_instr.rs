@@ -0,0 +1,128 @@
+use super::{ GwInstruction,
+             GwExpression,
+             InstructionResult,
+             EvaluationContext,
+             LineExecutionArgument,
+             GwProgram };
+
+/// AST element for the `ERASE` statement which removes arrays
+/// so they can be declared again with `DIM`.
+/// For example:
+/// ```basic
+/// ERASE A, B$
+/// ```
+pub struct GwErase {
+    names: Vec<String>
+}
+
+impl GwErase {
+    pub fn new(names: Vec<String>) -> GwErase {
+        GwErase { names }
+    }
+}
+
+impl GwInstruction for GwErase {
+    fn eval(&self,
+            _line: i16,
+            _argument: LineExecutionArgument,
+            context: &mut EvaluationContext,
+            _program: &mut GwProgram) -> InstructionResult {
+        for name in &self.names {
+            if let Err(error) = context.erase_array(name) {
+                return InstructionResult::EvaluateToError(error);
+            }
+        }
+        InstructionResult::EvaluateNext
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        buffer.push_str("ERASE ");
+        buffer.push_str(&self.names.join(", "));
+    }
+}
+
+/// AST element for the `CLEAR` statement which resets variables,
+/// arrays and user defined functions keeping the program.
+/// The memory size arguments are evaluated but have no effect
+pub struct GwClear {
+    arguments: Vec<Option<Box<dyn GwExpression>>>
+}
+
+impl GwClear {
+    pub fn new(arguments: Vec<Option<Box<dyn GwExpression>>>) -> GwClear {
+        GwClear { arguments }
+    }
+}
+
+impl GwInstruction for GwClear {
+    fn eval(&self,
+            _line: i16,
+            _argument: LineExecutionArgument,
+            context: &mut EvaluationContext,
+            _program: &mut GwProgram) -> InstructionResult {
+        for argument in self.arguments.iter().flatten() {
+            if let Err(error) = argument.eval(context) {
+                return InstructionResult::EvaluateToError(error);
+            }
+        }
+        context.clear();
+        InstructionResult::EvaluateNext
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        buffer.push_str("CLEAR");
+        if !self.arguments.is_empty() {
+            buffer.push(' ');
+        }
+        let mut c = self.arguments.len();
+        for argument in &self.arguments {
+            if let Some(expr) = argument {
+                expr.fill_structure_string(buffer);
+            }
+            c -= 1;
+            if c != 0 {
+                buffer.push(',');
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod clear_tests {
+    use super::*;
+    use crate::eval::{ ExpressionEvalResult, GwError };
+    use crate::eval::eval_tests::{ empty_context, empty_program };
+
+    #[test]
+    fn it_erases_arrays() {
+        let mut program = empty_program();
+        let mut ctx = empty_context();
+        ctx.declare_array("A", vec![5]).expect("success");
+
+        let erase = GwErase::new(vec!["A".to_string()]);
+        erase.eval(10, LineExecutionArgument::Empty, &mut ctx, &mut program);
+        assert!(ctx.get_existing_array("A").is_none());
+        ctx.declare_array("A", vec![20]).expect("success");
+
+        ctx.erase_array("A").expect("success");
+        assert!(matches!(erase.eval(10, LineExecutionArgument::Empty, &mut ctx, &mut program),
+                         InstructionResult::EvaluateToError(GwError::IllegalFunctionCall)));
+    }
+
+    #[test]
+    fn it_clears_variables_and_arrays() {
+        let mut program = empty_program();
+        let mut ctx = empty_context();
+        ctx.set_variable("X", &ExpressionEvalResult::IntegerResult(1)).expect("success");
+        ctx.declare_array("A", vec![5]).expect("success");
+        ctx.push_return(20);
+        ctx.data_position = 3;
+
+        GwClear::new(vec![]).eval(10, LineExecutionArgument::Empty, &mut ctx, &mut program);
+        assert_eq!(None, ctx.lookup_variable("X"));
+        assert!(ctx.get_existing_array("A").is_none());
+        assert_eq!(None, ctx.pop_return());
+        assert_eq!(-1, ctx.data_position);
+    }
+}
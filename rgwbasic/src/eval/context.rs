@@ -3,9 +3,17 @@ use std::collections::HashMap;
 use std::convert::TryFrom;
 use crate::parser::parse_instruction_line_from_string;
 use crate::parser::ParserResult;
+use crate::extractor;
 use super::GwExpression;
+use super::GwError;
+use super::binary::{ to_integer_operand, single_result };
+use super::deffn_instr::GwUserFunction;
+use super::files::{ FieldVariable, FileField, FileMode, FileSystem, OpenFile };
+use super::random::RandomGenerator;
 
 const MAX_ITERATIONS_WITHOUT_REFRESH: u32 = 1030;
+/// Width of the print zones used by commas in `PRINT`
+pub const PRINT_ZONE_WIDTH: usize = 14;
 
 #[derive(Debug, Clone)]
 pub enum LineExecutionArgument {
@@ -33,7 +41,7 @@ pub enum InstructionResult {
     EvaluateLine(i16),
     EvaluateLineWithArg(i16, LineExecutionArgument),
     EvaluateEnd,
-    EvaluateToError(String),
+    EvaluateToError(GwError),
     RequestAsyncAction(AsyncAction)
 }
 
@@ -88,20 +96,43 @@ impl ExpressionEvalResult {
         }
     }
 
-    pub fn assume_string_value(&self) -> Result<&String, String> {
+    pub fn assume_string_value(&self) -> Result<&String, GwError> {
         match self {
             ExpressionEvalResult::StringResult(ref string) => Ok(string),
-            _ => Err(String::from("Invalid type"))
+            _ => Err(GwError::TypeMismatch)
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ExpressionType {
     String, Integer, Single, Double
 }
 
+impl ExpressionType {
+    /// Type declared by a `$`, `%`, `!` or `#` name suffix
+    pub fn from_suffix(suffix: char) -> Option<ExpressionType> {
+        match suffix {
+            '$' => Some(ExpressionType::String),
+            '%' => Some(ExpressionType::Integer),
+            '!' => Some(ExpressionType::Single),
+            '#' => Some(ExpressionType::Double),
+            _ => None
+        }
+    }
+
+    pub fn suffix(&self) -> char {
+        match self {
+            ExpressionType::String => '$',
+            ExpressionType::Integer => '%',
+            ExpressionType::Single => '!',
+            ExpressionType::Double => '#'
+        }
+    }
+}
+
 
-fn get_default_value_for_type(var_type : &ExpressionType) -> ExpressionEvalResult {
+pub fn get_default_value_for_type(var_type : &ExpressionType) -> ExpressionEvalResult {
     match var_type {
         ExpressionType::String => ExpressionEvalResult::StringResult(String::from("")),
         ExpressionType::Integer => ExpressionEvalResult::IntegerResult(0),
@@ -122,12 +153,12 @@ impl ExpressionEvalResult {
         }
     }
 
-    pub fn as_i16(&self) -> Result<i16, String> {
+    pub fn as_i16(&self) -> Result<i16, GwError> {
         match self {
             ExpressionEvalResult::IntegerResult(iresult) => Ok(*iresult as i16),
             ExpressionEvalResult::SingleResult(sresult) => Ok(*sresult as i16),
             ExpressionEvalResult::DoubleResult(dresult) => Ok(*dresult as i16),
-            _ => Err("Type mismatch".to_string())
+            _ => Err(GwError::TypeMismatch)
 
         }
     }
@@ -141,19 +172,33 @@ pub trait Console {
     fn clear_screen(&mut self);
     fn current_text_column(&self) -> usize;
     fn read_file_lines(&self, file_name: &str) -> Box<dyn Iterator<Item=String>>;
+    /// Moves to the 1-based column `position` as done by `TAB`,
+    /// starting a new line if the column was already passed
     fn adjust_to_position(&mut self, position: usize) {
         let num_spaces: usize;
         let cur_column = self.current_text_column();
         if cur_column <= position {
             num_spaces = position - cur_column;
         } else {
-            num_spaces = position;
+            num_spaces = position.saturating_sub(1);
             self.print_line("")
         }
-       for _ in 1..num_spaces {
-            self.print(" ");
+        self.print(&" ".repeat(num_spaces));
+    }
+    /// Moves to the start of the next print zone, as done by a comma
+    /// in `PRINT`. A new line is started when there is no room for
+    /// another zone
+    fn advance_to_next_zone(&mut self) {
+        let position = self.current_text_column() - 1;
+        let next_zone = (position / PRINT_ZONE_WIDTH + 1) * PRINT_ZONE_WIDTH;
+        if next_zone + PRINT_ZONE_WIDTH > self.text_width() {
+            self.print_line("");
+        } else {
+            self.print(&" ".repeat(next_zone - position));
         }
     }
+    /// Number of columns of a line, text wraps after the last one
+    fn text_width(&self) -> usize { 80 }
     fn flush(&self);
     fn exit_program(&self);
     fn clone(&self) -> Box<dyn Console>;
@@ -162,9 +207,60 @@ pub trait Console {
     fn log(&self, msg: &str) {
         println!("{}",msg)
     }
+    /// Storage for the files used by `OPEN`, `None` when the host
+    /// does not provide files
+    fn file_system(&mut self) -> Option<&mut dyn FileSystem> {
+        None
+    }
+    fn seconds_since_midnight(&self) -> f64 {
+        let since_epoch = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        since_epoch.as_secs_f64() % 86400.0
+    }
 }
 
 
+/// Returns the 0-based column after printing `text` from the 0-based
+/// column `position`, wrapping to the next line after `width` columns.
+/// Used by consoles to implement `current_text_column`
+pub fn advance_column_position(position: usize, text: &str, width: usize) -> usize {
+    text.chars().fold(position, |current, c| match c {
+        '\n' | '\r' => 0,
+        _ if current + 1 >= width => 0,
+        _ => current + 1
+    })
+}
+
+/// State of the error trap enabled with `ON ERROR GOTO`
+pub struct ErrorTrap {
+    /// Line of the error handling routine (`ON ERROR GOTO line`)
+    pub handler_line: Option<i16>,
+    /// Last trapped error, used by `ERR` and `ERL`
+    pub last_error: Option<GwError>,
+    /// Index of the real line that caused the error being handled
+    pub resume_real_line: Option<usize>,
+}
+
+impl ErrorTrap {
+    pub fn new() -> ErrorTrap {
+        ErrorTrap {
+            handler_line: None,
+            last_error: None,
+            resume_real_line: None
+        }
+    }
+
+    pub fn is_handling_error(&self) -> bool {
+        self.resume_real_line.is_some()
+    }
+}
+
+impl Default for ErrorTrap {
+    fn default() -> Self {
+        ErrorTrap::new()
+    }
+}
 
 pub struct EvaluationContext/*<'a>*/ {
     pub variables: HashMap<String, ExpressionEvalResult>,
@@ -177,7 +273,18 @@ pub struct EvaluationContext/*<'a>*/ {
     pub console: Box<dyn Console>,
     pub data_position: i32,
     pub subroutine_stack: Vec<i16>,
-    pub current_real_line: i32
+    pub current_real_line: i32,
+    pub error_trap: ErrorTrap,
+    pub user_functions: HashMap<String, Rc<GwUserFunction>>,
+    pub random: RandomGenerator,
+    pub option_base: usize,
+    /// Index of the first DATA item at or after each program line
+    pub data_line_positions: HashMap<i16, usize>,
+    /// Type of the names without suffix by their first letter, as
+    /// changed by `DEFINT`, `DEFSNG`, `DEFDBL` and `DEFSTR`
+    pub default_types: [ExpressionType; 26],
+    /// Files opened with `OPEN` by file number
+    pub open_files: HashMap<u8, OpenFile>
 }
 
 
@@ -196,6 +303,13 @@ impl EvaluationContext/*<'_>*/  {
             data_position: -1,
             subroutine_stack: vec![],
             current_real_line: -1,
+            error_trap: ErrorTrap::new(),
+            user_functions: HashMap::new(),
+            random: RandomGenerator::new(),
+            option_base: 0,
+            data_line_positions: HashMap::new(),
+            default_types: [ExpressionType::Single; 26],
+            open_files: HashMap::new(),
         }
     }
     pub fn with_program(_program: &mut GwProgram, console: Box<dyn Console>) -> EvaluationContext {
@@ -211,6 +325,13 @@ impl EvaluationContext/*<'_>*/  {
             data_position: -1,
             subroutine_stack: vec![],
             current_real_line: -1,
+            error_trap: ErrorTrap::new(),
+            user_functions: HashMap::new(),
+            random: RandomGenerator::new(),
+            option_base: 0,
+            data_line_positions: HashMap::new(),
+            default_types: [ExpressionType::Single; 26],
+            open_files: HashMap::new(),
         }
     }
 
@@ -222,31 +343,214 @@ impl EvaluationContext/*<'_>*/  {
         self.subroutine_stack.pop()
     }
 
+    /// Sends an error to the active `ON ERROR GOTO` handler.
+    /// Returns the real line of the handler or the error if it
+    /// cannot be trapped.
+    pub fn trap_error(&mut self, error: GwError, real_line: usize) -> Result<usize, GwError> {
+        if self.error_trap.is_handling_error() {
+            return Err(error);
+        }
+        match self.error_trap.handler_line {
+            Some(handler_line) => {
+                if let Some(handler_real_line) = self.get_real_line(handler_line) {
+                    self.error_trap.last_error = Some(error);
+                    self.error_trap.resume_real_line = Some(real_line);
+                    Ok(handler_real_line as usize)
+                } else {
+                    Err(GwError::UndefinedLineNumber)
+                }
+            }
+            None => Err(error)
+        }
+    }
+
     pub fn set_array_entry(&mut self,
                            name : &str,
                            indices : Vec<usize>,
-                           new_value : &ExpressionEvalResult) {
-        if let Some(mut_array) = self.array_variables.get_mut(name) {
-            mut_array.set_value(&indices, &new_value);
+                           new_value : &ExpressionEvalResult) -> Result<(), GwError> {
+        self.ensure_array_exists(name, indices.len())?;
+        let qualified_name = self.qualified_name(name);
+        if let Some(mut_array) = self.array_variables.get_mut(&qualified_name) {
+            mut_array.set_value(&indices, new_value)
         } else {
-            panic!("array not found");
+            Err(GwError::SubscriptOutOfRange)
         }
     }
 
-    pub fn declare_array(&mut self, name : &str, size : usize) {
-        
-        let array_type =
-              Self::get_type_from_name(name).or(Some(ExpressionType::Double));
-        let new_array = GwArray::new_one_dimension(size, array_type.unwrap());
-        self.array_variables.insert(String::from(name), new_array);
+    pub fn get_array_entry(&mut self,
+                           name : &str,
+                           indices : Vec<usize>) -> Result<ExpressionEvalResult, GwError> {
+        self.ensure_array_exists(name, indices.len())?;
+        if let Some(array) = self.array_variables.get(&self.qualified_name(name)) {
+            array.get_value(&indices)
+        } else {
+            Err(GwError::SubscriptOutOfRange)
+        }
+    }
+
+    /// Arrays used without `DIM` get an upper bound of 10
+    /// in each dimension
+    fn ensure_array_exists(&mut self, name : &str, dimension_count : usize) -> Result<(), GwError> {
+        if self.array_variables.contains_key(&self.qualified_name(name)) {
+            Ok(())
+        } else {
+            self.declare_array(name, vec![10; dimension_count])
+        }
+    }
+
+    pub fn declare_array(&mut self, name : &str, dimensions : Vec<usize>) -> Result<(), GwError> {
+        let qualified_name = self.qualified_name(name);
+        if self.array_variables.contains_key(&qualified_name) {
+            return Err(GwError::DuplicateDefinition);
+        }
+        let array_type = self.get_type_from_name(name);
+        let new_array = GwArray::new(dimensions, self.option_base, array_type)?;
+        self.array_variables.insert(qualified_name, new_array);
+        Ok(())
+    }
+
+    pub fn erase_array(&mut self, name : &str) -> Result<(), GwError> {
+        match self.array_variables.remove(&self.qualified_name(name)) {
+            Some(_) => Ok(()),
+            None => Err(GwError::IllegalFunctionCall)
+        }
+    }
+
+    /// Resets the state of the running program (as done by `CLEAR`)
+    pub fn clear(&mut self) {
+        self.variables.clear();
+        self.array_variables.clear();
+        self.user_functions.clear();
+        self.subroutine_stack.clear();
+        self.data_position = -1;
+        self.option_base = 0;
+        self.default_types = [ExpressionType::Single; 26];
+        self.close_all_files();
+    }
+
+    /// Opens a file of the console's file system with the given number
+    pub fn open_file(&mut self,
+                     number: u8,
+                     name: &str,
+                     mode: FileMode,
+                     record_length: usize) -> Result<(), GwError> {
+        let already_open = self.open_files.values().any(|file| {
+            file.name == name && (file.mode != FileMode::Input || mode != FileMode::Input)
+        });
+        if already_open || self.open_files.contains_key(&number) {
+            return Err(GwError::FileAlreadyOpen);
+        }
+        let file_system = self.console.file_system().ok_or(GwError::DeviceUnavailable)?;
+        let file = OpenFile::open(name, mode, record_length, file_system)?;
+        self.open_files.insert(number, file);
+        Ok(())
+    }
+
+    pub fn close_file(&mut self, number: u8) {
+        self.open_files.remove(&number);
+    }
+
+    pub fn close_all_files(&mut self) {
+        self.open_files.clear();
+    }
+
+    pub fn get_open_file(&mut self, number: u8) -> Result<&mut OpenFile, GwError> {
+        self.open_files.get_mut(&number).ok_or(GwError::BadFileNumber)
+    }
+
+    /// Writes text to a file opened for output or append
+    pub fn write_to_file(&mut self, number: u8, text: &str) -> Result<(), GwError> {
+        let file = self.open_files.get_mut(&number).ok_or(GwError::BadFileNumber)?;
+        let file_system = self.console.file_system().ok_or(GwError::DeviceUnavailable)?;
+        file.write(text, file_system)
+    }
+
+    /// Implements `FIELD`, binds a variable to a part of the record
+    /// buffer of a random file. A variable is bound to one part at
+    /// a time and gets the text of that part
+    pub fn bind_field_variable(&mut self,
+                               number: u8,
+                               offset: usize,
+                               width: usize,
+                               variable: FieldVariable) -> Result<(), GwError> {
+        let file = self.get_open_file(number)?;
+        if file.mode != FileMode::Random {
+            return Err(GwError::BadFileMode);
+        }
+        if offset + width > file.record_length() {
+            return Err(GwError::FieldOverflow);
+        }
+        let text = file.field_text(offset, width);
+        for open_file in self.open_files.values_mut() {
+            open_file.fields.retain(|field| field.variable != variable);
+        }
+        self.assign_field_variable(&variable, text)?;
+        self.get_open_file(number)?.fields.push(FileField { variable, offset, width });
+        Ok(())
+    }
+
+    /// File number, offset and width of the part of a record buffer
+    /// bound to a field variable
+    pub fn find_field(&self, variable: &FieldVariable) -> Option<(u8, usize, usize)> {
+        self.open_files.iter().find_map(|(number, file)| {
+            file.fields
+                .iter()
+                .find(|field| field.variable == *variable)
+                .map(|field| (*number, field.offset, field.width))
+        })
+    }
+
+    fn assign_field_variable(&mut self, variable: &FieldVariable, text: String) -> Result<(), GwError> {
+        let value = ExpressionEvalResult::StringResult(text);
+        match variable {
+            FieldVariable::Simple(name) => self.set_variable(name, &value),
+            FieldVariable::ArrayEntry(name, indices) => self.set_array_entry(name, indices.clone(), &value)
+        }
+    }
+
+    /// Implements `GET`, the field variables of the file get the
+    /// text of the record read
+    pub fn read_file_record(&mut self, number: u8, record_number: Option<usize>) -> Result<(), GwError> {
+        let file = self.get_open_file(number)?;
+        file.read_record(record_number)?;
+        let field_values: Vec<(FieldVariable, String)> = file.fields
+            .iter()
+            .map(|field| (field.variable.clone(), file.field_text(field.offset, field.width)))
+            .collect();
+        for (variable, text) in field_values {
+            self.assign_field_variable(&variable, text)?;
+        }
+        Ok(())
+    }
+
+    /// Implements `PUT`
+    pub fn write_file_record(&mut self, number: u8, record_number: Option<usize>) -> Result<(), GwError> {
+        let file = self.open_files.get_mut(&number).ok_or(GwError::BadFileNumber)?;
+        let file_system = self.console.file_system().ok_or(GwError::DeviceUnavailable)?;
+        file.write_record(record_number, file_system)
+    }
+
+    /// Implements `OPTION BASE`, which must be used before any array
+    /// is created
+    pub fn set_option_base(&mut self, base : usize) -> Result<(), GwError> {
+        if !self.array_variables.is_empty() {
+            Err(GwError::DuplicateDefinition)
+        } else {
+            self.option_base = base;
+            Ok(())
+        }
     }
 
     pub fn get_existing_array(&self, name : &str) -> Option<&GwArray> {
-        self.array_variables.get(name)
+        self.array_variables.get(&self.qualified_name(name))
+    }
+
+    pub fn get_existing_function(&self, name : &str) -> Option<Rc<GwUserFunction>> {
+        self.user_functions.get(name).cloned()
     }
 
-    pub fn get_existing_function(&self, _name : &String, _size : usize) -> Option<&GwArray> {
-        panic!("not implemented");
+    pub fn define_function(&mut self, function: Rc<GwUserFunction>) {
+        self.user_functions.insert(function.get_name().to_string(), function);
     }
 
 
@@ -259,43 +563,67 @@ impl EvaluationContext/*<'_>*/  {
     }
 
     pub fn lookup_variable(&self, name : &str) -> Option<&ExpressionEvalResult> {
-        self.variables.get(name)
+        self.variables.get(&self.qualified_name(name))
     }
 
-    pub fn set_variable(&mut self, name : &str, value : &ExpressionEvalResult) -> Result<(), & 'static str> {
-        if let Some(entry) = self.variables.get_mut(name) {
-            if matches_type(entry, &value) {
-                *entry = value.clone();
-                Ok(())
-            } else if let Some(new_value) = coerce_value_type(&value, entry) {
-                *entry = new_value;
-                Ok(())
-            } else {
-                Err("Type mismatch")
+    /// Assigns a variable converting the value to the type of its name
+    pub fn set_variable(&mut self, name : &str, value : &ExpressionEvalResult) -> Result<(), GwError> {
+        let new_value = convert_to_type(value, self.get_type_from_name(name))?;
+        self.variables.insert(self.qualified_name(name), new_value);
+        Ok(())
+    }
+
+    pub fn get_variable_type(&self, name : &str) -> ExpressionType {
+        self.get_type_from_name(name)
+    }
+
+    /// The type of a variable is given by its suffix or, without one,
+    /// by the default type of its first letter
+    pub fn get_type_from_name(&self, name: &str) -> ExpressionType {
+        if let Some(suffix_type) = name.chars().last().and_then(ExpressionType::from_suffix) {
+            return suffix_type;
+        }
+        match name.chars().next() {
+            Some(first) if first.is_ascii_alphabetic() =>
+                self.default_types[(first.to_ascii_uppercase() as u8 - b'A') as usize],
+            _ => ExpressionType::Single
+        }
+    }
+
+    /// Name with its type suffix, so `A` and `A!` are the same variable
+    /// when the default type of `A` is single precision
+    pub fn qualified_name(&self, name: &str) -> String {
+        match name.chars().last() {
+            Some(last) if ExpressionType::from_suffix(last).is_some() => name.to_string(),
+            _ => {
+                let mut result = name.to_string();
+                result.push(self.get_type_from_name(name).suffix());
+                result
             }
-        } else {
-            self.variables.insert(name.to_string(), value.clone());
-            Ok(())
         }
     }
 
-    pub fn get_variable_type(&self, name : &String)
-                             -> Option<ExpressionType> {
-        match self.lookup_variable(name) {
-            Some(ExpressionEvalResult::StringResult(_)) => Some(ExpressionType::String),
-            Some(ExpressionEvalResult::IntegerResult(_)) => Some(ExpressionType::Integer),
-            Some(ExpressionEvalResult::SingleResult(_)) => Some(ExpressionType::Single),
-            Some(ExpressionEvalResult::DoubleResult(_)) => Some(ExpressionType::Double),
-            _ => Self::get_type_from_name(name)
+    /// Sets the type of the names without suffix starting with `letter`
+    pub fn set_default_type(&mut self, letter: char, var_type: ExpressionType) {
+        if letter.is_ascii_alphabetic() {
+            self.default_types[(letter.to_ascii_uppercase() as u8 - b'A') as usize] = var_type;
         }
     }
 
-    fn get_type_from_name(name: &str)
-                          -> Option<ExpressionType> {
-        match name.chars().last() {
-            Some('$') => Some(ExpressionType::String),
-            _ => None
+    /// Moves the DATA pointer to the start of the data or to the
+    /// first item at or after `line`
+    pub fn restore_data(&mut self, line: Option<i16>) -> Result<(), GwError> {
+        match line {
+            Some(line_number) => {
+                let position = self.data_line_positions.get(&line_number)
+                    .ok_or(GwError::UndefinedLineNumber)?;
+                self.data_position = *position as i32 - 1;
+            }
+            None => {
+                self.data_position = -1;
+            }
         }
+        Ok(())
     }
 
     pub fn get_next_data_item<'a>(&mut self, program: &'a GwProgram) -> Option<&'a String> {
@@ -308,31 +636,27 @@ impl EvaluationContext/*<'_>*/  {
         }
     }
 
-    pub fn set_variable_type(&mut self, name: &str, var_type: &ExpressionType) {
-        match var_type {
-            ExpressionType::String => {
-                self.variables.insert(
-                    name.to_string(),
-                    ExpressionEvalResult::StringResult("".to_string()));
-            }
-            ExpressionType::Integer => {
-                self.variables.insert(
-                    name.to_string(),
-                    ExpressionEvalResult::IntegerResult(0));
-            }
-            ExpressionType::Single => {
-                self.variables.insert(
-                    name.to_string(),
-                    ExpressionEvalResult::SingleResult(0 as f32));
-            }
-            ExpressionType::Double => {
-                self.variables.insert(
-                    name.to_string(),
-                    ExpressionEvalResult::DoubleResult(0 as f64));
+}
+
+/// Converts a value to the given type as done by assignments, numbers
+/// are rounded to integers and strings cannot be converted to numbers
+pub fn convert_to_type(value: &ExpressionEvalResult, var_type: ExpressionType)
+                       -> Result<ExpressionEvalResult, GwError> {
+    match (var_type, value) {
+        (ExpressionType::Integer, ExpressionEvalResult::SingleResult(_) |
+                                  ExpressionEvalResult::DoubleResult(_)) =>
+            Ok(ExpressionEvalResult::IntegerResult(to_integer_operand(value)?)),
+        (ExpressionType::Single, ExpressionEvalResult::DoubleResult(double_value)) =>
+            single_result(*double_value as f32),
+        _ => {
+            let default_value = get_default_value_for_type(&var_type);
+            if matches_type(&default_value, value) {
+                Ok(value.clone())
+            } else {
+                coerce_value_type(value, &default_value).ok_or(GwError::TypeMismatch)
             }
         }
     }
-
 }
 
 fn matches_type(entry: &ExpressionEvalResult, value: &ExpressionEvalResult) -> bool {
@@ -375,57 +699,71 @@ fn coerce_value_type(from: &ExpressionEvalResult, to: &ExpressionEvalResult)
 
 
 pub fn evaluate_to_usize(expr: &Box<dyn GwExpression>,
-                         context: &mut EvaluationContext) -> Result<usize, String> {
+                         context: &mut EvaluationContext) -> Result<usize, GwError> {
     match expr.eval(context) {
         Ok(ExpressionEvalResult::IntegerResult(ival)) if ival >= 0 => Ok(ival as usize),
         Ok(ExpressionEvalResult::SingleResult(sval)) if sval >= 0.0 => Ok(sval as usize),
         Ok(ExpressionEvalResult::DoubleResult(dval)) if dval >= 0.0 => Ok(dval as usize),
-        Err(eval_error) => Err(eval_error),
-        _ => Err("Type mismatch".to_string())
+        Ok(ExpressionEvalResult::StringResult(_)) => Err(GwError::TypeMismatch),
+        Ok(_) => Err(GwError::IllegalFunctionCall),
+        Err(eval_error) => Err(eval_error)
     }
 }
 
 
+/// Array created with `DIM` or automatically on first use.
+/// Values are stored in row-major order
 pub struct GwArray {
     values : Vec<ExpressionEvalResult>,
 //    element_type : GwVariableType,
-    dimensions: Vec<usize>
+    /// Upper bound of each dimension
+    dimensions: Vec<usize>,
+    /// Lower bound of every dimension, set with `OPTION BASE`
+    base: usize
 }
 
 impl GwArray {
-    fn new_one_dimension(size : usize, array_type : ExpressionType)
-                         -> GwArray{
-        let mut values = Vec::with_capacity(usize::from(size));
-        for _i in 0..size{
-            values.push( get_default_value_for_type(&array_type));
-        }
-        let dimensions = vec![size];
-        GwArray {
-            values,
-//            element_type: array_type,
-            dimensions
+    fn new(dimensions: Vec<usize>, base: usize, array_type : ExpressionType)
+           -> Result<GwArray, GwError> {
+        let mut size: usize = 1;
+        for upper_bound in &dimensions {
+            if *upper_bound < base {
+                return Err(GwError::SubscriptOutOfRange);
+            }
+            size = size.checked_mul(upper_bound - base + 1).ok_or(GwError::OutOfMemory)?;
         }
+        Ok(GwArray {
+            values: vec![get_default_value_for_type(&array_type); size],
+//            element_type: array_type,
+            dimensions,
+            base
+        })
     }
 
-    pub fn get_value(&self, index_array : Vec<usize>) -> ExpressionEvalResult {
+    fn flat_index(&self, index_array : &[usize]) -> Result<usize, GwError> {
+        if index_array.len() != self.dimensions.len() {
+            return Err(GwError::SubscriptOutOfRange);
+        }
         let mut index : usize = 0;
-        for i in 1..self.dimensions.len() {
-            index = (index_array[i] as usize) * self.dimensions[i];
+        for (subscript, upper_bound) in index_array.iter().zip(self.dimensions.iter()) {
+            if *subscript < self.base || *subscript > *upper_bound {
+                return Err(GwError::SubscriptOutOfRange);
+            }
+            index = index * (upper_bound - self.base + 1) + (subscript - self.base);
         }
-        let final_index = index_array[index_array.len() - 1];
-        index = index + ((final_index - 1) as usize);
-        self.values[usize::from(index)].clone()
+        Ok(index)
     }
 
-    pub fn set_value(&mut self, index_array : &Vec<usize>, new_value : &ExpressionEvalResult) {
-        let mut index : usize = 0;
-        for i in 1..self.dimensions.len() {
-            index = (index_array[i] as usize) * self.dimensions[i];
-        }
+    pub fn get_value(&self, index_array : &[usize]) -> Result<ExpressionEvalResult, GwError> {
+        let index = self.flat_index(index_array)?;
+        Ok(self.values[index].clone())
+    }
 
-        let final_index = index_array[index_array.len() - 1];
-        index = index + ((final_index - 1) as usize);
-        self.values[usize::from(index)] = new_value.clone();
+    pub fn set_value(&mut self, index_array : &[usize], new_value : &ExpressionEvalResult)
+                     -> Result<(), GwError> {
+        let index = self.flat_index(index_array)?;
+        self.values[index] = new_value.clone();
+        Ok(())
     }
 }
 
@@ -434,6 +772,36 @@ pub struct StepExecutionInfo/*<'a>*/ {
 //    pub execution_context: EvaluationContext/*<'a>*/
 }
 
+/// Range of program lines written as `10-50`, `-30`, `100-` or `10`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineRange {
+    pub first: Option<i16>,
+    pub last: Option<i16>
+}
+
+impl LineRange {
+    pub fn single(line_number: i16) -> LineRange {
+        LineRange { first: Some(line_number), last: Some(line_number) }
+    }
+
+    pub fn contains(&self, line_number: i16) -> bool {
+        !matches!(self.first, Some(first) if first > line_number)
+            && !matches!(self.last, Some(last) if line_number > last)
+    }
+
+    pub fn fill_structure_string(&self, buffer: &mut String) {
+        if let Some(first) = self.first {
+            buffer.push_str(&first.to_string());
+        }
+        if self.first != self.last {
+            buffer.push('-');
+            if let Some(last) = self.last {
+                buffer.push_str(&last.to_string());
+            }
+        }
+    }
+}
+
 pub struct GwProgram {
     pub lines : Vec<ProgramLine>,
     pub real_lines: Vec<Rc<dyn GwInstruction>>,
@@ -449,40 +817,76 @@ impl GwProgram {
         }
     }
 
+    /// Adds the given source lines to the program, stopping at the first
+    /// line that cannot be parsed
     pub fn load_from(&mut self,
-                     //file_name: &str,
                      console: &mut Box<dyn Console>,
                      file_lines: Box<dyn Iterator<Item = String>>)
-                -> Result<(), & 'static str> {
-        // let f = File::open(file_name)?;
-        // let reader = BufReader::new(f);
-        // let mut line_number = 1;
-        // for line in reader.lines() {
-        let mut line_number = 1;
-        //for uline in console.read_file_lines(file_name) {
+                -> Result<(), GwError> {
         for uline in file_lines {
-            //console.print_line(format!(">> {}", uline).as_str());
             match parse_instruction_line_from_string(uline) {
-                ParserResult::Success(parsed_line) => {
-                    //console.log("adding line");
-                    self.add_line(parsed_line);},
-                ParserResult::Error(_) => {/*return Err(format!("Line {} Error: {}", line_number, error)); */
-
-                // console.log("aaa");
-                return Err("Parsing error");},
-                ParserResult::Nothing=> {console.log("nothing");} //println!("Nothing")}
-               // _ => { console.log("last"); }
+                ParserResult::Success(parsed_line) => self.add_line(parsed_line),
+                ParserResult::Error(error) => return Err(error.error),
+                ParserResult::Nothing => console.log("nothing")
             }
-            line_number = line_number + 1;
         }
         Ok(())
     }
 
+    /// Adds the lines of a program file saved as text, tokenized or
+    /// protected, as read by `LOAD`
+    pub fn load_file_contents(&mut self,
+                              console: &mut Box<dyn Console>,
+                              contents: &[u8]) -> Result<(), GwError> {
+        let lines = extractor::program_file_lines(contents)?;
+        self.load_from(console, Box::new(lines.into_iter()))
+    }
+
+    /// Gets the source line number of the given index in `real_lines`
+    pub fn get_source_line(&self, real_line: usize) -> Option<i16> {
+        let mut first_index = 0;
+        for program_line in self.lines.iter() {
+            let count = 1 + program_line.rest_instructions.as_ref().map_or(0, |rest| rest.len());
+            if real_line < first_index + count {
+                return Some(program_line.line);
+            }
+            first_index += count;
+        }
+        None
+    }
+
+    /// Process an error produced by the instruction at `real_line`.
+    /// Returns the real line to continue the execution if the error
+    /// was trapped by an `ON ERROR GOTO` handler.
+    fn handle_runtime_error(&self,
+                            error: GwError,
+                            real_line: usize,
+                            context: &mut EvaluationContext) -> Option<usize> {
+        let error_with_line = match self.get_source_line(real_line) {
+            Some(source_line) if error.line().is_none() => error.at_line(source_line),
+            _ => error
+        };
+        match context.trap_error(error_with_line, real_line) {
+            Ok(handler_real_line) => Some(handler_real_line),
+            Err(error_to_report) => {
+                context.console.print_line(error_to_report.to_string().as_str());
+                None
+            }
+        }
+    }
+
+    /// Lines of the program as written by `LIST` and `SAVE`
+    pub fn listing(&self) -> Vec<String> {
+        self.lines.iter().map(|element| {
+            let mut line_text = String::new();
+            element.fill_structure_string(&mut line_text);
+            line_text
+        }).collect()
+    }
+
     pub fn list(&self, console: &mut Box<dyn Console>) {
-        for element in self.lines.iter() {
-            let mut string_to_print = String::new();
-            element.fill_structure_string(&mut string_to_print);
-            console.print_line(string_to_print.as_str());
+        for line_text in self.listing() {
+            console.print_line(line_text.as_str());
         }
     }
 
@@ -490,25 +894,29 @@ impl GwProgram {
         let real_lines = &mut self.real_lines;// &mut vec![];
         real_lines.clear();
         let mut global_data = vec![];
+        let mut data_line_positions = HashMap::new();
         let mut table = HashMap::new();
         let mut i = 0;
 
         for e in self.lines.iter() {
             table.insert(e.get_line(), i);
+            data_line_positions.insert(e.get_line(), global_data.len());
             real_lines.push(e.instruction.clone());
             if let Some(data) = e.instruction.get_data() {
-                for data_item in data {
-                    global_data.push(data_item);
-                }
+                global_data.extend(data.iter().cloned());
             }
             i += 1;
             if let Some(ref rest) = e.rest_instructions {
                 for nested in rest {
                     real_lines.push(nested.clone());
+                    if let Some(data) = nested.get_data() {
+                        global_data.extend(data.iter().cloned());
+                    }
                     i += 1;
                 }
             }
         }
+        self.data = global_data;
 
         let new_console = (*console).clone();
         let  context = EvaluationContext {
@@ -519,10 +927,16 @@ impl GwProgram {
             pair_instruction_table: HashMap::new(),
             //real_lines: Some(real_lines.to_vec()),
             console: new_console,
-            //data: global_data,
             data_position: -1,
             subroutine_stack: vec![],
-            current_real_line: -1
+            current_real_line: -1,
+            error_trap: ErrorTrap::new(),
+            user_functions: HashMap::new(),
+            random: RandomGenerator::new(),
+            option_base: 0,
+            data_line_positions,
+            default_types: [ExpressionType::Single; 26],
+            open_files: HashMap::new()
         };
 //        self.real_lines = *real_lines;
         return context;
@@ -615,6 +1029,48 @@ impl GwProgram {
         self.lines.push(new_line);
     }
 
+    /// Removes all the lines of the program, as done by `NEW`
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.real_lines.clear();
+        self.data.clear();
+    }
+
+    /// Removes the lines in the given range, as done by `DELETE`.
+    /// It is an error if there are no lines in the range
+    pub fn delete_lines(&mut self, range: LineRange) -> Result<(), GwError> {
+        let line_count = self.lines.len();
+        self.lines.retain(|line| !range.contains(line.get_line()));
+        if self.lines.len() == line_count {
+            Err(GwError::IllegalFunctionCall)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Removes a line of the program, as done when typing its number alone
+    pub fn delete_line(&mut self, line_number: i16) -> Result<(), GwError> {
+        self.delete_lines(LineRange::single(line_number))
+            .map_err(|_| GwError::UndefinedLineNumber)
+    }
+
+    /// Processes a line typed in direct mode that starts with a line
+    /// number. The line is added or replaced, or deleted when it only
+    /// has its number
+    pub fn enter_line(&mut self, line_text: String) -> Result<(), GwError> {
+        if let Ok(line_number) = line_text.trim().parse::<i16>() {
+            return self.delete_line(line_number);
+        }
+        match parse_instruction_line_from_string(line_text) {
+            ParserResult::Success(parsed_line) => {
+                self.add_line(parsed_line);
+                Ok(())
+            }
+            ParserResult::Error(error) => Err(error.error),
+            ParserResult::Nothing => Ok(())
+        }
+    }
+
     pub fn eval_fragment_async(&mut self,
                                line: usize,
                                line_execution_arg: LineExecutionArgument,
@@ -659,12 +1115,14 @@ impl GwProgram {
                     return EvalFragmentAsyncResult::EvaluationEnd;
                     
                 },
-                InstructionResult::EvaluateToError(error_message) => {                    
-                    context.console.print("RUNTIME ERROR: ");
-                    context.console.print_line(error_message.as_str());
-                    context.console.flush();
-                    //break;
-                    return EvalFragmentAsyncResult::EvaluationEnd;
+                InstructionResult::EvaluateToError(error) => {
+                    if let Some(handler_index) = self.handle_runtime_error(error, current_index, context) {
+                        current_index = handler_index;
+                    } else {
+                        context.console.flush();
+                        //break;
+                        return EvalFragmentAsyncResult::EvaluationEnd;
+                    }
                 },
                 InstructionResult::RequestAsyncAction(_) => {
                     //panic!("Attempting to execute async operation in sync evaluation");
@@ -711,10 +1169,12 @@ impl GwProgram {
                 InstructionResult::EvaluateEnd => {
                     break;
                 },
-                InstructionResult::EvaluateToError(error_message) => {
-                    context.console.print("RUNTIME ERROR: ");
-                    context.console.print_line(error_message.as_str());
-                    break;
+                InstructionResult::EvaluateToError(error) => {
+                    if let Some(handler_index) = self.handle_runtime_error(error, current_index, context) {
+                        current_index = handler_index;
+                    } else {
+                        break;
+                    }
                 },
                 InstructionResult::RequestAsyncAction(_) => {
                     panic!("Attempting to execute async operation in sync evaluation");
@@ -733,26 +1193,26 @@ mod context_tests {
     #[test]
     fn it_declares_valid_array() -> Result<(), & 'static str> {
         let mut ctx = empty_context();
-        ctx.declare_array("my_array", 3);
+        ctx.declare_array("my_array", vec![3]).expect("success");
 
         ctx.set_array_entry(
             "my_array",
             vec![1],
-            &ExpressionEvalResult::IntegerResult(10));
+            &ExpressionEvalResult::IntegerResult(10)).expect("success");
         ctx.set_array_entry(
             "my_array",
             vec![2],
-            &ExpressionEvalResult::IntegerResult(20));
+            &ExpressionEvalResult::IntegerResult(20)).expect("success");
         ctx.set_array_entry(
             "my_array",
             vec![3],
-            &ExpressionEvalResult::IntegerResult(30));
+            &ExpressionEvalResult::IntegerResult(30)).expect("success");
 
         if let Some(array) = ctx.get_existing_array("my_array") {
             let existing_values =
-                (array.get_value(vec![1]),
-                 array.get_value(vec![2]),
-                 array.get_value(vec![3]));
+                (array.get_value(&[1]).unwrap(),
+                 array.get_value(&[2]).unwrap(),
+                 array.get_value(&[3]).unwrap());
             match existing_values {
                 (ExpressionEvalResult::IntegerResult(10),
                  ExpressionEvalResult::IntegerResult(20),
@@ -764,4 +1224,55 @@ mod context_tests {
             Err("array not found")
         }
     }
+
+    #[test]
+    fn it_types_variables_by_suffix_and_default_type() -> Result<(), GwError> {
+        let mut ctx = empty_context();
+        ctx.set_variable("A%", &ExpressionEvalResult::DoubleResult(2.6))?;
+        ctx.set_variable("A!", &ExpressionEvalResult::IntegerResult(1))?;
+        ctx.set_variable("A#", &ExpressionEvalResult::SingleResult(1.5))?;
+        ctx.set_variable("A$", &ExpressionEvalResult::StringResult("X".to_string()))?;
+        assert_eq!(Some(&ExpressionEvalResult::IntegerResult(3)), ctx.lookup_variable("A%"));
+        assert_eq!(Some(&ExpressionEvalResult::SingleResult(1.0)), ctx.lookup_variable("A"));
+        assert_eq!(Some(&ExpressionEvalResult::DoubleResult(1.5)), ctx.lookup_variable("A#"));
+        assert_eq!(Err(GwError::TypeMismatch),
+                   ctx.set_variable("A%", &ExpressionEvalResult::StringResult("X".to_string())));
+        assert_eq!(Err(GwError::Overflow),
+                   ctx.set_variable("A%", &ExpressionEvalResult::DoubleResult(40000.0)));
+
+        ctx.set_default_type('a', ExpressionType::Integer);
+        assert_eq!(ExpressionType::Integer, ctx.get_variable_type("A"));
+        assert_eq!(Some(&ExpressionEvalResult::IntegerResult(3)), ctx.lookup_variable("A"));
+        assert_eq!(ExpressionType::Single, ctx.get_variable_type("B"));
+
+        ctx.clear();
+        assert_eq!(ExpressionType::Single, ctx.get_variable_type("A"));
+        Ok(())
+    }
+
+    #[test]
+    fn it_edits_program_lines() -> Result<(), GwError> {
+        let mut program = GwProgram::new();
+        for line_number in (10..=70).step_by(10) {
+            program.enter_line(format!("{} PRINT {}", line_number, line_number))?;
+        }
+        let line_numbers = |program: &GwProgram| -> Vec<i16> {
+            program.lines.iter().map(|line| line.get_line()).collect()
+        };
+
+        program.enter_line(String::from("20 "))?;
+        assert_eq!(Err(GwError::UndefinedLineNumber), program.enter_line(String::from("25")));
+        assert_eq!(Err(GwError::SyntaxError.at_line(15)), program.enter_line(String::from("15 )")));
+        program.delete_lines(LineRange { first: Some(35), last: Some(50) })?;
+        assert_eq!(vec![10, 30, 60, 70], line_numbers(&program));
+
+        program.delete_lines(LineRange { first: None, last: Some(10) })?;
+        program.delete_lines(LineRange { first: Some(65), last: None })?;
+        assert_eq!(vec![30, 60], line_numbers(&program));
+        assert_eq!(Err(GwError::IllegalFunctionCall), program.delete_lines(LineRange::single(40)));
+
+        program.clear();
+        assert!(program.lines.is_empty());
+        Ok(())
+    }
 }
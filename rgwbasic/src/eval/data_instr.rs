@@ -1,9 +1,9 @@
-use super::{ ExpressionType,
-             ExpressionEvalResult,
-             EvaluationContext,
+use super::{ EvaluationContext,
              GwInstruction, GwAssignableExpression, GwProgram,
              LineExecutionArgument,
-             InstructionResult };
+             InstructionResult,
+             GwError };
+use super::file_instr::convert_input_item;
 
 /// AST element for DATA declaration
 /// Example:
@@ -49,43 +49,34 @@ impl GwInstruction for GwData {
 /// AST element for reading DATA elements
 /// Example:
 /// ```basic
-///  READ X
+///  READ X, Y$
 /// ```
 pub struct GwRead {
-    variable_expr: Box<dyn GwAssignableExpression>
+    variables: Vec<Box<dyn GwAssignableExpression>>
 }
 
 impl GwRead {
-    pub fn new(variable_expr: Box<dyn GwAssignableExpression>) -> GwRead {
+    pub fn new(variables: Vec<Box<dyn GwAssignableExpression>>) -> GwRead {
         GwRead {
-            variable_expr
+            variables
         }
     }
 
-    fn preprocess_numeric_data_item(&self, next_data: &String) -> Option<f64> {
-        let without_suffix = next_data.trim_end_matches(|c| c == '#' || c == '$' || c == '%' || c == ' ');
-        let without_prefix = without_suffix.trim_start();
-        match without_prefix.parse::<f64>() {
-            Ok(v) => Some(v),
-            _ => None
-        }
+    fn read_item(variable_expr: &dyn GwAssignableExpression,
+                 context: &mut EvaluationContext,
+                 program: &GwProgram) -> Result<(), GwError> {
+        let var_type = variable_expr.get_type(context);
+        let next_data = context.get_next_data_item(program).ok_or(GwError::OutOfData)?;
+        let value = convert_input_item(unquote_data_item(next_data), var_type)?;
+        variable_expr.assign_value(value, context)
     }
-    
-    fn process_numeric_data_item_read<F>(&self,
-                                         f: F,
-                                         value_opt: Option<f64>,
-                                         context: &mut EvaluationContext)
-        -> InstructionResult
-    where F: FnOnce(f64) -> ExpressionEvalResult {
-        if let Some(value) = value_opt {
-            check_result![
-                self.variable_expr.assign_value(
-                    f(value),
-                    context)];
-            InstructionResult::EvaluateNext
-        } else {
-            InstructionResult::EvaluateToError("Type mismatch".into())
-        }
+}
+
+/// Quoted DATA items may contain commas, colons and leading spaces
+fn unquote_data_item(data_item: &str) -> String {
+    match data_item.strip_prefix('"') {
+        Some(rest) => rest.strip_suffix('"').unwrap_or(rest).to_string(),
+        None => data_item.to_string()
     }
 }
 
@@ -95,48 +86,54 @@ impl GwInstruction for GwRead {
              _argument: LineExecutionArgument,
              context : &mut EvaluationContext,
              program: &mut GwProgram) -> InstructionResult {
-        let var_type = self.variable_expr.get_type(context);
-        if let Some(next_data) = context.get_next_data_item(program) {
-            match var_type {
-                ExpressionType::String => {
-                    let clonned_string = next_data.clone();
-                    check_result![
-                        self.variable_expr.assign_value(
-                            ExpressionEvalResult::StringResult(clonned_string),
-                            context)];
-                    InstructionResult::EvaluateNext
-                },
-                ExpressionType::Integer => {
-                    let value_opt = self.preprocess_numeric_data_item(next_data);
-                    self.process_numeric_data_item_read(
-                        |v| { ExpressionEvalResult::IntegerResult(v as i16) },
-                        value_opt,
-                        context)
-                }
-                ExpressionType::Single => {
-                    let value_opt = self.preprocess_numeric_data_item(next_data);
-                    self.process_numeric_data_item_read(
-                        |v| { ExpressionEvalResult::SingleResult(v as f32) },
-                        value_opt,
-                        context)
-                }
-                ExpressionType::Double => {
-                    let value_opt = self.preprocess_numeric_data_item(next_data);
-                    self.process_numeric_data_item_read(
-                        |v| { ExpressionEvalResult::DoubleResult(v) },
-                        value_opt,
-                        context)
-                }
+        for variable_expr in &self.variables {
+            check_result![ GwRead::read_item(variable_expr.as_ref(), context, program) ];
+        }
+        InstructionResult::EvaluateNext
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        buffer.push_str("READ ");
+        let mut count = self.variables.len();
+        for variable_expr in &self.variables {
+            variable_expr.fill_structure_string(buffer);
+            count -= 1;
+            if count != 0 {
+                buffer.push_str(", ");
             }
-        } else {
-            InstructionResult::EvaluateToError("OUT OF DATA".to_string())
         }
+    }
+}
 
+/// AST element for the `RESTORE` statement which moves the
+/// DATA pointer to the start or to the first item at or
+/// after the given line
+pub struct GwRestore {
+    line: Option<i16>
+}
+
+impl GwRestore {
+    pub fn new(line: Option<i16>) -> GwRestore {
+        GwRestore { line }
+    }
+}
+
+impl GwInstruction for GwRestore {
+    fn eval (&self,
+             _line: i16,
+             _argument: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        check_result![ context.restore_data(self.line) ];
+        InstructionResult::EvaluateNext
     }
 
     fn fill_structure_string(&self, buffer : &mut String) {
-        buffer.push_str("READ ");
-        self.variable_expr.fill_structure_string(buffer);
+        buffer.push_str("RESTORE");
+        if let Some(line) = self.line {
+            buffer.push(' ');
+            buffer.push_str(&line.to_string());
+        }
     }
 }
 
@@ -144,21 +141,21 @@ impl GwInstruction for GwRead {
 mod data_tests {
     use super::*;
     use crate::eval::eval_tests::DummyConsole;
-    use crate::eval::{ ExpressionType, GwVariableExpression, GwProgram };
+    use crate::eval::{ ExpressionType, ExpressionEvalResult, GwVariableExpression, GwProgram };
 
     #[test]
     fn it_reads_number_data() -> Result<(), & 'static str> {
         let string1 = "first".to_string();
         let string2 = "second".to_string();
         let mut ctx = EvaluationContext::new(Box::new(DummyConsole{}));
-        ctx.set_variable_type("x", &ExpressionType::String);
+        ctx.set_default_type('x', ExpressionType::String);
         let mut program = GwProgram {
             lines: vec![],
             real_lines: vec![],
             data: vec![string1, string2]
         };
         let read_instr = GwRead::new(
-            Box::new(GwVariableExpression::with_name("x".to_string())));
+            vec![Box::new(GwVariableExpression::with_name("x".to_string()))]);
         match read_instr.eval(1, LineExecutionArgument::Empty, &mut ctx, &mut program) {
             InstructionResult::EvaluateNext => {
                 assert_eq!(ExpressionEvalResult::StringResult("first".to_string()),
@@ -169,4 +166,47 @@ mod data_tests {
         }
 
     }
+
+    #[test]
+    fn it_rounds_integer_data() {
+        let mut ctx = EvaluationContext::new(Box::new(DummyConsole{}));
+        let mut program = GwProgram {
+            lines: vec![],
+            real_lines: vec![],
+            data: vec!["4.6".to_string(), "-2.5".to_string(), "40000".to_string()]
+        };
+        let read_instr = GwRead::new(
+            vec![Box::new(GwVariableExpression::with_name("b%".to_string())),
+                 Box::new(GwVariableExpression::with_name("c%".to_string()))]);
+        assert!(matches!(read_instr.eval(1, LineExecutionArgument::Empty, &mut ctx, &mut program),
+                         InstructionResult::EvaluateNext));
+        assert_eq!(Some(&ExpressionEvalResult::IntegerResult(5)), ctx.lookup_variable("b%"));
+        assert_eq!(Some(&ExpressionEvalResult::IntegerResult(-3)), ctx.lookup_variable("c%"));
+        assert!(matches!(read_instr.eval(1, LineExecutionArgument::Empty, &mut ctx, &mut program),
+                         InstructionResult::EvaluateToError(GwError::Overflow)));
+    }
+
+    #[test]
+    fn it_reads_several_items_and_restores() {
+        let mut ctx = EvaluationContext::new(Box::new(DummyConsole{}));
+        let mut program = GwProgram {
+            lines: vec![],
+            real_lines: vec![],
+            data: vec!["1.5".to_string(), "\"TWO\"".to_string()]
+        };
+        let read_instr = GwRead::new(
+            vec![Box::new(GwVariableExpression::with_name("a".to_string())),
+                 Box::new(GwVariableExpression::with_name("b$".to_string()))]);
+        read_instr.eval(1, LineExecutionArgument::Empty, &mut ctx, &mut program);
+        assert_eq!(Some(&ExpressionEvalResult::SingleResult(1.5)), ctx.lookup_variable("a"));
+        assert_eq!(Some(&ExpressionEvalResult::StringResult("TWO".to_string())), ctx.lookup_variable("b$"));
+
+        assert!(matches!(read_instr.eval(1, LineExecutionArgument::Empty, &mut ctx, &mut program),
+                         InstructionResult::EvaluateToError(GwError::OutOfData)));
+        GwRestore::new(None).eval(1, LineExecutionArgument::Empty, &mut ctx, &mut program);
+        assert!(matches!(read_instr.eval(1, LineExecutionArgument::Empty, &mut ctx, &mut program),
+                         InstructionResult::EvaluateNext));
+        assert!(matches!(GwRestore::new(Some(100)).eval(1, LineExecutionArgument::Empty, &mut ctx, &mut program),
+                         InstructionResult::EvaluateToError(GwError::UndefinedLineNumber)));
+    }
 }
@@ -7,6 +7,13 @@ pub enum DefVarRange {
     Range(char, char)
 }
 
+/// AST element for the `DEFINT`, `DEFSNG`, `DEFDBL` and `DEFSTR`
+/// statements which set the type of the names without suffix
+/// by their first letter.
+/// For example:
+/// ```basic
+/// DEFINT I-N
+/// ```
 pub struct GwDefType {
     ranges : Vec<DefVarRange>,
     definition_type: ExpressionType
@@ -50,39 +57,32 @@ impl GwInstruction for GwDefType {
 }
 
 fn range_to_string_buffer(ranges: &Vec<DefVarRange>, buffer: &mut String) {
-    for obj in ranges {
+    for (index, obj) in ranges.iter().enumerate() {
+        if index > 0 {
+            buffer.push(',');
+        }
         match obj {
             DefVarRange::Single(c) =>
                 buffer.push_str(&c.to_string()[..]),
             DefVarRange::Range(s, e) => {
                 buffer.push_str(&s.to_string()[..]);
-                buffer.push_str("-");
+                buffer.push('-');
                 buffer.push_str(&e.to_string()[..]);
             }
         }
-        buffer.push_str(",");
     }
 }
 
 fn set_range_type(range: &DefVarRange,
                   range_type: &ExpressionType,
                   context: &mut EvaluationContext) {
-    let mut tmp_str = String::with_capacity(1);
     match range {
-        DefVarRange::Single(var_name) => {
-            tmp_str.push(*var_name);
-            context.set_variable_type(&tmp_str, range_type);
+        DefVarRange::Single(letter) => {
+            context.set_default_type(*letter, *range_type);
         }
         DefVarRange::Range(first_char, second_char) => {
-            if first_char.is_ascii_alphabetic() {
-                let mut tmp_char = *first_char as u8;
-                let end_char = *second_char as u8;
-                while tmp_char < end_char {
-                    tmp_str.clear();
-                    tmp_str.push(tmp_char as char);
-                    context.set_variable_type(&tmp_str, range_type);
-                    tmp_char += 1;
-                }
+            for letter in *first_char..=*second_char {
+                context.set_default_type(letter, *range_type);
             }
         }
     }
@@ -0,0 +1,207 @@
+use std::rc::Rc;
+use super::{ EvaluationContext, LineExecutionArgument, ExpressionEvalResult,
+             ExpressionType, InstructionResult, GwInstruction, GwExpression,
+             GwProgram, GwError, EvaluationError };
+use super::context::convert_to_type;
+
+
+/// User defined function created with `DEF FN`
+pub struct GwUserFunction {
+    name: String,
+    parameters: Vec<String>,
+    body: Box<dyn GwExpression>
+}
+
+impl GwUserFunction {
+    pub fn new(name: String,
+               parameters: Vec<String>,
+               body: Box<dyn GwExpression>) -> GwUserFunction {
+        GwUserFunction { name, parameters, body }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// The result type is given by the name after `FN`, so `FNA` is
+    /// an integer function after `DEFINT A`
+    fn result_type(&self, context: &EvaluationContext) -> ExpressionType {
+        context.get_type_from_name(self.name.strip_prefix("FN").unwrap_or(&self.name))
+    }
+
+    /// Evaluates the body of the function with the parameters bound to
+    /// the given arguments. Parameters shadow global variables with the
+    /// same name only during the evaluation of the body.
+    pub fn call(&self,
+                arguments: Vec<ExpressionEvalResult>,
+                context: &mut EvaluationContext)
+                -> Result<ExpressionEvalResult, EvaluationError> {
+        if arguments.len() != self.parameters.len() {
+            return Err(GwError::SyntaxError);
+        }
+        for (parameter, argument) in self.parameters.iter().zip(arguments.iter()) {
+            if (context.get_type_from_name(parameter) == ExpressionType::String) !=
+                matches!(argument, ExpressionEvalResult::StringResult(_)) {
+                return Err(GwError::TypeMismatch);
+            }
+        }
+
+        let parameter_names: Vec<String> =
+            self.parameters.iter()
+                .map(|parameter| context.qualified_name(parameter))
+                .collect();
+        let saved_values: Vec<Option<ExpressionEvalResult>> =
+            parameter_names.iter()
+                .map(|parameter| context.variables.remove(parameter))
+                .collect();
+        let result = self.parameters.iter()
+            .zip(arguments.iter())
+            .try_for_each(|(parameter, argument)| context.set_variable(parameter, argument))
+            .and_then(|_| self.body.eval(context));
+
+        for (parameter, saved_value) in parameter_names.into_iter().zip(saved_values) {
+            match saved_value {
+                Some(value) => { context.variables.insert(parameter, value); }
+                None => { context.variables.remove(&parameter); }
+            }
+        }
+
+        result.and_then(|value| convert_to_type(&value, self.result_type(context)))
+    }
+}
+
+/// AST element for the `DEF FN` statement.
+/// For example:
+/// ```basic
+/// DEF FNAREA(W, H) = W * H
+/// ```
+pub struct GwDefFn {
+    function: Rc<GwUserFunction>
+}
+
+impl GwDefFn {
+    pub fn new(function: GwUserFunction) -> GwDefFn {
+        GwDefFn { function: Rc::new(function) }
+    }
+}
+
+impl GwInstruction for GwDefFn {
+    fn eval (&self,
+             line: i16,
+             _argument: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        if line < 0 {
+            InstructionResult::EvaluateToError(GwError::IllegalDirect)
+        } else {
+            context.define_function(self.function.clone());
+            InstructionResult::EvaluateNext
+        }
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        buffer.push_str("DEF ");
+        buffer.push_str(&self.function.name);
+        if !self.function.parameters.is_empty() {
+            buffer.push('(');
+            buffer.push_str(&self.function.parameters.join(","));
+            buffer.push(')');
+        }
+        buffer.push_str(" = ");
+        self.function.body.fill_structure_string(buffer);
+    }
+}
+
+/// AST element for calls to user defined functions.
+/// For example:
+/// ```basic
+/// PRINT FNAREA(10, 20)
+/// ```
+pub struct GwFnCall {
+    name: String,
+    arguments: Vec<Box<dyn GwExpression>>
+}
+
+impl GwFnCall {
+    pub fn new(name: String, arguments: Vec<Box<dyn GwExpression>>) -> GwFnCall {
+        GwFnCall { name, arguments }
+    }
+}
+
+impl GwExpression for GwFnCall {
+    fn eval(&self, context: &mut EvaluationContext) -> Result<ExpressionEvalResult, EvaluationError> {
+        if let Some(function) = context.get_existing_function(&self.name) {
+            let mut evaluated_arguments = Vec::with_capacity(self.arguments.len());
+            for argument in &self.arguments {
+                evaluated_arguments.push(argument.eval(context)?);
+            }
+            function.call(evaluated_arguments, context)
+        } else {
+            Err(GwError::UndefinedUserFunction)
+        }
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push_str(&self.name);
+        if !self.arguments.is_empty() {
+            buffer.push('(');
+            let mut c = self.arguments.len();
+            for argument in &self.arguments {
+                argument.fill_structure_string(buffer);
+                c -= 1;
+                if c != 0 {
+                    buffer.push(',');
+                }
+            }
+            buffer.push(')');
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod def_fn_tests {
+    use crate::eval::eval_tests::DummyConsole;
+    use crate::eval::binary::{ GwBinaryOperation, GwBinaryOperationKind };
+    use crate::eval::*;
+    use crate::eval::deffn_instr::*;
+
+    fn define_times_function(ctxt: &mut EvaluationContext) {
+        let mut program = GwProgram::new();
+        let def_fn = GwDefFn::new(GwUserFunction::new(
+            String::from("FNT"),
+            vec![String::from("X")],
+            Box::new(GwBinaryOperation::new(
+                GwBinaryOperationKind::Times,
+                Box::new(GwVariableExpression::with_name(String::from("X"))),
+                Box::new(GwVariableExpression::with_name(String::from("Y")))))));
+        let _ = def_fn.eval(10, LineExecutionArgument::Empty, ctxt, &mut program);
+    }
+
+    #[test]
+    fn it_evaluates_user_functions_with_parameter_scope() {
+        let mut ctxt = EvaluationContext::new(Box::new(DummyConsole{}));
+        define_times_function(&mut ctxt);
+        let _ = ctxt.set_variable("X", &ExpressionEvalResult::SingleResult(100.0));
+        let _ = ctxt.set_variable("Y", &ExpressionEvalResult::SingleResult(3.0));
+
+        let call = GwFnCall::new(String::from("FNT"),
+                                 vec![Box::new(GwIntegerLiteral::with_value(5))]);
+        assert_eq!(Ok(ExpressionEvalResult::SingleResult(15.0)), call.eval(&mut ctxt));
+        assert_eq!(Some(&ExpressionEvalResult::SingleResult(100.0)), ctxt.lookup_variable("X"));
+    }
+
+    #[test]
+    fn it_reports_errors_calling_user_functions() {
+        let mut ctxt = EvaluationContext::new(Box::new(DummyConsole{}));
+        let call = GwFnCall::new(String::from("FNT"),
+                                 vec![Box::new(GwIntegerLiteral::with_value(5))]);
+        assert_eq!(Err(GwError::UndefinedUserFunction), call.eval(&mut ctxt));
+
+        define_times_function(&mut ctxt);
+        let call = GwFnCall::new(String::from("FNT"),
+                                 vec![Box::new(GwStringLiteral::with_value(String::from("A")))]);
+        assert_eq!(Err(GwError::TypeMismatch), call.eval(&mut ctxt));
+        assert_eq!(None, ctxt.lookup_variable("X"));
+    }
+}
@@ -2,7 +2,8 @@ use super::{EvaluationContext,
             GwInstruction, GwExpression, GwProgram,
             evaluate_to_usize,
             InstructionResult,
-            LineExecutionArgument};
+            LineExecutionArgument,
+            GwError};
 use std::result::Result;
 
 pub struct GwDimDecl {
@@ -17,16 +18,15 @@ impl GwDimDecl {
             dimensions,
         }
     }
-    fn perform_declaration(&self, context : &mut EvaluationContext) -> Result<(), String> {
+    fn perform_declaration(&self, context : &mut EvaluationContext) -> Result<(), GwError> {
 
         match evaluate_sequence_of_integers(&self.dimensions, context) {
             Ok(dimensions_to_use) if dimensions_to_use.len() > 0 => {
                 context.declare_array(
                             &self.name,
-                            *dimensions_to_use.get(0).unwrap() as usize);
-                Ok(())
+                            dimensions_to_use.iter().map(|d| *d as usize).collect())
             }
-            Ok(_) => Err("Dimensions are required".to_string()),
+            Ok(_) => Err(GwError::SyntaxError),
             Err(e) => Err(e)
         }
     }
@@ -86,10 +86,42 @@ impl GwInstruction for GwDim {
 
     }
     fn fill_structure_string(&self, buffer: &mut String) {
-        buffer.push_str("(DIM ");
-
+        buffer.push_str("DIM ");
         self.declaration.fill_structure_string(buffer);
+        for declaration in self.rest.iter().flatten() {
+            buffer.push_str(", ");
+            declaration.fill_structure_string(buffer);
+        }
+    }
+}
+
+/// AST element for the `OPTION BASE` statement which sets the
+/// lowest subscript of arrays to 0 or 1
+pub struct GwOptionBase {
+    base: usize
+}
+
+impl GwOptionBase {
+    pub fn new(base: usize) -> GwOptionBase {
+        GwOptionBase { base }
+    }
+}
+
+impl GwInstruction for GwOptionBase {
+    fn eval(&self,
+            _line: i16,
+            _arg: LineExecutionArgument,
+            context : &mut EvaluationContext,
+            _program: &mut GwProgram) -> InstructionResult {
+        match context.set_option_base(self.base) {
+            Ok(_) => InstructionResult::EvaluateNext,
+            Err(error) => InstructionResult::EvaluateToError(error)
+        }
+    }
 
+    fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push_str("OPTION BASE ");
+        buffer.push_str(&self.base.to_string());
     }
 }
 
@@ -109,7 +141,7 @@ fn append_to_string_with_separator(target: &mut String,
 
 fn evaluate_sequence_of_integers(exprs: &Vec<Box<dyn GwExpression>>,
                                  context: &mut EvaluationContext)
-                                 -> Result<Vec<u16>, String> {
+                                 -> Result<Vec<u16>, GwError> {
 
     let mut result = Vec::with_capacity(exprs.len());
     for expr in exprs {
@@ -117,8 +149,8 @@ fn evaluate_sequence_of_integers(exprs: &Vec<Box<dyn GwExpression>>,
             Ok(usize_val) => {
                 result.push(usize_val as u16);
             }
-            _ => {
-                return Err(String::from("Invalid dimension"));
+            Err(error) => {
+                return Err(error);
             }
         }
     }
@@ -156,4 +188,42 @@ mod dim_tests {
         } 
 
     }
+
+    #[test]
+    fn it_declares_multidimensional_arrays() -> Result<(), GwError> {
+        let mut program = eval_tests::empty_program();
+        let dim = GwDim::new(
+                     GwDimDecl::new(
+                         "arr".to_string(),
+                         vec![Box::new(GwIntegerLiteral::with_value(2)),
+                              Box::new(GwIntegerLiteral::with_value(3))]),
+                    None);
+        let mut context = eval_tests::empty_context();
+        dim.eval(1, LineExecutionArgument::Empty, &mut context, &mut program);
+
+        context.set_array_entry("arr", vec![1, 2], &ExpressionEvalResult::IntegerResult(12))?;
+        context.set_array_entry("arr", vec![2, 1], &ExpressionEvalResult::IntegerResult(21))?;
+        assert_eq!(ExpressionEvalResult::IntegerResult(12), context.get_array_entry("arr", vec![1, 2])?);
+        assert_eq!(ExpressionEvalResult::IntegerResult(21), context.get_array_entry("arr", vec![2, 1])?);
+        assert_eq!(ExpressionEvalResult::SingleResult(0.0), context.get_array_entry("arr", vec![0, 0])?);
+        assert_eq!(Err(GwError::SubscriptOutOfRange), context.get_array_entry("arr", vec![3, 0]));
+        assert_eq!(Err(GwError::SubscriptOutOfRange), context.get_array_entry("arr", vec![1]));
+        assert!(matches!(dim.eval(1, LineExecutionArgument::Empty, &mut context, &mut program),
+                         InstructionResult::EvaluateToError(GwError::DuplicateDefinition)));
+        Ok(())
+    }
+
+    #[test]
+    fn it_uses_option_base_for_lower_bounds() -> Result<(), GwError> {
+        let mut program = eval_tests::empty_program();
+        let mut context = eval_tests::empty_context();
+        GwOptionBase::new(1).eval(1, LineExecutionArgument::Empty, &mut context, &mut program);
+
+        context.set_array_entry("auto", vec![10], &ExpressionEvalResult::IntegerResult(1))?;
+        assert_eq!(Err(GwError::SubscriptOutOfRange), context.get_array_entry("auto", vec![0]));
+        assert!(matches!(
+            GwOptionBase::new(0).eval(1, LineExecutionArgument::Empty, &mut context, &mut program),
+            InstructionResult::EvaluateToError(GwError::DuplicateDefinition)));
+        Ok(())
+    }
 }
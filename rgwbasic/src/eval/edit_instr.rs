@@ -0,0 +1,60 @@
+use super::{ GwInstruction,
+             InstructionResult,
+             EvaluationContext,
+             LineExecutionArgument,
+             GwProgram,
+             LineRange };
+
+/// AST element for the `NEW` statement which removes the program.
+/// As in GW-BASIC it returns to direct mode when used in a program
+pub struct GwNew {
+}
+
+impl GwInstruction for GwNew {
+    fn eval(&self,
+            _line: i16,
+            _argument: LineExecutionArgument,
+            _context: &mut EvaluationContext,
+            program: &mut GwProgram) -> InstructionResult {
+        program.clear();
+        InstructionResult::EvaluateEnd
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push_str("NEW");
+    }
+}
+
+/// AST element for the `DELETE` statement which removes a range of
+/// lines from the program and returns to direct mode.
+/// For example:
+/// ```basic
+/// DELETE 10-50
+/// ```
+pub struct GwDelete {
+    range: LineRange
+}
+
+impl GwDelete {
+    pub fn new(range: LineRange) -> GwDelete {
+        GwDelete { range }
+    }
+}
+
+impl GwInstruction for GwDelete {
+    fn eval(&self,
+            _line: i16,
+            _argument: LineExecutionArgument,
+            _context: &mut EvaluationContext,
+            program: &mut GwProgram) -> InstructionResult {
+        match program.delete_lines(self.range) {
+            Ok(_) => InstructionResult::EvaluateEnd,
+            Err(error) => InstructionResult::EvaluateToError(error)
+        }
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push_str("DELETE ");
+        self.range.fill_structure_string(buffer);
+    }
+}
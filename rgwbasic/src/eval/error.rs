@@ -0,0 +1,306 @@
+use std::fmt;
+
+/// Errors reported by the interpreter.
+///
+/// Each variant corresponds to one of the classic GW-BASIC error
+/// numbers (see `code`). Errors are usually created without
+/// location information and get the source line attached by the
+/// main evaluation loop using `at_line`. For example:
+/// ```basic
+/// Type mismatch in 20
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum GwError {
+    NextWithoutFor,
+    SyntaxError,
+    ReturnWithoutGosub,
+    OutOfData,
+    IllegalFunctionCall,
+    Overflow,
+    OutOfMemory,
+    UndefinedLineNumber,
+    SubscriptOutOfRange,
+    DuplicateDefinition,
+    DivisionByZero,
+    IllegalDirect,
+    TypeMismatch,
+    OutOfStringSpace,
+    StringTooLong,
+    StringFormulaTooComplex,
+    CantContinue,
+    UndefinedUserFunction,
+    NoResume,
+    ResumeWithoutError,
+    MissingOperand,
+    LineBufferOverflow,
+    DeviceTimeout,
+    DeviceFault,
+    ForWithoutNext,
+    OutOfPaper,
+    WhileWithoutWend,
+    WendWithoutWhile,
+    FieldOverflow,
+    InternalError,
+    BadFileNumber,
+    FileNotFound,
+    BadFileMode,
+    FileAlreadyOpen,
+    DeviceIoError,
+    FileAlreadyExists,
+    DiskFull,
+    InputPastEnd,
+    BadRecordNumber,
+    BadFileName,
+    DirectStatementInFile,
+    TooManyFiles,
+    DeviceUnavailable,
+    CommunicationBufferOverflow,
+    PermissionDenied,
+    DiskNotReady,
+    DiskMediaError,
+    AdvancedFeature,
+    RenameAcrossDisks,
+    PathFileAccessError,
+    PathNotFound,
+    /// Error number without a predefined message
+    Unprintable(u8),
+    /// Error with the source line where it was raised
+    InLine(Box<GwError>, i16),
+}
+
+impl GwError {
+    /// Gets the error for a GW-BASIC error number
+    pub fn from_code(code: u8) -> GwError {
+        match code {
+            1 => GwError::NextWithoutFor,
+            2 => GwError::SyntaxError,
+            3 => GwError::ReturnWithoutGosub,
+            4 => GwError::OutOfData,
+            5 => GwError::IllegalFunctionCall,
+            6 => GwError::Overflow,
+            7 => GwError::OutOfMemory,
+            8 => GwError::UndefinedLineNumber,
+            9 => GwError::SubscriptOutOfRange,
+            10 => GwError::DuplicateDefinition,
+            11 => GwError::DivisionByZero,
+            12 => GwError::IllegalDirect,
+            13 => GwError::TypeMismatch,
+            14 => GwError::OutOfStringSpace,
+            15 => GwError::StringTooLong,
+            16 => GwError::StringFormulaTooComplex,
+            17 => GwError::CantContinue,
+            18 => GwError::UndefinedUserFunction,
+            19 => GwError::NoResume,
+            20 => GwError::ResumeWithoutError,
+            22 => GwError::MissingOperand,
+            23 => GwError::LineBufferOverflow,
+            24 => GwError::DeviceTimeout,
+            25 => GwError::DeviceFault,
+            26 => GwError::ForWithoutNext,
+            27 => GwError::OutOfPaper,
+            29 => GwError::WhileWithoutWend,
+            30 => GwError::WendWithoutWhile,
+            50 => GwError::FieldOverflow,
+            51 => GwError::InternalError,
+            52 => GwError::BadFileNumber,
+            53 => GwError::FileNotFound,
+            54 => GwError::BadFileMode,
+            55 => GwError::FileAlreadyOpen,
+            57 => GwError::DeviceIoError,
+            58 => GwError::FileAlreadyExists,
+            61 => GwError::DiskFull,
+            62 => GwError::InputPastEnd,
+            63 => GwError::BadRecordNumber,
+            64 => GwError::BadFileName,
+            66 => GwError::DirectStatementInFile,
+            67 => GwError::TooManyFiles,
+            68 => GwError::DeviceUnavailable,
+            69 => GwError::CommunicationBufferOverflow,
+            70 => GwError::PermissionDenied,
+            71 => GwError::DiskNotReady,
+            72 => GwError::DiskMediaError,
+            73 => GwError::AdvancedFeature,
+            74 => GwError::RenameAcrossDisks,
+            75 => GwError::PathFileAccessError,
+            76 => GwError::PathNotFound,
+            other => GwError::Unprintable(other),
+        }
+    }
+
+    /// GW-BASIC error number
+    pub fn code(&self) -> u8 {
+        match self {
+            GwError::NextWithoutFor => 1,
+            GwError::SyntaxError => 2,
+            GwError::ReturnWithoutGosub => 3,
+            GwError::OutOfData => 4,
+            GwError::IllegalFunctionCall => 5,
+            GwError::Overflow => 6,
+            GwError::OutOfMemory => 7,
+            GwError::UndefinedLineNumber => 8,
+            GwError::SubscriptOutOfRange => 9,
+            GwError::DuplicateDefinition => 10,
+            GwError::DivisionByZero => 11,
+            GwError::IllegalDirect => 12,
+            GwError::TypeMismatch => 13,
+            GwError::OutOfStringSpace => 14,
+            GwError::StringTooLong => 15,
+            GwError::StringFormulaTooComplex => 16,
+            GwError::CantContinue => 17,
+            GwError::UndefinedUserFunction => 18,
+            GwError::NoResume => 19,
+            GwError::ResumeWithoutError => 20,
+            GwError::MissingOperand => 22,
+            GwError::LineBufferOverflow => 23,
+            GwError::DeviceTimeout => 24,
+            GwError::DeviceFault => 25,
+            GwError::ForWithoutNext => 26,
+            GwError::OutOfPaper => 27,
+            GwError::WhileWithoutWend => 29,
+            GwError::WendWithoutWhile => 30,
+            GwError::FieldOverflow => 50,
+            GwError::InternalError => 51,
+            GwError::BadFileNumber => 52,
+            GwError::FileNotFound => 53,
+            GwError::BadFileMode => 54,
+            GwError::FileAlreadyOpen => 55,
+            GwError::DeviceIoError => 57,
+            GwError::FileAlreadyExists => 58,
+            GwError::DiskFull => 61,
+            GwError::InputPastEnd => 62,
+            GwError::BadRecordNumber => 63,
+            GwError::BadFileName => 64,
+            GwError::DirectStatementInFile => 66,
+            GwError::TooManyFiles => 67,
+            GwError::DeviceUnavailable => 68,
+            GwError::CommunicationBufferOverflow => 69,
+            GwError::PermissionDenied => 70,
+            GwError::DiskNotReady => 71,
+            GwError::DiskMediaError => 72,
+            GwError::AdvancedFeature => 73,
+            GwError::RenameAcrossDisks => 74,
+            GwError::PathFileAccessError => 75,
+            GwError::PathNotFound => 76,
+            GwError::Unprintable(code) => *code,
+            GwError::InLine(error, _) => error.code(),
+        }
+    }
+
+    /// Canonical GW-BASIC message for this error
+    pub fn message(&self) -> &'static str {
+        match self {
+            GwError::NextWithoutFor => "NEXT without FOR",
+            GwError::SyntaxError => "Syntax error",
+            GwError::ReturnWithoutGosub => "RETURN without GOSUB",
+            GwError::OutOfData => "Out of DATA",
+            GwError::IllegalFunctionCall => "Illegal function call",
+            GwError::Overflow => "Overflow",
+            GwError::OutOfMemory => "Out of memory",
+            GwError::UndefinedLineNumber => "Undefined line number",
+            GwError::SubscriptOutOfRange => "Subscript out of range",
+            GwError::DuplicateDefinition => "Duplicate Definition",
+            GwError::DivisionByZero => "Division by zero",
+            GwError::IllegalDirect => "Illegal direct",
+            GwError::TypeMismatch => "Type mismatch",
+            GwError::OutOfStringSpace => "Out of string space",
+            GwError::StringTooLong => "String too long",
+            GwError::StringFormulaTooComplex => "String formula too complex",
+            GwError::CantContinue => "Can't continue",
+            GwError::UndefinedUserFunction => "Undefined user function",
+            GwError::NoResume => "No RESUME",
+            GwError::ResumeWithoutError => "RESUME without error",
+            GwError::MissingOperand => "Missing operand",
+            GwError::LineBufferOverflow => "Line buffer overflow",
+            GwError::DeviceTimeout => "Device Timeout",
+            GwError::DeviceFault => "Device Fault",
+            GwError::ForWithoutNext => "FOR without NEXT",
+            GwError::OutOfPaper => "Out of Paper",
+            GwError::WhileWithoutWend => "WHILE without WEND",
+            GwError::WendWithoutWhile => "WEND without WHILE",
+            GwError::FieldOverflow => "FIELD overflow",
+            GwError::InternalError => "Internal error",
+            GwError::BadFileNumber => "Bad file number",
+            GwError::FileNotFound => "File not found",
+            GwError::BadFileMode => "Bad file mode",
+            GwError::FileAlreadyOpen => "File already open",
+            GwError::DeviceIoError => "Device I/O Error",
+            GwError::FileAlreadyExists => "File already exists",
+            GwError::DiskFull => "Disk full",
+            GwError::InputPastEnd => "Input past end",
+            GwError::BadRecordNumber => "Bad record number",
+            GwError::BadFileName => "Bad file name",
+            GwError::DirectStatementInFile => "Direct statement in file",
+            GwError::TooManyFiles => "Too many files",
+            GwError::DeviceUnavailable => "Device Unavailable",
+            GwError::CommunicationBufferOverflow => "Communication buffer overflow",
+            GwError::PermissionDenied => "Permission Denied",
+            GwError::DiskNotReady => "Disk not Ready",
+            GwError::DiskMediaError => "Disk media error",
+            GwError::AdvancedFeature => "Advanced Feature",
+            GwError::RenameAcrossDisks => "Rename across disks",
+            GwError::PathFileAccessError => "Path/File Access Error",
+            GwError::PathNotFound => "Path not found",
+            GwError::Unprintable(_) => "Unprintable error",
+            GwError::InLine(error, _) => error.message(),
+        }
+    }
+
+    /// Source line number where the error was raised, if known
+    pub fn line(&self) -> Option<i16> {
+        match self {
+            GwError::InLine(_, line) => Some(*line),
+            _ => None
+        }
+    }
+
+    /// Returns the same error associated with the given source line
+    pub fn at_line(self, line: i16) -> GwError {
+        match self {
+            GwError::InLine(error, _) => GwError::InLine(error, line),
+            error => GwError::InLine(Box::new(error), line)
+        }
+    }
+
+    /// Returns the error without the source line information
+    pub fn without_line(self) -> GwError {
+        match self {
+            GwError::InLine(error, _) => *error,
+            error => error
+        }
+    }
+}
+
+impl fmt::Display for GwError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line() {
+            Some(line) => write!(f, "{} in {}", self.message(), line),
+            None => write!(f, "{}", self.message())
+        }
+    }
+}
+
+impl std::error::Error for GwError {}
+
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    #[test]
+    fn it_maps_error_codes() {
+        for code in 1..=255u8 {
+            assert_eq!(code, GwError::from_code(code).code());
+        }
+        assert_eq!(GwError::TypeMismatch, GwError::from_code(13));
+        assert_eq!(GwError::Unprintable(21), GwError::from_code(21));
+    }
+
+    #[test]
+    fn it_formats_errors_with_line() {
+        let error = GwError::SubscriptOutOfRange.at_line(20);
+        assert_eq!(9, error.code());
+        assert_eq!(Some(20), error.line());
+        assert_eq!("Subscript out of range in 20", error.to_string());
+        assert_eq!("Division by zero", GwError::DivisionByZero.to_string());
+        assert_eq!(GwError::SubscriptOutOfRange, error.without_line());
+    }
+}
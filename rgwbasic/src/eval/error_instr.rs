@@ -0,0 +1,248 @@
+use super::{ EvaluationContext, LineExecutionArgument, ExpressionEvalResult,
+             InstructionResult, GwInstruction, GwExpression, GwProgram,
+             GwError, EvaluationError, get_as_integer };
+
+
+/// AST element for the `ON ERROR GOTO` statement.
+/// Using line `0` disables the error trap.
+/// For example:
+/// ```basic
+/// ON ERROR GOTO 1000
+/// ```
+pub struct GwOnErrorGoto {
+    line_number: i16
+}
+
+impl GwOnErrorGoto {
+    pub fn new(line_number: i16) -> GwOnErrorGoto {
+        GwOnErrorGoto { line_number }
+    }
+}
+
+impl GwInstruction for GwOnErrorGoto {
+    fn eval (&self,
+             _line: i16,
+             _argument: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        if self.line_number == 0 {
+            context.error_trap.handler_line = None;
+            // Disabling the trap inside the handler stops the program
+            // reporting the error being handled
+            if context.error_trap.is_handling_error() {
+                if let Some(error) = context.error_trap.last_error.clone() {
+                    return InstructionResult::EvaluateToError(error);
+                }
+            }
+            InstructionResult::EvaluateNext
+        } else if context.get_real_line(self.line_number).is_some() {
+            context.error_trap.handler_line = Some(self.line_number);
+            InstructionResult::EvaluateNext
+        } else {
+            InstructionResult::EvaluateToError(GwError::UndefinedLineNumber)
+        }
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        buffer.push_str(format!("ON ERROR GOTO {}", self.line_number).as_str());
+    }
+}
+
+/// Place where the execution continues after `RESUME`
+#[derive(Debug, PartialEq)]
+pub enum ResumeTarget {
+    /// `RESUME` or `RESUME 0`, the statement that caused the error
+    Current,
+    /// `RESUME NEXT`, the statement following the one that caused the error
+    Next,
+    /// `RESUME line`
+    Line(i16)
+}
+
+/// AST element for the `RESUME` statement.
+/// For example:
+/// ```basic
+/// RESUME NEXT
+/// ```
+pub struct GwResume {
+    target: ResumeTarget
+}
+
+impl GwResume {
+    pub fn new(target: ResumeTarget) -> GwResume {
+        GwResume { target }
+    }
+}
+
+impl GwInstruction for GwResume {
+    fn eval (&self,
+             _line: i16,
+             _argument: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        if let Some(resume_real_line) = context.error_trap.resume_real_line {
+            let destination = match self.target {
+                ResumeTarget::Current => resume_real_line as i16,
+                ResumeTarget::Next => resume_real_line as i16 + 1,
+                ResumeTarget::Line(line_number) => {
+                    if let Some(real_line) = context.get_real_line(line_number) {
+                        real_line
+                    } else {
+                        return InstructionResult::EvaluateToError(GwError::UndefinedLineNumber);
+                    }
+                }
+            };
+            context.error_trap.resume_real_line = None;
+            InstructionResult::EvaluateLine(destination)
+        } else {
+            InstructionResult::EvaluateToError(GwError::ResumeWithoutError)
+        }
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        match self.target {
+            ResumeTarget::Current => buffer.push_str("RESUME"),
+            ResumeTarget::Next => buffer.push_str("RESUME NEXT"),
+            ResumeTarget::Line(line_number) =>
+                buffer.push_str(format!("RESUME {}", line_number).as_str())
+        }
+    }
+}
+
+/// AST element for the `ERROR` statement used to simulate errors.
+/// For example:
+/// ```basic
+/// ERROR 53
+/// ```
+pub struct GwErrorStat {
+    code: Box<dyn GwExpression>
+}
+
+impl GwErrorStat {
+    pub fn new(code: Box<dyn GwExpression>) -> GwErrorStat {
+        GwErrorStat { code }
+    }
+}
+
+impl GwInstruction for GwErrorStat {
+    fn eval (&self,
+             _line: i16,
+             _argument: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        let code = match self.code.eval(context) {
+            Ok(code) => code,
+            Err(error) => return InstructionResult::EvaluateToError(error)
+        };
+        match get_as_integer(&Some(&code)) {
+            Ok(code) if (1..=255).contains(&code) =>
+                InstructionResult::EvaluateToError(GwError::from_code(code as u8)),
+            Ok(_) => InstructionResult::EvaluateToError(GwError::IllegalFunctionCall),
+            Err(error) => InstructionResult::EvaluateToError(error)
+        }
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        buffer.push_str("ERROR ");
+        self.code.fill_structure_string(buffer);
+    }
+}
+
+/// AST element for the `ERR` function, the code of the last trapped error
+pub struct GwErr {
+}
+
+impl GwExpression for GwErr {
+    fn eval(&self, context: &mut EvaluationContext) -> Result<ExpressionEvalResult, EvaluationError> {
+        let code = context.error_trap.last_error.as_ref().map_or(0, |error| error.code());
+        Ok(ExpressionEvalResult::IntegerResult(code as i16))
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push_str("ERR");
+    }
+}
+
+/// AST element for the `ERL` function, the line of the last trapped error
+pub struct GwErl {
+}
+
+impl GwExpression for GwErl {
+    fn eval(&self, context: &mut EvaluationContext) -> Result<ExpressionEvalResult, EvaluationError> {
+        let line = context.error_trap.last_error.as_ref().and_then(|error| error.line());
+        Ok(ExpressionEvalResult::IntegerResult(line.unwrap_or(0)))
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push_str("ERL");
+    }
+}
+
+
+#[cfg(test)]
+mod error_instr_tests {
+    use crate::eval::eval_tests::DummyConsole;
+    use crate::eval::*;
+    use crate::eval::error_instr::*;
+
+    fn empty_program() -> GwProgram {
+        GwProgram {
+            lines: vec![],
+            real_lines: vec![],
+            data: vec![],
+        }
+    }
+
+    #[test]
+    fn it_traps_errors_when_handler_is_enabled() {
+        let mut ctxt = EvaluationContext::new(Box::new(DummyConsole{}));
+        let mut program = empty_program();
+        ctxt.jump_table.insert(100, 5);
+        let on_error = GwOnErrorGoto::new(100);
+        assert!(matches!(on_error.eval(0, LineExecutionArgument::Empty, &mut ctxt, &mut program),
+                         InstructionResult::EvaluateNext));
+
+        assert_eq!(Ok(5), ctxt.trap_error(GwError::DivisionByZero.at_line(20), 2));
+        assert_eq!(Ok(ExpressionEvalResult::IntegerResult(11)), GwErr{}.eval(&mut ctxt));
+        assert_eq!(Ok(ExpressionEvalResult::IntegerResult(20)), GwErl{}.eval(&mut ctxt));
+
+        // Errors inside the handler are not trapped
+        assert_eq!(Err(GwError::Overflow), ctxt.trap_error(GwError::Overflow, 6));
+    }
+
+    #[test]
+    fn it_resumes_execution() {
+        let mut ctxt = EvaluationContext::new(Box::new(DummyConsole{}));
+        let mut program = empty_program();
+        ctxt.jump_table.insert(100, 5);
+        ctxt.jump_table.insert(30, 3);
+        ctxt.error_trap.handler_line = Some(100);
+
+        for (target, expected) in [(ResumeTarget::Current, 2), (ResumeTarget::Next, 3), (ResumeTarget::Line(30), 3)] {
+            let _ = ctxt.trap_error(GwError::TypeMismatch, 2);
+            match GwResume::new(target).eval(6, LineExecutionArgument::Empty, &mut ctxt, &mut program) {
+                InstructionResult::EvaluateLine(line) => assert_eq!(expected, line),
+                _ => panic!("Unexpected RESUME result")
+            }
+            assert!(!ctxt.error_trap.is_handling_error());
+        }
+
+        assert!(matches!(
+            GwResume::new(ResumeTarget::Next).eval(6, LineExecutionArgument::Empty, &mut ctxt, &mut program),
+            InstructionResult::EvaluateToError(GwError::ResumeWithoutError)));
+    }
+
+    #[test]
+    fn it_raises_errors_with_error_statement() {
+        let mut ctxt = EvaluationContext::new(Box::new(DummyConsole{}));
+        let mut program = empty_program();
+        let error_stat = GwErrorStat::new(Box::new(GwIntegerLiteral::with_value(53)));
+        assert!(matches!(
+            error_stat.eval(0, LineExecutionArgument::Empty, &mut ctxt, &mut program),
+            InstructionResult::EvaluateToError(GwError::FileNotFound)));
+        let error_stat = GwErrorStat::new(Box::new(GwIntegerLiteral::with_value(0)));
+        assert!(matches!(
+            error_stat.eval(0, LineExecutionArgument::Empty, &mut ctxt, &mut program),
+            InstructionResult::EvaluateToError(GwError::IllegalFunctionCall)));
+    }
+}
@@ -0,0 +1,585 @@
+use super::{ ExpressionType,
+             ExpressionEvalResult,
+             EvaluationContext,
+             EvaluationError,
+             GwExpression,
+             GwInstruction, GwAssignableExpression, GwProgram,
+             LineExecutionArgument,
+             InstructionResult,
+             PrintTarget,
+             GwError };
+use super::binary::{ get_double_value, to_integer_operand, single_result, double_result };
+use super::files::{ FileMode, DEFAULT_RECORD_LENGTH, MAX_FILE_NUMBER, MAX_RECORD_NUMBER };
+use super::number_format::format_value;
+use super::string_func::parse_numeric_prefix;
+
+/// Evaluates the number of a file used by `OPEN`, `PRINT #` and the
+/// other file statements
+pub fn evaluate_file_number(number_expr: &dyn GwExpression,
+                            context: &mut EvaluationContext) -> Result<u8, GwError> {
+    match to_integer_operand(&number_expr.eval(context)?)? {
+        number if number >= 1 && number <= i16::from(MAX_FILE_NUMBER) => Ok(number as u8),
+        _ => Err(GwError::BadFileNumber)
+    }
+}
+
+fn fill_file_number_structure_string(number_expr: &dyn GwExpression, buffer: &mut String) {
+    buffer.push('#');
+    number_expr.fill_structure_string(buffer);
+}
+
+/// Evaluates the name of a file used by `OPEN` and `SAVE`
+pub fn eval_file_name(name_expr: &dyn GwExpression,
+                      context: &mut EvaluationContext) -> Result<String, GwError> {
+    match name_expr.eval(context)? {
+        ExpressionEvalResult::StringResult(name) if name.is_empty() => Err(GwError::BadFileName),
+        ExpressionEvalResult::StringResult(name) => Ok(name),
+        _ => Err(GwError::TypeMismatch)
+    }
+}
+
+/// Way the mode of a file is written in `OPEN`
+pub enum GwOpenMode {
+    /// `OPEN "DATA.TXT" FOR OUTPUT AS #1`
+    For(FileMode),
+    /// Mode string of the short form, `OPEN "O", #1, "DATA.TXT"`
+    ModeString(Box<dyn GwExpression>)
+}
+
+/// AST element for the `OPEN` statement
+pub struct GwOpen {
+    mode: GwOpenMode,
+    file_number: Box<dyn GwExpression>,
+    file_name: Box<dyn GwExpression>,
+    /// `LEN=` of random files
+    record_length: Option<Box<dyn GwExpression>>
+}
+
+impl GwOpen {
+    pub fn new(mode: GwOpenMode,
+               file_number: Box<dyn GwExpression>,
+               file_name: Box<dyn GwExpression>,
+               record_length: Option<Box<dyn GwExpression>>) -> GwOpen {
+        GwOpen { mode, file_number, file_name, record_length }
+    }
+
+    fn open(&self, context: &mut EvaluationContext) -> Result<(), GwError> {
+        let mode = match &self.mode {
+            GwOpenMode::For(mode) => *mode,
+            GwOpenMode::ModeString(mode_expr) => {
+                let mode_string = mode_expr.eval(context)?;
+                FileMode::from_mode_string(mode_string.assume_string_value()?)?
+            }
+        };
+        let number = evaluate_file_number(self.file_number.as_ref(), context)?;
+        let name = eval_file_name(self.file_name.as_ref(), context)?;
+        let record_length = match &self.record_length {
+            Some(length_expr) => match to_integer_operand(&length_expr.eval(context)?)? {
+                length if length >= 1 => length as usize,
+                _ => return Err(GwError::IllegalFunctionCall)
+            },
+            None => DEFAULT_RECORD_LENGTH
+        };
+        context.open_file(number, &name, mode, record_length)
+    }
+}
+
+impl GwInstruction for GwOpen {
+    fn eval (&self,
+             _line: i16,
+             _argument: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        check_result![ self.open(context) ];
+        InstructionResult::EvaluateNext
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        buffer.push_str("OPEN ");
+        match &self.mode {
+            GwOpenMode::For(mode) => {
+                self.file_name.fill_structure_string(buffer);
+                buffer.push_str(" FOR ");
+                buffer.push_str(mode.name());
+                buffer.push_str(" AS ");
+                fill_file_number_structure_string(self.file_number.as_ref(), buffer);
+                if let Some(length_expr) = &self.record_length {
+                    buffer.push_str(" LEN = ");
+                    length_expr.fill_structure_string(buffer);
+                }
+            }
+            GwOpenMode::ModeString(mode_expr) => {
+                mode_expr.fill_structure_string(buffer);
+                buffer.push_str(", ");
+                fill_file_number_structure_string(self.file_number.as_ref(), buffer);
+                buffer.push_str(", ");
+                self.file_name.fill_structure_string(buffer);
+                if let Some(length_expr) = &self.record_length {
+                    buffer.push_str(", ");
+                    length_expr.fill_structure_string(buffer);
+                }
+            }
+        }
+    }
+}
+
+/// AST element for the `CLOSE` statement, which closes all
+/// the files when no file number is given
+pub struct GwClose {
+    file_numbers: Vec<Box<dyn GwExpression>>
+}
+
+impl GwClose {
+    pub fn new(file_numbers: Vec<Box<dyn GwExpression>>) -> GwClose {
+        GwClose { file_numbers }
+    }
+}
+
+impl GwInstruction for GwClose {
+    fn eval (&self,
+             _line: i16,
+             _argument: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        if self.file_numbers.is_empty() {
+            context.close_all_files();
+        }
+        for number_expr in &self.file_numbers {
+            match evaluate_file_number(number_expr.as_ref(), context) {
+                Ok(number) => context.close_file(number),
+                Err(error) => return InstructionResult::EvaluateToError(error)
+            }
+        }
+        InstructionResult::EvaluateNext
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        buffer.push_str("CLOSE");
+        let mut separator = " ";
+        for number_expr in &self.file_numbers {
+            buffer.push_str(separator);
+            fill_file_number_structure_string(number_expr.as_ref(), buffer);
+            separator = ", ";
+        }
+    }
+}
+
+/// Text of a value written by `WRITE`, strings are quoted and
+/// numbers don't have the leading space
+fn format_write_item(value: &ExpressionEvalResult) -> String {
+    match value {
+        ExpressionEvalResult::StringResult(text) => format!("\"{}\"", text),
+        _ => format_value(value).trim_start().to_string()
+    }
+}
+
+/// AST element for the `WRITE` statement, which writes values
+/// separated by commas to the screen or to a file.
+/// Example:
+/// ```basic
+/// WRITE #1, NAME$, AGE
+/// ```
+pub struct GwWrite {
+    file_number: Option<Box<dyn GwExpression>>,
+    expressions: Vec<Box<dyn GwExpression>>
+}
+
+impl GwWrite {
+    pub fn new(file_number: Option<Box<dyn GwExpression>>,
+               expressions: Vec<Box<dyn GwExpression>>) -> GwWrite {
+        GwWrite { file_number, expressions }
+    }
+
+    fn write(&self, context: &mut EvaluationContext) -> Result<(), GwError> {
+        let target = PrintTarget::with_file_number(&self.file_number, context)?;
+        let mut items = vec![];
+        for expr in &self.expressions {
+            items.push(format_write_item(&expr.eval(context)?));
+        }
+        target.print_line(context, &items.join(","))
+    }
+}
+
+impl GwInstruction for GwWrite {
+    fn eval (&self,
+             _line: i16,
+             _argument: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        check_result![ self.write(context) ];
+        InstructionResult::EvaluateNext
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        buffer.push_str("WRITE ");
+        if let Some(number_expr) = &self.file_number {
+            fill_file_number_structure_string(number_expr.as_ref(), buffer);
+            buffer.push_str(", ");
+        }
+        let mut count = self.expressions.len();
+        for expr in &self.expressions {
+            expr.fill_structure_string(buffer);
+            count -= 1;
+            if count != 0 {
+                buffer.push_str(", ");
+            }
+        }
+    }
+}
+
+/// Converts an item read by `INPUT #` or `READ` to the type of the variable,
+/// numbers are converted as done by `VAL`
+pub fn convert_input_item(text: String, var_type: ExpressionType) -> Result<ExpressionEvalResult, GwError> {
+    if var_type == ExpressionType::String {
+        return Ok(ExpressionEvalResult::StringResult(text));
+    }
+    let value = parse_numeric_prefix(&text)?;
+    match var_type {
+        ExpressionType::Integer => Ok(ExpressionEvalResult::IntegerResult(to_integer_operand(&value)?)),
+        ExpressionType::Single => single_result(get_double_value(&value).unwrap_or(0.0) as f32),
+        _ => double_result(get_double_value(&value).unwrap_or(0.0))
+    }
+}
+
+/// AST element for `INPUT #` and `LINE INPUT #` which read
+/// variables from a file opened for input.
+/// Example:
+/// ```basic
+/// INPUT #1, NAME$, AGE
+/// LINE INPUT #1, L$
+/// ```
+pub struct GwInputFile {
+    file_number: Box<dyn GwExpression>,
+    variables: Vec<Box<dyn GwAssignableExpression>>,
+    /// `LINE INPUT #` reads a complete line in a string variable
+    whole_line: bool
+}
+
+impl GwInputFile {
+    pub fn new(file_number: Box<dyn GwExpression>,
+               variables: Vec<Box<dyn GwAssignableExpression>>) -> GwInputFile {
+        GwInputFile { file_number, variables, whole_line: false }
+    }
+
+    pub fn line_input(file_number: Box<dyn GwExpression>,
+                      variable: Box<dyn GwAssignableExpression>) -> GwInputFile {
+        GwInputFile { file_number, variables: vec![variable], whole_line: true }
+    }
+
+    fn read_variables(&self, context: &mut EvaluationContext) -> Result<(), GwError> {
+        let number = evaluate_file_number(self.file_number.as_ref(), context)?;
+        for variable in &self.variables {
+            let var_type = variable.get_type(context);
+            let file = context.get_open_file(number)?;
+            let value = if self.whole_line {
+                if var_type != ExpressionType::String {
+                    return Err(GwError::TypeMismatch);
+                }
+                ExpressionEvalResult::StringResult(file.read_line()?)
+            } else {
+                convert_input_item(file.read_item(var_type == ExpressionType::String)?, var_type)?
+            };
+            variable.assign_value(value, context)?;
+        }
+        Ok(())
+    }
+}
+
+impl GwInstruction for GwInputFile {
+    fn eval (&self,
+             _line: i16,
+             _argument: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        check_result![ self.read_variables(context) ];
+        InstructionResult::EvaluateNext
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        if self.whole_line {
+            buffer.push_str("LINE ");
+        }
+        buffer.push_str("INPUT ");
+        fill_file_number_structure_string(self.file_number.as_ref(), buffer);
+        for variable in &self.variables {
+            buffer.push_str(", ");
+            variable.fill_structure_string(buffer);
+        }
+    }
+}
+
+/// AST element for the `FIELD` statement, which binds string
+/// variables to consecutive parts of the record buffer of a
+/// random file.
+/// Example:
+/// ```basic
+/// FIELD #1, 20 AS NAME$, 4 AS PRICE$
+/// ```
+pub struct GwField {
+    file_number: Box<dyn GwExpression>,
+    fields: Vec<(Box<dyn GwExpression>, Box<dyn GwAssignableExpression>)>
+}
+
+impl GwField {
+    pub fn new(file_number: Box<dyn GwExpression>,
+               fields: Vec<(Box<dyn GwExpression>, Box<dyn GwAssignableExpression>)>) -> GwField {
+        GwField { file_number, fields }
+    }
+
+    fn bind_fields(&self, context: &mut EvaluationContext) -> Result<(), GwError> {
+        let number = evaluate_file_number(self.file_number.as_ref(), context)?;
+        let mut offset = 0;
+        for (width_expr, variable) in &self.fields {
+            let width = match to_integer_operand(&width_expr.eval(context)?)? {
+                width @ 0..=255 => width as usize,
+                _ => return Err(GwError::IllegalFunctionCall)
+            };
+            if variable.get_type(context) != ExpressionType::String {
+                return Err(GwError::TypeMismatch);
+            }
+            let field_variable = variable.field_variable(context)?;
+            context.bind_field_variable(number, offset, width, field_variable)?;
+            offset += width;
+        }
+        Ok(())
+    }
+}
+
+impl GwInstruction for GwField {
+    fn eval (&self,
+             _line: i16,
+             _argument: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        check_result![ self.bind_fields(context) ];
+        InstructionResult::EvaluateNext
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        buffer.push_str("FIELD ");
+        fill_file_number_structure_string(self.file_number.as_ref(), buffer);
+        for (width_expr, variable) in &self.fields {
+            buffer.push_str(", ");
+            width_expr.fill_structure_string(buffer);
+            buffer.push_str(" AS ");
+            variable.fill_structure_string(buffer);
+        }
+    }
+}
+
+/// AST element for `GET` and `PUT`, which read and write the
+/// records of a random file. Without a record number the record
+/// after the last one used is read or written.
+/// Example:
+/// ```basic
+/// GET #1, 12
+/// PUT #1
+/// ```
+pub struct GwFileRecord {
+    file_number: Box<dyn GwExpression>,
+    record_number: Option<Box<dyn GwExpression>>,
+    /// `PUT` writes the record
+    put: bool
+}
+
+impl GwFileRecord {
+    pub fn get(file_number: Box<dyn GwExpression>,
+               record_number: Option<Box<dyn GwExpression>>) -> GwFileRecord {
+        GwFileRecord { file_number, record_number, put: false }
+    }
+
+    pub fn put(file_number: Box<dyn GwExpression>,
+               record_number: Option<Box<dyn GwExpression>>) -> GwFileRecord {
+        GwFileRecord { file_number, record_number, put: true }
+    }
+
+    fn transfer(&self, context: &mut EvaluationContext) -> Result<(), GwError> {
+        let number = evaluate_file_number(self.file_number.as_ref(), context)?;
+        let record_number = match &self.record_number {
+            Some(record_expr) => {
+                let value = record_expr.eval(context)?;
+                match get_double_value(&value).ok_or(GwError::TypeMismatch)?.round() {
+                    record if record >= 1.0 && record <= MAX_RECORD_NUMBER as f64 => Some(record as usize),
+                    _ => return Err(GwError::BadRecordNumber)
+                }
+            }
+            None => None
+        };
+        if self.put {
+            context.write_file_record(number, record_number)
+        } else {
+            context.read_file_record(number, record_number)
+        }
+    }
+}
+
+impl GwInstruction for GwFileRecord {
+    fn eval (&self,
+             _line: i16,
+             _argument: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        check_result![ self.transfer(context) ];
+        InstructionResult::EvaluateNext
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        buffer.push_str(if self.put { "PUT " } else { "GET " });
+        fill_file_number_structure_string(self.file_number.as_ref(), buffer);
+        if let Some(record_expr) = &self.record_number {
+            buffer.push_str(", ");
+            record_expr.fill_structure_string(buffer);
+        }
+    }
+}
+
+/// Text justified to the left or right in the given width,
+/// longer texts are cut on the right
+fn justify_text(text: &str, width: usize, right: bool) -> String {
+    let cut: String = text.chars().take(width).collect();
+    let padding = " ".repeat(width - cut.chars().count());
+    if right { padding + &cut } else { cut + &padding }
+}
+
+/// AST element for `LSET` and `RSET`, which justify a string in
+/// the space of a string variable without changing its length.
+/// For field variables the record buffer is changed too.
+/// Example:
+/// ```basic
+/// LSET NAME$ = "SCREWDRIVER"
+/// RSET PRICE$ = MKS$(1.25)
+/// ```
+pub struct GwSetStr {
+    target: Box<dyn GwAssignableExpression>,
+    value_expr: Box<dyn GwExpression>,
+    /// `RSET` justifies to the right
+    right: bool
+}
+
+impl GwSetStr {
+    pub fn lset(target: Box<dyn GwAssignableExpression>,
+                value_expr: Box<dyn GwExpression>) -> GwSetStr {
+        GwSetStr { target, value_expr, right: false }
+    }
+
+    pub fn rset(target: Box<dyn GwAssignableExpression>,
+                value_expr: Box<dyn GwExpression>) -> GwSetStr {
+        GwSetStr { target, value_expr, right: true }
+    }
+
+    fn set(&self, context: &mut EvaluationContext) -> Result<(), GwError> {
+        if self.target.get_type(context) != ExpressionType::String {
+            return Err(GwError::TypeMismatch);
+        }
+        let value = self.value_expr.eval(context)?;
+        let text = value.assume_string_value()?;
+        let field_variable = self.target.field_variable(context)?;
+        let justified = match context.find_field(&field_variable) {
+            Some((number, offset, width)) => {
+                let justified = justify_text(text, width, self.right);
+                context.get_open_file(number)?.set_field_text(offset, &justified);
+                justified
+            }
+            None => {
+                let current = self.target.eval(context)?;
+                justify_text(text, current.assume_string_value()?.chars().count(), self.right)
+            }
+        };
+        self.target.assign_value(ExpressionEvalResult::StringResult(justified), context)
+    }
+}
+
+impl GwInstruction for GwSetStr {
+    fn eval (&self,
+             _line: i16,
+             _argument: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        check_result![ self.set(context) ];
+        InstructionResult::EvaluateNext
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        buffer.push_str(if self.right { "RSET " } else { "LSET " });
+        self.target.fill_structure_string(buffer);
+        buffer.push_str(" = ");
+        self.value_expr.fill_structure_string(buffer);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GwFileFunctionKind {
+    Eof,
+    Lof,
+    Loc
+}
+
+/// AST element for the `EOF(n)`, `LOF(n)` and `LOC(n)` functions
+pub struct GwFileFunction {
+    kind: GwFileFunctionKind,
+    file_number: Box<dyn GwExpression>
+}
+
+impl GwFileFunction {
+    pub fn new(kind: GwFileFunctionKind, file_number: Box<dyn GwExpression>) -> GwFileFunction {
+        GwFileFunction { kind, file_number }
+    }
+}
+
+impl GwExpression for GwFileFunction {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let number = evaluate_file_number(self.file_number.as_ref(), context)?;
+        let file = context.get_open_file(number)?;
+        match self.kind {
+            GwFileFunctionKind::Eof =>
+                Ok(ExpressionEvalResult::IntegerResult(if file.is_at_end()? { -1 } else { 0 })),
+            GwFileFunctionKind::Lof =>
+                Ok(ExpressionEvalResult::SingleResult(file.length() as f32)),
+            GwFileFunctionKind::Loc =>
+                Ok(ExpressionEvalResult::SingleResult(file.record_position() as f32))
+        }
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push_str(match self.kind {
+            GwFileFunctionKind::Eof => "EOF(",
+            GwFileFunctionKind::Lof => "LOF(",
+            GwFileFunctionKind::Loc => "LOC("
+        });
+        self.file_number.fill_structure_string(buffer);
+        buffer.push(')');
+    }
+}
+
+#[cfg(test)]
+mod file_instr_tests {
+    use super::*;
+
+    #[test]
+    fn it_formats_write_items() {
+        assert_eq!("\"A, B\"", format_write_item(&ExpressionEvalResult::StringResult("A, B".to_string())));
+        assert_eq!("12", format_write_item(&ExpressionEvalResult::IntegerResult(12)));
+        assert_eq!("-1.5", format_write_item(&ExpressionEvalResult::SingleResult(-1.5)));
+    }
+
+    #[test]
+    fn it_justifies_text_for_lset_and_rset() {
+        assert_eq!("AB   ", justify_text("AB", 5, false));
+        assert_eq!("   AB", justify_text("AB", 5, true));
+        assert_eq!("ABC", justify_text("ABCDE", 3, true));
+        assert_eq!("", justify_text("ABCDE", 0, false));
+    }
+
+    #[test]
+    fn it_converts_input_items_to_the_variable_type() {
+        assert_eq!(Ok(ExpressionEvalResult::IntegerResult(3)),
+                   convert_input_item("2.5".to_string(), ExpressionType::Integer));
+        assert_eq!(Ok(ExpressionEvalResult::SingleResult(0.0)),
+                   convert_input_item("".to_string(), ExpressionType::Single));
+        assert_eq!(Ok(ExpressionEvalResult::StringResult("12".to_string())),
+                   convert_input_item("12".to_string(), ExpressionType::String));
+        assert_eq!(Err(GwError::Overflow),
+                   convert_input_item("40000".to_string(), ExpressionType::Integer));
+    }
+}
@@ -0,0 +1,454 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use super::GwError;
+use super::string_func::{ bytes_to_string, string_to_bytes };
+
+/// Largest file number accepted by `OPEN`
+pub const MAX_FILE_NUMBER: u8 = 15;
+/// Width of the lines written with `PRINT #`
+pub const FILE_TEXT_WIDTH: usize = 255;
+/// Size of the records counted by `LOC` in sequential files
+const SEQUENTIAL_RECORD_SIZE: usize = 128;
+/// Ctrl-Z, marks the end of text files
+const END_OF_FILE_MARK: u8 = 0x1A;
+/// Record length of the files opened without `LEN=`
+pub const DEFAULT_RECORD_LENGTH: usize = 128;
+/// Largest record number accepted by `GET` and `PUT`
+pub const MAX_RECORD_NUMBER: usize = 16_777_215;
+
+/// Storage provided by the host for the files used by `OPEN`.
+/// File names are given as written in the program
+pub trait FileSystem {
+    /// Gets the contents of an existing file
+    fn read_file(&self, file_name: &str) -> Result<Vec<u8>, GwError>;
+    /// Creates or replaces a file with the given contents
+    fn write_file(&mut self, file_name: &str, contents: &[u8]) -> Result<(), GwError>;
+    /// Adds to the end of a file, creating it if it does not exist
+    fn append_file(&mut self, file_name: &str, contents: &[u8]) -> Result<(), GwError>;
+}
+
+/// File system kept in memory, the clones of a `MemoryFileSystem`
+/// share the same files
+#[derive(Clone, Default)]
+pub struct MemoryFileSystem {
+    files: Rc<RefCell<HashMap<String, Vec<u8>>>>
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> MemoryFileSystem {
+        MemoryFileSystem::default()
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn read_file(&self, file_name: &str) -> Result<Vec<u8>, GwError> {
+        self.files.borrow().get(file_name).cloned().ok_or(GwError::FileNotFound)
+    }
+
+    fn write_file(&mut self, file_name: &str, contents: &[u8]) -> Result<(), GwError> {
+        self.files.borrow_mut().insert(file_name.to_string(), contents.to_vec());
+        Ok(())
+    }
+
+    fn append_file(&mut self, file_name: &str, contents: &[u8]) -> Result<(), GwError> {
+        self.files.borrow_mut()
+            .entry(file_name.to_string())
+            .or_default()
+            .extend_from_slice(contents);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileMode {
+    Input,
+    Output,
+    Append,
+    Random
+}
+
+impl FileMode {
+    /// Mode given by the first letter of the mode string of the
+    /// short form of `OPEN`, for example `OPEN "O", #1, "DATA.TXT"`
+    pub fn from_mode_string(mode: &str) -> Result<FileMode, GwError> {
+        match mode.chars().next().map(|c| c.to_ascii_uppercase()) {
+            Some('I') => Ok(FileMode::Input),
+            Some('O') => Ok(FileMode::Output),
+            Some('A') => Ok(FileMode::Append),
+            Some('R') => Ok(FileMode::Random),
+            _ => Err(GwError::BadFileMode)
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileMode::Input => "INPUT",
+            FileMode::Output => "OUTPUT",
+            FileMode::Append => "APPEND",
+            FileMode::Random => "RANDOM"
+        }
+    }
+}
+
+/// Variable bound to a part of the record buffer of a random file
+/// by `FIELD`
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldVariable {
+    /// Qualified name of a simple variable
+    Simple(String),
+    /// Qualified name and indices of an array element
+    ArrayEntry(String, Vec<usize>)
+}
+
+/// Part of the record buffer used by a field variable
+pub struct FileField {
+    pub variable: FieldVariable,
+    pub offset: usize,
+    pub width: usize
+}
+
+/// File opened with `OPEN`. Files opened for input or as random
+/// files are read completely when opened and the text written to
+/// output files goes to the file system right away
+pub struct OpenFile {
+    pub name: String,
+    pub mode: FileMode,
+    /// Contents of a file opened for input or as a random file
+    contents: Vec<u8>,
+    /// Number of bytes read or written
+    position: usize,
+    /// Size of the file in bytes
+    length: usize,
+    /// 0-based column after the last text written
+    column: usize,
+    /// Record buffer of a random file, used by `GET`, `PUT` and `FIELD`
+    record_buffer: Vec<u8>,
+    /// Number of the last record read or written
+    record_number: usize,
+    /// The last `GET` was past the end of a random file
+    past_end: bool,
+    pub fields: Vec<FileField>
+}
+
+impl OpenFile {
+    /// Opens a file, `record_length` is the size of the records
+    /// of random files
+    pub fn open(name: &str,
+                mode: FileMode,
+                record_length: usize,
+                file_system: &mut dyn FileSystem) -> Result<OpenFile, GwError> {
+        let mut contents = vec![];
+        let mut length = 0;
+        match mode {
+            FileMode::Input => {
+                contents = file_system.read_file(name)?;
+                length = contents.len();
+            }
+            FileMode::Random => {
+                match file_system.read_file(name) {
+                    Ok(existing) => { contents = existing; }
+                    Err(GwError::FileNotFound) => file_system.write_file(name, &[])?,
+                    Err(error) => return Err(error)
+                }
+                length = contents.len();
+            }
+            FileMode::Output => {
+                file_system.write_file(name, &[])?;
+            }
+            FileMode::Append => {
+                match file_system.read_file(name) {
+                    Ok(existing) => { length = existing.len(); }
+                    Err(GwError::FileNotFound) => {}
+                    Err(error) => return Err(error)
+                }
+            }
+        }
+        Ok(OpenFile {
+            name: name.to_string(),
+            mode,
+            contents,
+            position: 0,
+            length,
+            column: 0,
+            record_buffer: vec![0; record_length],
+            record_number: 0,
+            past_end: false,
+            fields: vec![]
+        })
+    }
+
+    pub fn write(&mut self, text: &str, file_system: &mut dyn FileSystem) -> Result<(), GwError> {
+        if matches!(self.mode, FileMode::Input | FileMode::Random) {
+            return Err(GwError::BadFileMode);
+        }
+        let bytes = string_to_bytes(text);
+        file_system.append_file(&self.name, &bytes)?;
+        self.position += bytes.len();
+        self.length += bytes.len();
+        self.column = match text.rfind(['\r', '\n']) {
+            Some(line_end) => text[line_end + 1..].chars().count(),
+            None => self.column + text.chars().count()
+        };
+        Ok(())
+    }
+
+    /// 1-based column for the next text written, as given by
+    /// `Console::current_text_column` for the screen
+    pub fn current_text_column(&self) -> usize {
+        self.column + 1
+    }
+
+    /// Size of the file in bytes, as returned by `LOF`
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Position returned by `LOC`, the number of the last record
+    /// of a random file or the number of 128 bytes records read or
+    /// written in a sequential file
+    pub fn record_position(&self) -> usize {
+        match self.mode {
+            FileMode::Random => self.record_number,
+            _ => self.position.div_ceil(SEQUENTIAL_RECORD_SIZE)
+        }
+    }
+
+    /// Implements `EOF`, which is only valid for files opened for
+    /// input or as random files
+    pub fn is_at_end(&self) -> Result<bool, GwError> {
+        match self.mode {
+            FileMode::Input => Ok(self.peek_byte().is_none()),
+            FileMode::Random => Ok(self.past_end),
+            _ => Err(GwError::BadFileMode)
+        }
+    }
+
+    pub fn record_length(&self) -> usize {
+        self.record_buffer.len()
+    }
+
+    /// Number of the record used by `GET` or `PUT`, the next one
+    /// when no number is given
+    fn select_record(&self, record_number: Option<usize>) -> Result<usize, GwError> {
+        if self.mode != FileMode::Random {
+            return Err(GwError::BadFileMode);
+        }
+        match record_number.unwrap_or(self.record_number + 1) {
+            number @ 1..=MAX_RECORD_NUMBER => Ok(number),
+            _ => Err(GwError::BadRecordNumber)
+        }
+    }
+
+    /// Implements `GET`, reads a record in the record buffer. The
+    /// buffer is filled with zeros past the end of the file
+    pub fn read_record(&mut self, record_number: Option<usize>) -> Result<(), GwError> {
+        let number = self.select_record(record_number)?;
+        let start = (number - 1) * self.record_length();
+        for (index, byte) in self.record_buffer.iter_mut().enumerate() {
+            *byte = self.contents.get(start + index).copied().unwrap_or(0);
+        }
+        self.record_number = number;
+        self.past_end = start >= self.contents.len();
+        Ok(())
+    }
+
+    /// Implements `PUT`, writes the record buffer to the file
+    pub fn write_record(&mut self,
+                        record_number: Option<usize>,
+                        file_system: &mut dyn FileSystem) -> Result<(), GwError> {
+        let number = self.select_record(record_number)?;
+        let start = (number - 1) * self.record_length();
+        let end = start + self.record_length();
+        if self.contents.len() < end {
+            self.contents.resize(end, 0);
+        }
+        self.contents[start..end].copy_from_slice(&self.record_buffer);
+        file_system.write_file(&self.name, &self.contents)?;
+        self.record_number = number;
+        self.length = self.contents.len();
+        Ok(())
+    }
+
+    /// Text of a part of the record buffer
+    pub fn field_text(&self, offset: usize, width: usize) -> String {
+        bytes_to_string(&self.record_buffer[offset..offset + width])
+    }
+
+    /// Replaces a part of the record buffer, `text` has the width of the part
+    pub fn set_field_text(&mut self, offset: usize, text: &str) {
+        let bytes = string_to_bytes(text);
+        self.record_buffer[offset..offset + bytes.len()].copy_from_slice(&bytes);
+    }
+
+    fn peek_byte(&self) -> Option<u8> {
+        self.contents.get(self.position).copied().filter(|byte| *byte != END_OF_FILE_MARK)
+    }
+
+    fn check_can_read(&self) -> Result<(), GwError> {
+        match self.mode {
+            FileMode::Input if self.peek_byte().is_some() => Ok(()),
+            FileMode::Input => Err(GwError::InputPastEnd),
+            _ => Err(GwError::BadFileMode)
+        }
+    }
+
+    /// Takes bytes while `predicate` holds
+    fn read_while<F>(&mut self, predicate: F) -> Vec<u8> where F: Fn(u8) -> bool {
+        let mut result = vec![];
+        while let Some(byte) = self.peek_byte().filter(|byte| predicate(*byte)) {
+            result.push(byte);
+            self.position += 1;
+        }
+        result
+    }
+
+    /// Skips a CR, LF or CR LF line ending
+    fn skip_line_end(&mut self) {
+        if self.peek_byte() == Some(b'\r') {
+            self.position += 1;
+        }
+        if self.peek_byte() == Some(b'\n') {
+            self.position += 1;
+        }
+    }
+
+    /// Skips the spaces and the comma or line end after an item
+    fn skip_item_separator(&mut self) {
+        self.read_while(|byte| byte == b' ');
+        if self.peek_byte() == Some(b',') {
+            self.position += 1;
+        } else {
+            self.skip_line_end();
+        }
+    }
+
+    /// Reads a line without its line ending, for `LINE INPUT #`
+    pub fn read_line(&mut self) -> Result<String, GwError> {
+        self.check_can_read()?;
+        let line = self.read_while(|byte| byte != b'\r' && byte != b'\n');
+        self.skip_line_end();
+        Ok(bytes_to_string(&line))
+    }
+
+    /// Reads the text of the next item for `INPUT #`. Items are
+    /// separated by commas or line endings and strings can be
+    /// quoted to include commas. Numbers also end at a space
+    pub fn read_item(&mut self, is_string: bool) -> Result<String, GwError> {
+        self.read_while(|byte| matches!(byte, b' ' | b'\r' | b'\n'));
+        self.check_can_read()?;
+        let item = if is_string && self.peek_byte() == Some(b'"') {
+            self.position += 1;
+            let quoted = self.read_while(|byte| byte != b'"');
+            // Anything between the closing quote and the separator is ignored
+            self.read_while(|byte| !matches!(byte, b',' | b'\r' | b'\n'));
+            quoted
+        } else if is_string {
+            let mut unquoted = self.read_while(|byte| !matches!(byte, b',' | b'\r' | b'\n'));
+            while unquoted.last() == Some(&b' ') {
+                unquoted.pop();
+            }
+            unquoted
+        } else {
+            self.read_while(|byte| !matches!(byte, b' ' | b',' | b'\r' | b'\n'))
+        };
+        self.skip_item_separator();
+        Ok(bytes_to_string(&item))
+    }
+}
+
+#[cfg(test)]
+mod files_tests {
+    use super::*;
+
+    fn input_file(contents: &str) -> OpenFile {
+        let mut file_system = MemoryFileSystem::new();
+        file_system.write_file("TEST.DAT", contents.as_bytes()).unwrap();
+        OpenFile::open("TEST.DAT", FileMode::Input, DEFAULT_RECORD_LENGTH, &mut file_system).unwrap()
+    }
+
+    #[test]
+    fn it_reads_quoted_and_unquoted_items() -> Result<(), GwError> {
+        let mut file = input_file("\"SMITH, JOHN\",  42 ,ACME INC  \r\n-1.5E3 7\r\n");
+        assert_eq!("SMITH, JOHN", file.read_item(true)?);
+        assert_eq!("42", file.read_item(false)?);
+        assert_eq!("ACME INC", file.read_item(true)?);
+        assert_eq!("-1.5E3", file.read_item(false)?);
+        assert_eq!("7", file.read_item(false)?);
+        assert_eq!(Ok(true), file.is_at_end());
+        assert_eq!(Err(GwError::InputPastEnd), file.read_item(false));
+        Ok(())
+    }
+
+    #[test]
+    fn it_reads_lines() -> Result<(), GwError> {
+        let mut file = input_file("FIRST, LINE\r\n\nLAST\x1A");
+        assert_eq!("FIRST, LINE", file.read_line()?);
+        assert_eq!("", file.read_line()?);
+        assert_eq!("LAST", file.read_line()?);
+        assert_eq!(Ok(true), file.is_at_end());
+        assert_eq!(Err(GwError::InputPastEnd), file.read_line());
+        Ok(())
+    }
+
+    #[test]
+    fn it_writes_and_appends_to_files() -> Result<(), GwError> {
+        let mut file_system = MemoryFileSystem::new();
+        let mut file = OpenFile::open("OUT.TXT", FileMode::Output, DEFAULT_RECORD_LENGTH, &mut file_system)?;
+        file.write("HELLO\r\nAB", &mut file_system)?;
+        assert_eq!(3, file.current_text_column());
+        assert_eq!(Err(GwError::BadFileMode), file.read_line());
+
+        let mut file = OpenFile::open("OUT.TXT", FileMode::Append, DEFAULT_RECORD_LENGTH, &mut file_system)?;
+        assert_eq!(9, file.length());
+        file.write("C", &mut file_system)?;
+        assert_eq!(10, file.length());
+        assert_eq!(1, file.record_position());
+        assert_eq!(b"HELLO\r\nABC".to_vec(), file_system.read_file("OUT.TXT")?);
+        assert_eq!(Err(GwError::FileNotFound),
+                   OpenFile::open("MISSING.TXT", FileMode::Input, DEFAULT_RECORD_LENGTH, &mut file_system)
+                       .map(|_| ()));
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_one_byte_for_each_character() -> Result<(), GwError> {
+        let mut file_system = MemoryFileSystem::new();
+        let mut file = OpenFile::open("CHARS.TXT", FileMode::Output, DEFAULT_RECORD_LENGTH, &mut file_system)?;
+        file.write("CAF\u{C9},\u{C8}\r\n", &mut file_system)?;
+        assert_eq!(8, file.length());
+        assert_eq!(b"CAF\xC9,\xC8\r\n".to_vec(), file_system.read_file("CHARS.TXT")?);
+
+        let mut file = OpenFile::open("CHARS.TXT", FileMode::Input, DEFAULT_RECORD_LENGTH, &mut file_system)?;
+        assert_eq!("CAF\u{C9}", file.read_item(true)?);
+        assert_eq!("\u{C8}", file.read_item(true)?);
+        let mut file = OpenFile::open("CHARS.TXT", FileMode::Input, DEFAULT_RECORD_LENGTH, &mut file_system)?;
+        assert_eq!("CAF\u{C9},\u{C8}", file.read_line()?);
+        Ok(())
+    }
+
+    #[test]
+    fn it_reads_and_writes_records() -> Result<(), GwError> {
+        let mut file_system = MemoryFileSystem::new();
+        let mut file = OpenFile::open("STOCK.DAT", FileMode::Random, 4, &mut file_system)?;
+        assert_eq!(0, file.length());
+        file.set_field_text(0, "AB");
+        file.write_record(Some(2), &mut file_system)?;
+        file.set_field_text(2, "CD");
+        file.write_record(None, &mut file_system)?;
+        assert_eq!(b"\0\0\0\0AB\0\0ABCD".to_vec(), file_system.read_file("STOCK.DAT")?);
+        assert_eq!(3, file.record_position());
+
+        file.read_record(Some(2))?;
+        assert_eq!("AB\0\0", file.field_text(0, 4));
+        assert_eq!(Ok(false), file.is_at_end());
+        file.read_record(None)?;
+        assert_eq!("CD", file.field_text(2, 2));
+        file.read_record(None)?;
+        assert_eq!("\0\0\0\0", file.field_text(0, 4));
+        assert_eq!(Ok(true), file.is_at_end());
+        assert_eq!(Err(GwError::BadRecordNumber), file.read_record(Some(0)));
+        assert_eq!(Err(GwError::BadFileMode), file.read_line());
+        Ok(())
+    }
+}
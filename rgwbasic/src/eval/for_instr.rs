@@ -4,6 +4,7 @@ use super::{GwExpression, GwInstruction, GwProgram, EvaluationContext,
             LineExecutionArgument,
             InstructionResult,
             ExpressionEvalResult,
+            GwError,
             evaluate_to_usize };
 
 pub struct GwFor {
@@ -16,7 +17,7 @@ pub struct GwFor {
 impl GwFor {
     fn get_increment(&self,
                      context: &mut EvaluationContext)
-                     -> Result<i16, String> {
+                     -> Result<i16, GwError> {
         match &self.step {
             Some(expr) => {
                 let eval_result = expr.eval(context)?;
@@ -29,7 +30,7 @@ impl GwFor {
     fn try_next_iteration(&self,
                           next_line : i16,
                           context: &mut EvaluationContext)
-                          -> Result<InstructionResult, String> {
+                          -> Result<InstructionResult, GwError> {
         let n_value = get_as_integer(& context.lookup_variable(&self.variable))? as usize;
         let from_i_value = evaluate_to_usize(&self.from, context)?;
         let to_i_value = evaluate_to_usize(&self.to, context)?;
@@ -59,7 +60,7 @@ impl GwInstruction for GwFor {
         } else { //if let Some(ref real_lines) = context.real_lines {
             let index_of_next = find_next(line, &program.real_lines);
             if index_of_next == -1 {
-                return InstructionResult::EvaluateToError(String::from("NEXT WITHOUT FOR"));
+                return InstructionResult::EvaluateToError(GwError::ForWithoutNext);
             } else {
                 context.pair_instruction_table.insert(line, index_of_next);
                 context.pair_instruction_table.insert(index_of_next, line);
@@ -73,15 +74,16 @@ impl GwInstruction for GwFor {
                 Err(err) => InstructionResult::EvaluateToError(err)
             }
         } else {
-            if let Ok(result) = self.from.eval(context) {
-                let assign_result = context.set_variable(&self.variable, &result);
-                if assign_result.is_ok() {
-                    InstructionResult::EvaluateNext
-                } else {
-                    InstructionResult::EvaluateToError(assign_result.err().unwrap().to_string())
+            match self.from.eval(context) {
+                Ok(result) => {
+                    let assign_result = context.set_variable(&self.variable, &result);
+                    if assign_result.is_ok() {
+                        InstructionResult::EvaluateNext
+                    } else {
+                        InstructionResult::EvaluateToError(assign_result.err().unwrap())
+                    }
                 }
-            } else {
-                todo!();
+                Err(error) => InstructionResult::EvaluateToError(error)
             }
         }
     }
@@ -93,7 +95,16 @@ impl GwInstruction for GwFor {
 //    }
 
     fn fill_structure_string(&self, buffer : &mut String) {
-        buffer.push_str(&"FOR");
+        buffer.push_str("FOR ");
+        buffer.push_str(&self.variable);
+        buffer.push_str(" = ");
+        self.from.fill_structure_string(buffer);
+        buffer.push_str(" TO ");
+        self.to.fill_structure_string(buffer);
+        if let Some(step) = &self.step {
+            buffer.push_str(" STEP ");
+            step.fill_structure_string(buffer);
+        }
     }
 }
 
@@ -139,7 +150,7 @@ impl GwInstruction for GwNext {
                 *corresponding_for,
                 LineExecutionArgument::NextIteration)
         } else {
-            InstructionResult::EvaluateToError(String::from("NEXT WITHOUT FOR"))
+            InstructionResult::EvaluateToError(GwError::NextWithoutFor)
         }
     }
 
@@ -147,15 +158,19 @@ impl GwInstruction for GwNext {
 
     fn fill_structure_string(&self, buffer : &mut String) {
         buffer.push_str(&"NEXT");
+        if let Some(variable) = &self.variable {
+            buffer.push(' ');
+            buffer.push_str(variable);
+        }
     }
 }
 
-fn get_as_integer(value: &Option<&ExpressionEvalResult>) -> Result<i16, String> {
+fn get_as_integer(value: &Option<&ExpressionEvalResult>) -> Result<i16, GwError> {
     match value {
         Some(ExpressionEvalResult::IntegerResult(int_value)) =>  Ok(*int_value),
         Some(ExpressionEvalResult::SingleResult(single_value)) => Ok(*single_value as i16),
         Some(ExpressionEvalResult::DoubleResult(double_value)) =>  Ok(*double_value as i16),
-        _ => Err("Type mismatch".to_string())
+        _ => Err(GwError::TypeMismatch)
     }
 }
 
@@ -169,8 +184,8 @@ mod for_eval_tests {
     #[test]
     fn it_iterates_for_loop() {
         test_for_loop("i",
-                      GwIntegerLiteral { value: 1 },
-                      GwIntegerLiteral { value: 5 },
+                      GwIntegerLiteral::with_value(1),
+                      GwIntegerLiteral::with_value(5),
                       None,
                       vec![1, 2, 3, 4, 5]);
     }
@@ -178,18 +193,18 @@ mod for_eval_tests {
     #[test]
     fn it_iterates_for_loop_with_step() {
         test_for_loop("i",
-                      GwIntegerLiteral { value: 1 },
-                      GwIntegerLiteral { value: 5 },
-                      Some(GwIntegerLiteral { value: 1 }),
+                      GwIntegerLiteral::with_value(1),
+                      GwIntegerLiteral::with_value(5),
+                      Some(GwIntegerLiteral::with_value(1)),
                       vec![1, 2, 3, 4, 5]);
     }
 
     #[test]
     fn it_iterates_for_loop_with_step_2() {
         test_for_loop("i",
-                      GwIntegerLiteral { value: 0 },
-                      GwIntegerLiteral { value: 6 },
-                      Some(GwIntegerLiteral { value: 2 }),
+                      GwIntegerLiteral::with_value(0),
+                      GwIntegerLiteral::with_value(6),
+                      Some(GwIntegerLiteral::with_value(2)),
                       vec![0, 2, 4, 6]);
     }
 
@@ -197,18 +212,18 @@ mod for_eval_tests {
     #[test]
     fn it_iterates_for_loop_with_step_2_2() {
         test_for_loop("i",
-                      GwIntegerLiteral { value: 0 },
-                      GwIntegerLiteral { value: 5 },
-                      Some(GwIntegerLiteral { value: 2 }),
+                      GwIntegerLiteral::with_value(0),
+                      GwIntegerLiteral::with_value(5),
+                      Some(GwIntegerLiteral::with_value(2)),
                       vec![0, 2, 4]);
     }
 
     #[test]
     fn it_iterates_for_loop_in_reverse() {
         test_for_loop("i",
-                      GwIntegerLiteral { value: 5 },
-                      GwIntegerLiteral { value: 1 },
-                      Some(GwIntegerLiteral { value: -1 }),
+                      GwIntegerLiteral::with_value(5),
+                      GwIntegerLiteral::with_value(1),
+                      Some(GwIntegerLiteral::with_value(-1)),
                       vec![5, 4, 3, 2, 1]);
     }
 
@@ -216,9 +231,9 @@ mod for_eval_tests {
     #[test]
     fn it_iterates_for_loop_in_reverse_2() {
         test_for_loop("i",
-                      GwIntegerLiteral { value: 6 },
-                      GwIntegerLiteral { value: 0 },
-                      Some(GwIntegerLiteral { value: -2 }),
+                      GwIntegerLiteral::with_value(6),
+                      GwIntegerLiteral::with_value(0),
+                      Some(GwIntegerLiteral::with_value(-2)),
                       vec![6, 4, 2, 0]);
     }
 
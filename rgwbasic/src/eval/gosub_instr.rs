@@ -1,5 +1,5 @@
 use super::{ EvaluationContext, LineExecutionArgument,
-             InstructionResult, GwInstruction, GwProgram };
+             InstructionResult, GwInstruction, GwProgram, GwError };
 
 
 /// AST element  for GOSUB subroutine invocation element
@@ -30,13 +30,14 @@ impl GwInstruction for GwGosub {
                 context.push_return(line);
                 InstructionResult::EvaluateLine(real_line)
             } else {
-                InstructionResult::EvaluateToError("Unknown line".into())
+                InstructionResult::EvaluateToError(GwError::UndefinedLineNumber)
             }
         }
     }
 
     fn fill_structure_string(&self, buffer : &mut String) {
         buffer.push_str("GOSUB ");
+        buffer.push_str(&self.line_number.to_string());
     }
 }
 
@@ -66,7 +67,7 @@ impl GwInstruction for GwReturn {
                 line_to_return,
                 LineExecutionArgument::SubReturn)
         } else {
-            InstructionResult::EvaluateToError("RETURN: no place to return".into())
+            InstructionResult::EvaluateToError(GwError::ReturnWithoutGosub)
         }
     }
 
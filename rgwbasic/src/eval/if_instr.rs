@@ -4,24 +4,43 @@ use super::{ EvaluationContext,
              LineExecutionArgument,
              InstructionResult,
              GwInstruction,
-             GwProgram };
+             GwProgram,
+             GwError };
 
 /// AST element for `IF` with line numbers
 /// For example:
 /// ```basic
-/// IF X > 10 THEN 10
+/// IF X > 10 THEN 10 ELSE 20
 /// ```
 pub struct GwIf {
     condition: Box<dyn GwExpression>,
     then_line: i16,
+    else_line: Option<i16>,
 }
 
 impl GwIf {
     pub fn new(condition: Box<dyn GwExpression>, then_line: i16) -> GwIf {
-        return GwIf {
+        GwIf {
             condition,
             then_line,
-        };
+            else_line: None,
+        }
+    }
+
+    pub fn with_else(condition: Box<dyn GwExpression>, then_line: i16, else_line: i16) -> GwIf {
+        GwIf {
+            condition,
+            then_line,
+            else_line: Some(else_line),
+        }
+    }
+}
+
+fn jump_to_line(line: i16, context: &mut EvaluationContext) -> InstructionResult {
+    if let Some(real_line) = context.get_real_line(line) {
+        InstructionResult::EvaluateLine(real_line)
+    } else {
+        InstructionResult::EvaluateToError(GwError::UndefinedLineNumber)
     }
 }
 
@@ -35,23 +54,24 @@ impl GwInstruction for GwIf {
     ) -> InstructionResult {
         match self.condition.eval(context) {
             Ok(eval_result) if eval_result.is_false() => {
-                InstructionResult::EvaluateNext
-            }
-            Ok(_) => {
-                if let Some(real_line) = context.get_real_line(self.then_line) {
-                    InstructionResult::EvaluateLine(real_line)
+                if let Some(else_line) = self.else_line {
+                    jump_to_line(else_line, context)
                 } else {
-                    panic!("Jumping to a non-existing line!");
+                    InstructionResult::EvaluateNext
                 }
             }
-            Err(err) => InstructionResult::EvaluateToError(err.into())
+            Ok(_) => jump_to_line(self.then_line, context),
+            Err(err) => InstructionResult::EvaluateToError(err)
         }
     }
 
     fn fill_structure_string(&self, buffer: &mut String) {
         buffer.push_str(&"IF ");
         self.condition.fill_structure_string(buffer);
-        buffer.push_str(format!(" {}", self.then_line).as_str());
+        buffer.push_str(format!(" THEN {}", self.then_line).as_str());
+        if let Some(else_line) = self.else_line {
+            buffer.push_str(format!(" ELSE {}", else_line).as_str());
+        }
     }
 }
 
@@ -59,11 +79,12 @@ impl GwInstruction for GwIf {
 /// AST element for `IF` with nested statements
 /// For example:
 /// ```basic
-/// IF X > 10 THEN PRINT "a" : PRINT "b"
+/// IF X > 10 THEN PRINT "a" : PRINT "b" ELSE PRINT "c"
 /// ```
 pub struct GwIfWithStats {
     condition: Box<dyn GwExpression>,
-    stats: Vec<Rc<dyn GwInstruction>>
+    stats: Vec<Rc<dyn GwInstruction>>,
+    else_stats: Option<Vec<Rc<dyn GwInstruction>>>
 }
 
 impl GwIfWithStats {
@@ -71,7 +92,45 @@ impl GwIfWithStats {
                stats: Vec<Rc<dyn GwInstruction>>) -> GwIfWithStats {
         GwIfWithStats {
             condition,
-            stats
+            stats,
+            else_stats: None
+        }
+    }
+
+    pub fn with_else(condition: Box<dyn GwExpression>,
+                     stats: Vec<Rc<dyn GwInstruction>>,
+                     else_stats: Option<Vec<Rc<dyn GwInstruction>>>) -> GwIfWithStats {
+        GwIfWithStats {
+            condition,
+            stats,
+            else_stats
+        }
+    }
+}
+
+fn eval_stats(stats: &[Rc<dyn GwInstruction>],
+              line: i16,
+              context : &mut EvaluationContext,
+              program: &mut GwProgram) -> InstructionResult {
+    for stat in stats {
+        let stat_result = stat.eval(line,
+                                    LineExecutionArgument::Empty,
+                                    context,
+                                    program);
+        if !matches!(stat_result, InstructionResult::EvaluateNext) {
+            return stat_result;
+        }
+    }
+    InstructionResult::EvaluateNext
+}
+
+fn fill_stats_structure_string(stats: &[Rc<dyn GwInstruction>], buffer : &mut String) {
+    let mut c = stats.len();
+    for stat in stats {
+        stat.fill_structure_string(buffer);
+        c -= 1;
+        if c != 0 {
+            buffer.push_str(" : ");
         }
     }
 }
@@ -84,19 +143,14 @@ impl GwInstruction for GwIfWithStats {
              program: &mut GwProgram) -> InstructionResult {
         match self.condition.eval(context) {
             Ok(eval_result) if eval_result.is_false() => {
-                InstructionResult::EvaluateNext
-            }
-            Ok(_) => {
-                let mut last_stat_result = InstructionResult::EvaluateNext;
-                for stat in &self.stats {
-                    last_stat_result = stat.eval(line,
-                                                 LineExecutionArgument::Empty,
-                                                 context,
-                                                 program);
+                if let Some(else_stats) = &self.else_stats {
+                    eval_stats(else_stats, line, context, program)
+                } else {
+                    InstructionResult::EvaluateNext
                 }
-                last_stat_result
             }
-            Err(err) => InstructionResult::EvaluateToError(err.into())
+            Ok(_) => eval_stats(&self.stats, line, context, program),
+            Err(err) => InstructionResult::EvaluateToError(err)
         }
     }
 
@@ -104,15 +158,11 @@ impl GwInstruction for GwIfWithStats {
         buffer.push_str("IF ");
         self.condition.fill_structure_string(buffer);
         buffer.push_str(" THEN ");
-        let mut c = self.stats.len();
-        for stat in &self.stats {
-            stat.fill_structure_string(buffer);
-            c -= 1;
-            if c != 0 {
-                buffer.push_str(" : ");
-            }
+        fill_stats_structure_string(&self.stats, buffer);
+        if let Some(else_stats) = &self.else_stats {
+            buffer.push_str(" ELSE ");
+            fill_stats_structure_string(else_stats, buffer);
         }
-           
     }
 }
 
@@ -210,4 +260,30 @@ mod if_stat_tests {
             _ => Err("Unexpected eval result")
         }     
     }
+
+    #[test]
+    fn it_executes_else_stats() -> Result<(), & 'static str>{
+        let mut ctx = empty_context();
+        let mut program = empty_program();
+        let if_stat = GwIfWithStats::with_else(
+            Box::new(GwIntegerLiteral::with_value(0)),
+            vec![ Rc::new(GwStop {}) ],
+            Some(vec![
+                Rc::new(
+                    GwAssign {
+                        variable: "x".into(),
+                        expression: Box::new(GwIntegerLiteral::with_value(123))
+                    })
+            ])
+        );
+
+        let eval_result =
+            if_stat.eval(0, LineExecutionArgument::Empty, &mut ctx, &mut program);
+
+        assert!(ctx.lookup_variable("x").is_some());
+        match eval_result {
+            InstructionResult::EvaluateNext => Ok(()),
+            _ => Err("Unexpected eval result")
+        }
+    }
 }
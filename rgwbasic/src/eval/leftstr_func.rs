@@ -2,6 +2,7 @@ use super::{ GwExpression,
              EvaluationContext,
              ExpressionEvalResult,
              EvaluationError };
+use super::string_func::eval_integer_argument;
 
 pub struct GwLeftStr {
     string_expr: Box<dyn GwExpression>,
@@ -22,14 +23,9 @@ impl GwExpression for GwLeftStr {
             -> Result<ExpressionEvalResult, EvaluationError> {
         let binding = self.string_expr.eval(context)?;
         let string_value = binding.assume_string_value()?;
-        let position_value = self.position_expr.eval(context)?.as_i16()?;
-
-        if position_value >= 0 {
-            Ok(ExpressionEvalResult::StringResult(
-                string_value[0..(position_value as usize)].to_string()))
-        } else {
-            Err("Illegal function call".to_string())
-        }
+        let position_value = eval_integer_argument(self.position_expr.as_ref(), context, 0, 255)?;
+        Ok(ExpressionEvalResult::StringResult(
+            string_value.chars().take(position_value as usize).collect()))
     }
 
     fn fill_structure_string(&self, buffer: &mut String) {
@@ -45,13 +41,13 @@ impl GwExpression for GwLeftStr {
 
 #[cfg(test)]
 mod left_str_tests {
-    use crate::eval::{GwStringLiteral, GwIntegerLiteral};
+    use crate::eval::{GwStringLiteral, GwIntegerLiteral, GwError};
 
     use super::*;
     use super::super::eval_tests::empty_context;
     
     #[test]
-    fn it_extract_characters_inside_with_left() -> Result<(), String> {
+    fn it_extract_characters_inside_with_left() -> Result<(), GwError> {
         let left_call =
             GwLeftStr::new(
                 Box::new(GwStringLiteral::with_value("APPLE".to_string())),
@@ -60,6 +56,13 @@ mod left_str_tests {
         let eval_result = left_call.eval(&mut ctxt)?;
         assert_eq!("APP", eval_result.assume_string_value()?);
 
+        let left_call =
+            GwLeftStr::new(
+                Box::new(GwStringLiteral::with_value("APPLE".to_string())),
+                Box::new(GwIntegerLiteral::with_value(10)));
+        let eval_result = left_call.eval(&mut ctxt)?;
+        assert_eq!("APPLE", eval_result.assume_string_value()?);
+
         Ok(())
     }
 }
@@ -0,0 +1,189 @@
+use super::{ EvaluationContext, LineExecutionArgument, ExpressionEvalResult,
+             InstructionResult, GwInstruction, GwExpression, GwProgram,
+             GwAssignableExpression, GwError, EvaluationError };
+use super::string_func::eval_integer_argument;
+
+/// AST element for `MID$(x$, n [, m])`, the `m` characters of `x$`
+/// starting at position `n`. Without `m` the rest of the string is returned
+pub struct GwMidStr {
+    string_expr: Box<dyn GwExpression>,
+    start_expr: Box<dyn GwExpression>,
+    length_expr: Option<Box<dyn GwExpression>>
+}
+
+impl GwMidStr {
+    pub fn new(string_expr: Box<dyn GwExpression>,
+               start_expr: Box<dyn GwExpression>,
+               length_expr: Option<Box<dyn GwExpression>>) -> GwMidStr {
+        GwMidStr { string_expr, start_expr, length_expr }
+    }
+}
+
+fn eval_length_argument(length_expr: &Option<Box<dyn GwExpression>>,
+                        context: &mut EvaluationContext) -> Result<usize, GwError> {
+    match length_expr {
+        Some(expr) => Ok(eval_integer_argument(expr.as_ref(), context, 0, 255)? as usize),
+        None => Ok(255)
+    }
+}
+
+fn fill_mid_structure_string(string_expr: &dyn GwExpression,
+                             start_expr: &dyn GwExpression,
+                             length_expr: &Option<Box<dyn GwExpression>>,
+                             buffer: &mut String) {
+    buffer.push_str("MID$(");
+    string_expr.fill_structure_string(buffer);
+    buffer.push_str(", ");
+    start_expr.fill_structure_string(buffer);
+    if let Some(expr) = length_expr {
+        buffer.push_str(", ");
+        expr.fill_structure_string(buffer);
+    }
+    buffer.push(')');
+}
+
+impl GwExpression for GwMidStr {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let binding = self.string_expr.eval(context)?;
+        let string_value = binding.assume_string_value()?;
+        let start = eval_integer_argument(self.start_expr.as_ref(), context, 1, 255)? as usize;
+        let length = eval_length_argument(&self.length_expr, context)?;
+        Ok(ExpressionEvalResult::StringResult(
+            string_value.chars().skip(start - 1).take(length).collect()))
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        fill_mid_structure_string(self.string_expr.as_ref(),
+                                  self.start_expr.as_ref(),
+                                  &self.length_expr,
+                                  buffer);
+    }
+}
+
+/// AST element for the `MID$` statement which replaces characters of a
+/// string variable in place. The length of the variable never changes.
+/// For example:
+/// ```basic
+/// MID$(A$, 2, 3) = "XYZ"
+/// ```
+pub struct GwMidStrAssign {
+    target: Box<dyn GwAssignableExpression>,
+    start_expr: Box<dyn GwExpression>,
+    length_expr: Option<Box<dyn GwExpression>>,
+    value_expr: Box<dyn GwExpression>
+}
+
+impl GwMidStrAssign {
+    pub fn new(target: Box<dyn GwAssignableExpression>,
+               start_expr: Box<dyn GwExpression>,
+               length_expr: Option<Box<dyn GwExpression>>,
+               value_expr: Box<dyn GwExpression>) -> GwMidStrAssign {
+        GwMidStrAssign { target, start_expr, length_expr, value_expr }
+    }
+
+    fn replace(&self, context: &mut EvaluationContext) -> Result<(), GwError> {
+        let mut target_chars: Vec<char> =
+            self.target.eval(context)?.assume_string_value()?.chars().collect();
+        let start = eval_integer_argument(self.start_expr.as_ref(), context, 1, 255)? as usize;
+        let length = eval_length_argument(&self.length_expr, context)?;
+        let binding = self.value_expr.eval(context)?;
+        let replacement = binding.assume_string_value()?;
+
+        if start > target_chars.len() {
+            return Err(GwError::IllegalFunctionCall);
+        }
+        for (target_char, new_char) in target_chars[start - 1..]
+            .iter_mut()
+            .zip(replacement.chars().take(length)) {
+            *target_char = new_char;
+        }
+        self.target.assign_value(
+            ExpressionEvalResult::StringResult(target_chars.into_iter().collect()),
+            context)
+    }
+}
+
+impl GwInstruction for GwMidStrAssign {
+    fn eval (&self,
+             _line: i16,
+             _argument: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        match self.replace(context) {
+            Ok(_) => InstructionResult::EvaluateNext,
+            Err(error) => InstructionResult::EvaluateToError(error)
+        }
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        fill_mid_structure_string(self.target.as_ref(),
+                                  self.start_expr.as_ref(),
+                                  &self.length_expr,
+                                  buffer);
+        buffer.push_str(" = ");
+        self.value_expr.fill_structure_string(buffer);
+    }
+}
+
+
+#[cfg(test)]
+mod mid_str_tests {
+    use crate::eval::{GwStringLiteral, GwIntegerLiteral, GwVariableExpression};
+    use crate::eval::eval_tests::{empty_context, empty_program};
+
+    use super::*;
+
+    fn str_lit(value: &str) -> Box<dyn GwExpression> {
+        Box::new(GwStringLiteral::with_value(value.to_string()))
+    }
+
+    fn int_lit(value: i16) -> Box<dyn GwExpression> {
+        Box::new(GwIntegerLiteral::with_value(value))
+    }
+
+    #[test]
+    fn it_extracts_characters_with_mid() -> Result<(), GwError> {
+        let mut ctxt = empty_context();
+        let mid_call = GwMidStr::new(str_lit("APPLE"), int_lit(2), Some(int_lit(3)));
+        assert_eq!("PPL", mid_call.eval(&mut ctxt)?.assume_string_value()?);
+
+        let mid_call = GwMidStr::new(str_lit("APPLE"), int_lit(3), None);
+        assert_eq!("PLE", mid_call.eval(&mut ctxt)?.assume_string_value()?);
+
+        let mid_call = GwMidStr::new(str_lit("APPLE"), int_lit(9), None);
+        assert_eq!("", mid_call.eval(&mut ctxt)?.assume_string_value()?);
+
+        let mid_call = GwMidStr::new(str_lit("APPLE"), int_lit(0), None);
+        assert_eq!(Err(GwError::IllegalFunctionCall), mid_call.eval(&mut ctxt));
+        Ok(())
+    }
+
+    #[test]
+    fn it_replaces_characters_in_place_with_mid_statement() {
+        let mut ctxt = empty_context();
+        let mut program = empty_program();
+        ctxt.set_variable("A$", &ExpressionEvalResult::StringResult("APPLE".to_string()))
+            .expect("success");
+
+        let stat = GwMidStrAssign::new(
+            Box::new(GwVariableExpression::with_name("A$".to_string())),
+            int_lit(4), None, str_lit("XYZ"));
+        stat.eval(10, LineExecutionArgument::Empty, &mut ctxt, &mut program);
+        assert_eq!(Some(&ExpressionEvalResult::StringResult("APPXY".to_string())),
+                   ctxt.lookup_variable("A$"));
+
+        let stat = GwMidStrAssign::new(
+            Box::new(GwVariableExpression::with_name("A$".to_string())),
+            int_lit(1), Some(int_lit(1)), str_lit("OK"));
+        stat.eval(10, LineExecutionArgument::Empty, &mut ctxt, &mut program);
+        assert_eq!(Some(&ExpressionEvalResult::StringResult("OPPXY".to_string())),
+                   ctxt.lookup_variable("A$"));
+
+        let stat = GwMidStrAssign::new(
+            Box::new(GwVariableExpression::with_name("A$".to_string())),
+            int_lit(6), None, str_lit("Z"));
+        assert!(matches!(stat.eval(10, LineExecutionArgument::Empty, &mut ctxt, &mut program),
+                         InstructionResult::EvaluateToError(GwError::IllegalFunctionCall)));
+    }
+}
@@ -1,8 +1,8 @@
-use rand::Rng;
 
 pub mod binary;
 pub mod context;
 pub mod def_instr;
+pub mod error;
 pub mod dim_instr;
 pub mod for_instr;
 pub mod if_instr;
@@ -14,21 +14,41 @@ pub mod stop_instr;
 
 // expressions
 pub mod leftstr_func;
+pub mod midstr_func;
+pub mod string_func;
+pub mod numeric_func;
+pub mod random;
 
 #[macro_use]
 pub mod utils;
 pub mod swap_instr;
 pub mod data_instr;
 pub mod gosub_instr;
+pub mod error_instr;
+pub mod deffn_instr;
+pub mod clear_instr;
+pub mod number_format;
+pub mod files;
+pub mod file_instr;
+pub mod record_func;
+pub mod save_instr;
+pub mod edit_instr;
 
 pub use crate::eval::context::{
     evaluate_to_usize,
     EvaluationContext, ExpressionEvalResult, ExpressionType, GwInstruction,
     GwProgram, InstructionResult, LineExecutionArgument, ProgramLine,
-    AsyncAction
+    AsyncAction, LineRange
 };
+pub use crate::eval::error::GwError;
+use crate::eval::context::PRINT_ZONE_WIDTH;
+use crate::eval::context::get_default_value_for_type;
+use crate::eval::files::FieldVariable;
+use crate::eval::number_format::format_value;
+use crate::eval::numeric_func::{ apply_float_function, apply_rounding_function };
+pub use crate::eval::random::{ GwRnd, RandomGenerator };
 
-pub type EvaluationError = String;
+pub type EvaluationError = GwError;
 
 pub trait GwExpression {
     fn eval(&self, context: &mut EvaluationContext) -> Result<ExpressionEvalResult, EvaluationError>;
@@ -41,7 +61,10 @@ pub trait GwAssignableExpression: GwExpression {
         &self,
         value: ExpressionEvalResult,
         context: &mut EvaluationContext,
-    ) -> Result<(), String>;
+    ) -> Result<(), GwError>;
+    /// Variable or array element referenced by the expression, as
+    /// bound to a record buffer by `FIELD`
+    fn field_variable(&self, context: &mut EvaluationContext) -> Result<FieldVariable, GwError>;
 }
 
 //  Node for function call or array access elements for example:
@@ -64,21 +87,13 @@ impl GwExpression for GwParenthesizedAccessExpr {
                 Ok(eval_index) => {
                     evaluated_arguments.push(eval_index);
                 }
-                Err(msg) => {
-                    return Err(msg.to_string());
+                Err(error) => {
+                    return Err(error);
                 }
             }
         }
 
-        if let Some(array) = context.get_existing_array(&self.name) {
-            Ok(array.get_value(evaluated_arguments))
-        } else if let Some(_function) =
-            context.get_existing_function(&self.name, self.arguments.len())
-        {
-            todo!();
-        } else {
-            Err("Subscript out of range".to_string())
-        }
+        context.get_array_entry(&self.name, evaluated_arguments)
     }
 
     fn fill_structure_string(&self, buffer: &mut String) {
@@ -110,33 +125,36 @@ pub struct GwCall {
 }
 
 impl GwAssignableExpression for GwCall {
-    fn get_type(&self, _context: &EvaluationContext) -> ExpressionType {
-        //return context.get_variable_type(&self.array_or_function).unwrap();
-        return ExpressionType::Single;
+    fn get_type(&self, context: &EvaluationContext) -> ExpressionType {
+        context.get_variable_type(&self.array_or_function)
     }
 
     fn assign_value(
         &self,
         value: ExpressionEvalResult,
         context: &mut EvaluationContext,
-    ) -> Result<(), String> {
+    ) -> Result<(), GwError> {
         let mut indices: Vec<usize> = vec![];
         for expr in &self.arguments {
             match evaluate_to_usize(expr, context) {
-                Ok(index) if index > 0 => {
+                Ok(index) => {
                     indices.push(index);
                 }
-                Ok(_) => {
-                    return Err("Invalid index".to_string());
-                }
                 Err(err) => {
                     return Err(err);
                 }
             }
         }
 
-        context.set_array_entry(&self.array_or_function, indices, &value);
-        Ok(())
+        context.set_array_entry(&self.array_or_function, indices, &value)
+    }
+
+    fn field_variable(&self, context: &mut EvaluationContext) -> Result<FieldVariable, GwError> {
+        let mut indices: Vec<usize> = vec![];
+        for expr in &self.arguments {
+            indices.push(evaluate_to_usize(expr, context)?);
+        }
+        Ok(FieldVariable::ArrayEntry(context.qualified_name(&self.array_or_function), indices))
     }
 }
 
@@ -149,16 +167,12 @@ impl GwExpression for GwCall {
                 Ok(index)  => {
                     indices.push(index);
                 }
-                _ => {
-                    todo!();
+                Err(error) => {
+                    return Err(error);
                 }
             }
         }
-        if let Some(array) = context.get_existing_array(&self.array_or_function) {
-            Ok(array.get_value(indices))
-        } else {
-            todo!();
-        }
+        context.get_array_entry(&self.array_or_function, indices)
     }
     fn fill_structure_string(&self, buffer: &mut String) {
         buffer.push_str(&self.array_or_function[..]);
@@ -192,7 +206,7 @@ impl GwExpression for GwAbs {
                 Ok(ExpressionEvalResult::DoubleResult(value.abs()))
             }
             Ok(_) => {
-                Err("Type mismatch".to_string())
+                Err(GwError::TypeMismatch)
             },
             err@Err(_) => err
         }
@@ -211,21 +225,8 @@ pub struct GwLog {
 impl GwExpression for GwLog {
     fn eval(&self, context: &mut EvaluationContext)
             -> Result<ExpressionEvalResult, EvaluationError> {
-        match self.expr.eval(context) {
-            Ok(ExpressionEvalResult::IntegerResult(value)) => {
-                Ok(ExpressionEvalResult::DoubleResult((value as f64).ln()))
-            }
-            Ok(ExpressionEvalResult::SingleResult(value)) => {
-                Ok(ExpressionEvalResult::SingleResult(value.ln()))
-            }
-            Ok(ExpressionEvalResult::DoubleResult(value)) => {
-                Ok(ExpressionEvalResult::DoubleResult(value.ln()))
-            }
-            Ok(_) => Err("Type mismatch".to_string()),
-            error@Err(_) => {
-                error
-            }
-        }
+        let value = self.expr.eval(context)?;
+        apply_float_function(&value, |x| if x <= 0.0 { None } else { Some(x.ln()) })
     }
     fn fill_structure_string(&self, buffer: &mut String) {
         buffer.push_str("LOG(");
@@ -252,7 +253,7 @@ impl GwExpression for GwSin {
             Ok(ExpressionEvalResult::DoubleResult(value)) => {
                 Ok(ExpressionEvalResult::DoubleResult(value.sin()))
             }
-            Ok(_) => Err("Type mismatch".to_string()),
+            Ok(_) => Err(GwError::TypeMismatch),
             error@Err(_) => {
                 error
             }
@@ -265,23 +266,6 @@ impl GwExpression for GwSin {
     }
 }
 
-pub struct GwRnd {
-    pub expr: Box<dyn GwExpression>,
-}
-
-impl GwExpression for GwRnd {
-    fn eval(&self, _context: &mut EvaluationContext)
-            -> Result<ExpressionEvalResult, EvaluationError> {
-        Ok(ExpressionEvalResult::DoubleResult(rand::thread_rng().gen::<f64>()))
-    }
-
-    fn fill_structure_string(&self, buffer: &mut String) {
-        buffer.push_str("RND(");
-        self.expr.fill_structure_string(buffer);
-        buffer.push(')')
-    }
-}
-
 pub struct GwCos {
     pub expr: Box<dyn GwExpression>,
 }
@@ -299,7 +283,7 @@ impl GwExpression for GwCos {
             Ok(ExpressionEvalResult::DoubleResult(value)) => {
                 Ok(ExpressionEvalResult::DoubleResult(value.cos()))
             }
-            Ok(_) => Err("Type mismatch".to_string()),
+            Ok(_) => Err(GwError::TypeMismatch),
             error@Err(_) => {
                 error
             }
@@ -319,16 +303,7 @@ pub struct GwInt {
 impl GwExpression for GwInt {
     fn eval(&self, context: &mut EvaluationContext)
             -> Result<ExpressionEvalResult, EvaluationError> {
-        match self.expr.eval(context) {
-            full @ Ok(ExpressionEvalResult::IntegerResult(_)) => full,
-            Ok(ExpressionEvalResult::DoubleResult(value)) => {
-                Ok(ExpressionEvalResult::DoubleResult(value.trunc()))
-            }
-            Ok(_) => Err("Type mismatch".to_string()),
-            error@Err(_) => {
-                error
-            }
-        }
+        apply_rounding_function(self.expr.eval(context)?, f64::floor)
     }
     fn fill_structure_string(&self, buffer: &mut String) {
         buffer.push_str("INT(");
@@ -346,7 +321,10 @@ impl GwExpression for GwNegExpr {
             -> Result<ExpressionEvalResult, EvaluationError> {
         match self.expr.eval(context) {
             Ok(ExpressionEvalResult::IntegerResult(value)) => {
-                Ok(ExpressionEvalResult::IntegerResult(-1 * value))
+                Ok(match value.checked_neg() {
+                    Some(negated) => ExpressionEvalResult::IntegerResult(negated),
+                    None => ExpressionEvalResult::SingleResult(-f32::from(value))
+                })
             }
             Ok(ExpressionEvalResult::DoubleResult(value)) => {
                 Ok(ExpressionEvalResult::DoubleResult(-1.0 * value))
@@ -354,7 +332,7 @@ impl GwExpression for GwNegExpr {
             Ok(ExpressionEvalResult::SingleResult(value)) => {
                 Ok(ExpressionEvalResult::SingleResult(-1.0 * value))
             }
-            Ok(_) => Err("Type mismatch".to_string()),
+            Ok(_) => Err(GwError::TypeMismatch),
             error@Err(_) => {
                 error
             }
@@ -366,6 +344,25 @@ impl GwExpression for GwNegExpr {
     }
 }
 
+/// AST element for the logical `NOT` operator, the bitwise
+/// complement of the 16-bit integer value of the operand
+pub struct GwNotExpr {
+    pub expr: Box<dyn GwExpression>,
+}
+
+impl GwExpression for GwNotExpr {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let value = self.expr.eval(context)?;
+        let int_value = binary::to_integer_operand(&value)?;
+        Ok(ExpressionEvalResult::IntegerResult(!int_value))
+    }
+    fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push_str("NOT ");
+        self.expr.fill_structure_string(buffer);
+    }
+}
+
 pub struct GwParenthesizedExpr {
     expr: Box<dyn GwExpression>,
 }
@@ -377,16 +374,47 @@ impl GwParenthesizedExpr {
 }
 
 impl GwExpression for GwParenthesizedExpr {
-    fn eval(&self, context: &mut EvaluationContext) -> Result<ExpressionEvalResult,String> {
+    fn eval(&self, context: &mut EvaluationContext) -> Result<ExpressionEvalResult, EvaluationError> {
         return self.expr.eval(context);
     }
     fn fill_structure_string(&self, buffer: &mut String) {
-        buffer.push_str("(");
-        self.expr.fill_structure_string(buffer);
-        buffer.push_str(")");
+        let mut inner = String::new();
+        self.expr.fill_structure_string(&mut inner);
+        // Binary operations are already written between parentheses
+        if is_enclosed_in_parentheses(&inner) {
+            buffer.push_str(&inner);
+        } else {
+            buffer.push('(');
+            buffer.push_str(&inner);
+            buffer.push(')');
+        }
     }
 }
 
+/// Checks if the first parenthesis of a text closes at its end,
+/// parentheses in string literals are ignored
+fn is_enclosed_in_parentheses(text: &str) -> bool {
+    if !text.starts_with('(') {
+        return false;
+    }
+    let mut depth = 0;
+    let mut in_string = false;
+    for (index, c) in text.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return index == text.len() - 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
 pub struct GwStringLiteral {
     value: String,
 }
@@ -404,17 +432,26 @@ impl GwExpression for GwStringLiteral {
     }
 
     fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push('"');
         buffer.push_str(&self.value[..]);
+        buffer.push('"');
     }
 }
 
 pub struct GwIntegerLiteral {
     value: i16,
+    text: Option<String>,
 }
 
 impl GwIntegerLiteral {
     pub fn with_value(value: i16) -> GwIntegerLiteral {
-        GwIntegerLiteral { value }
+        GwIntegerLiteral { value, text: None }
+    }
+
+    /// Literal written as `&H1F`, `&O17` or `100%`, the text is kept
+    /// to print it back
+    pub fn with_text(value: i16, text: String) -> GwIntegerLiteral {
+        GwIntegerLiteral { value, text: Some(text) }
     }
 }
 
@@ -424,17 +461,53 @@ impl GwExpression for GwIntegerLiteral {
         Ok(ExpressionEvalResult::IntegerResult(self.value))
     }
     fn fill_structure_string(&self, buffer: &mut String) {
-        buffer.push_str(&self.value.to_string());
+        match &self.text {
+            Some(text) => buffer.push_str(text),
+            None => buffer.push_str(&self.value.to_string())
+        }
+    }
+}
+
+pub struct GwSingleLiteral {
+    value: f32,
+    text: Option<String>,
+}
+
+impl GwSingleLiteral {
+    pub fn with_value(value: f32) -> GwSingleLiteral {
+        GwSingleLiteral { value, text: None }
+    }
+
+    pub fn with_text(value: f32, text: String) -> GwSingleLiteral {
+        GwSingleLiteral { value, text: Some(text) }
+    }
+}
+
+impl GwExpression for GwSingleLiteral {
+    fn eval(&self, _context: &mut EvaluationContext)
+                     -> Result<ExpressionEvalResult, EvaluationError> {
+        Ok(ExpressionEvalResult::SingleResult(self.value))
+    }
+    fn fill_structure_string(&self, buffer: &mut String) {
+        match &self.text {
+            Some(text) => buffer.push_str(text),
+            None => buffer.push_str(&self.value.to_string())
+        }
     }
 }
 
 pub struct GwDoubleLiteral {
     value: f64,
+    text: Option<String>,
 }
 
 impl GwDoubleLiteral {
     pub fn with_value(value: f64) -> GwDoubleLiteral {
-        GwDoubleLiteral { value }
+        GwDoubleLiteral { value, text: None }
+    }
+
+    pub fn with_text(value: f64, text: String) -> GwDoubleLiteral {
+        GwDoubleLiteral { value, text: Some(text) }
     }
 }
 
@@ -444,7 +517,10 @@ impl GwExpression for GwDoubleLiteral {
         Ok(ExpressionEvalResult::DoubleResult(self.value))
     }
     fn fill_structure_string(&self, buffer: &mut String) {
-        buffer.push_str(&self.value.to_string());
+        match &self.text {
+            Some(text) => buffer.push_str(text),
+            None => buffer.push_str(&self.value.to_string())
+        }
     }
 }
 
@@ -454,19 +530,18 @@ pub struct GwVariableExpression {
 
 impl GwAssignableExpression for GwVariableExpression {
     fn get_type(&self, context: &EvaluationContext) -> ExpressionType {
-        context
-            .get_variable_type(&self.name)
-            .unwrap_or(ExpressionType::Double)
+        context.get_variable_type(&self.name)
     }
 
     fn assign_value(&self,
                     value: ExpressionEvalResult,
                     context: &mut EvaluationContext)
-               -> Result<(), String> {
-        match context.set_variable(&self.name, &value) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.to_string())
-        }
+               -> Result<(), GwError> {
+        context.set_variable(&self.name, &value)
+    }
+
+    fn field_variable(&self, context: &mut EvaluationContext) -> Result<FieldVariable, GwError> {
+        Ok(FieldVariable::Simple(context.qualified_name(&self.name)))
     }
 }
 
@@ -482,8 +557,7 @@ impl GwExpression for GwVariableExpression {
         if let Some(value) = context.lookup_variable(&self.name) {
             Ok(value.clone())
         } else {
-            // TODO we need to define a variable here???
-            Ok(ExpressionEvalResult::IntegerResult(0))
+            Ok(get_default_value_for_type(&context.get_variable_type(&self.name)))
         }
     }
 
@@ -501,8 +575,9 @@ impl ProgramLine {
     //      self.instruction.eval(self.line, context)
     // }
 
+    /// Writes the line as listed by `LIST` and `SAVE`, which is
+    /// parsed back to the same line
     pub fn fill_structure_string(&self, buffer: &mut String) {
-        buffer.push('(');
         buffer.push_str(&self.line.to_string()[..]);
         buffer.push(' ');
         self.instruction.fill_structure_string(buffer);
@@ -513,7 +588,6 @@ impl ProgramLine {
                 e.fill_structure_string(buffer);
             }
         }
-        buffer.push(')');
     }
 }
 
@@ -551,7 +625,7 @@ impl GwInstruction for GwLoadStat {
         if let  LineExecutionArgument::SupplyPendingResult(code)= arg {
             context.console.log("about to load delayed");
             context.console.log(&code);
-            let lines_vec:Vec<String> = code.split("\n").map(String::from).filter(|s| { s.len() != 0}).collect();
+            let lines_vec:Vec<String> = code.lines().map(String::from).filter(|s| { !s.is_empty() }).collect();
             for s in (&lines_vec).into_iter() {
                 context.console.log("___");
                 context.console.log(&s);
@@ -561,49 +635,41 @@ impl GwInstruction for GwLoadStat {
                     match program.load_from(/*"fix.bas",*/ c,  lines) {
                         Ok(_) => {
                             context.console.log(">>1");
-                            println!("File loaded");
                         }
                         Err(error) => {
                             context.console.log(">>2");
-                            panic!("Error loading file {:?}", error);
+                            return InstructionResult::EvaluateToError(error);
                         }
                     }
             context.console.log("end load");
                     return InstructionResult::EvaluateNext;
             
         }
-        let result = self.filename.eval(context);
-
-        return match result {
-            Ok(ExpressionEvalResult::StringResult(filename)) => {
-                context.console.log("async load 1");
-                if  !(&context.console).requires_async_readline() {
-                    context.console.log("async load X");
-                    let file_name_to_use = &filename.trim_matches('"');
-                    let lines = context.console.read_file_lines(file_name_to_use);
-                    let c = &mut context.console;
-                    match program.load_from(/*&file_name_to_use,*/ c, lines) {
-                        Ok(_) => {
-                            println!("File loaded");
-                        }
-                        Err(error) => {
-                            panic!("Error loading file {:?}", error);
-                        }
-                    }                
-                    InstructionResult::EvaluateNext
-                } else {
-                    context.console.log("async load 2");
-                    InstructionResult::RequestAsyncAction(
-                        AsyncAction::LoadProgram(String::from(filename)))
-                }
-            }
-            Ok(_) => {
-                InstructionResult::EvaluateToError("Type mismatch".to_string())
+        let filename = match file_instr::eval_file_name(self.filename.as_ref(), context) {
+            Ok(filename) => filename,
+            Err(error) => return InstructionResult::EvaluateToError(error)
+        };
+        let contents = context.console.file_system()
+            .map(|file_system| file_system.read_file(&filename));
+        let c = &mut context.console;
+        let load_result = match contents {
+            Some(Ok(contents)) => program.load_file_contents(c, &contents),
+            // Programs that are not in the file system are fetched by the console
+            _ if c.requires_async_readline() => {
+                c.log("async load 2");
+                return InstructionResult::RequestAsyncAction(
+                    AsyncAction::LoadProgram(filename));
             }
-            Err(error) => {
-                InstructionResult::EvaluateToError(error)
+            Some(Err(error)) => Err(error),
+            None => {
+                let lines = c.read_file_lines(&filename);
+                program.load_from(c, lines)
             }
         };
+        match load_result {
+            Ok(_) => InstructionResult::EvaluateNext,
+            Err(error) => InstructionResult::EvaluateToError(error)
+        }
     }
     fn fill_structure_string(&self, buffer: &mut String) {
         buffer.push_str(&"LOAD ");
@@ -667,7 +733,8 @@ impl GwInstruction for GwRem {
     }
 
     fn fill_structure_string(&self, buffer: &mut String) {
-        buffer.push_str(&"REM ");
+        // The comment keeps the spaces after `REM`
+        buffer.push_str("REM");
         buffer.push_str(&self.comment[..]);
     }
 }
@@ -708,12 +775,9 @@ impl GwInstruction for GwAssign {
         //let expression_evaluation = ;
         match self.expression.eval(context) {
             Ok(expression_evaluation) =>{
-                if let None = context.get_variable_type(&self.variable) {
-                    context.set_variable_type(&self.variable, &ExpressionType::Single);
-                }
                 match context.set_variable(&self.variable, &expression_evaluation) {
-                    Err(error_message)
-                        => InstructionResult::EvaluateToError(error_message.to_string()),
+                    Err(error)
+                        => InstructionResult::EvaluateToError(error),
                     _ => InstructionResult::EvaluateNext
                 }
             }
@@ -759,12 +823,14 @@ impl GwInstruction for GwArrayAssign {
 
         match self.expression.eval(context) {
             Ok(expression_evaluation) => {
-                context.set_array_entry(
+                match context.set_array_entry(
                     &self.variable,
                     evaluated_arguments,
                     &expression_evaluation,
-                );
-                InstructionResult::EvaluateNext
+                ) {
+                    Ok(_) => InstructionResult::EvaluateNext,
+                    Err(error) => InstructionResult::EvaluateToError(error)
+                }
             }
             Err(error) => InstructionResult::EvaluateToError(error),
         }
@@ -797,15 +863,14 @@ impl GwInstruction for GwGotoStat {
         _program: &mut GwProgram
     ) -> InstructionResult {
         if let Some(actual_line) = context.get_real_line(self.line) {
-            return InstructionResult::EvaluateLine(actual_line);
+            InstructionResult::EvaluateLine(actual_line)
         } else {
-            println!("-- {}", self.line);
-            panic!("Trying to jump to non existing line");
+            InstructionResult::EvaluateToError(GwError::UndefinedLineNumber)
         }
     }
 
     fn fill_structure_string(&self, buffer: &mut String) {
-        buffer.push_str("GOTO ");
+        buffer.push_str(format!("GOTO {}", self.line).as_str());
     }
 }
 
@@ -872,81 +937,205 @@ pub enum PrintElementWrapper {
     Nothing,
     Expr(Box<dyn GwExpression>),
     Tab(Box<dyn GwExpression>),
+    Spc(Box<dyn GwExpression>),
+}
+
+/// Writes the elements of `PRINT` and `PRINT USING` with their separators
+pub fn fill_print_elements_structure_string(expressions: &[(PrintElementWrapper, Option<PrintSeparator>)],
+                                            buffer: &mut String) {
+    let mut remaining = expressions.len();
+    for (element, separator) in expressions {
+        match element {
+            PrintElementWrapper::Expr(expr) => {
+                expr.fill_structure_string(buffer);
+            }
+            PrintElementWrapper::Tab(position) => {
+                buffer.push_str("TAB(");
+                position.fill_structure_string(buffer);
+                buffer.push(')');
+            }
+            PrintElementWrapper::Spc(count) => {
+                buffer.push_str("SPC(");
+                count.fill_structure_string(buffer);
+                buffer.push(')');
+            }
+            PrintElementWrapper::Nothing => {}
+        }
+        remaining -= 1;
+        match separator {
+            Some(PrintSeparator::Comma) => buffer.push(','),
+            Some(PrintSeparator::Semicolon) => buffer.push(';'),
+            None if remaining > 0 => buffer.push(' '),
+            None => {}
+        }
+    }
+}
+
+/// Destination of `PRINT` and `WRITE`, the screen or a file
+/// opened for output with `OPEN`
+pub enum PrintTarget {
+    Screen,
+    File(u8)
+}
+
+impl PrintTarget {
+    pub fn with_file_number(file_number: &Option<Box<dyn GwExpression>>,
+                            context: &mut EvaluationContext) -> Result<PrintTarget, GwError> {
+        match file_number {
+            Some(number_expr) =>
+                Ok(PrintTarget::File(file_instr::evaluate_file_number(number_expr.as_ref(), context)?)),
+            None => Ok(PrintTarget::Screen)
+        }
+    }
+
+    fn text_width(&self, context: &EvaluationContext) -> usize {
+        match self {
+            PrintTarget::Screen => context.console.text_width(),
+            PrintTarget::File(_) => files::FILE_TEXT_WIDTH
+        }
+    }
+
+    fn current_text_column(&self, context: &mut EvaluationContext) -> Result<usize, GwError> {
+        match self {
+            PrintTarget::Screen => Ok(context.console.current_text_column()),
+            PrintTarget::File(number) =>
+                Ok(context.get_open_file(*number)?.current_text_column())
+        }
+    }
+
+    pub fn print(&self, context: &mut EvaluationContext, text: &str) -> Result<(), GwError> {
+        match self {
+            PrintTarget::Screen => {
+                context.console.print(text);
+                Ok(())
+            }
+            PrintTarget::File(number) => context.write_to_file(*number, text)
+        }
+    }
+
+    /// Prints text followed by a new line, which is CR LF in files
+    pub fn print_line(&self, context: &mut EvaluationContext, text: &str) -> Result<(), GwError> {
+        match self {
+            PrintTarget::Screen => {
+                context.console.print_line(text);
+                Ok(())
+            }
+            PrintTarget::File(number) => context.write_to_file(*number, &format!("{}\r\n", text))
+        }
+    }
+
+    fn adjust_to_position(&self, context: &mut EvaluationContext, position: usize) -> Result<(), GwError> {
+        if let PrintTarget::Screen = self {
+            context.console.adjust_to_position(position);
+            return Ok(());
+        }
+        let column = self.current_text_column(context)?;
+        if column <= position {
+            self.print(context, &" ".repeat(position - column))
+        } else {
+            self.print_line(context, "")?;
+            self.print(context, &" ".repeat(position - 1))
+        }
+    }
+
+    fn advance_to_next_zone(&self, context: &mut EvaluationContext) -> Result<(), GwError> {
+        if let PrintTarget::Screen = self {
+            context.console.advance_to_next_zone();
+            return Ok(());
+        }
+        let position = self.current_text_column(context)? - 1;
+        let next_zone = (position / PRINT_ZONE_WIDTH + 1) * PRINT_ZONE_WIDTH;
+        if next_zone + PRINT_ZONE_WIDTH > self.text_width(context) {
+            self.print_line(context, "")
+        } else {
+            self.print(context, &" ".repeat(next_zone - position))
+        }
+    }
 }
 
 pub struct GwPrintStat {
+    pub file_number: Option<Box<dyn GwExpression>>,
     pub expressions: Vec<(PrintElementWrapper, Option<PrintSeparator>)>,
 }
 
-impl GwInstruction for GwPrintStat {
-    fn eval(
-        &self,
-        _line: i16,
-        _arg: LineExecutionArgument,
-        context: &mut EvaluationContext,
-        _program: &mut GwProgram
-    ) -> InstructionResult {
-        let mut i = 0;
-        let mut newline_at_the_end = true;
-        for print_expr in &self.expressions {
-            match print_expr {
-                (PrintElementWrapper::Expr(expr), separator) => {
-                    //let evaluated_expr = expr.eval(context);
-                    match expr.eval(context) {
-                        Ok(evaluated_expr) => {
-                            context.console.print(&evaluated_expr.to_string());
-                            if i == &self.expressions.len() - 1 {
-                                if let Some(PrintSeparator::Semicolon) = separator {
-                                    newline_at_the_end = false;
-                                }
-                            }
-                        }
-                        Err(eval_error) => {
-                            return InstructionResult::EvaluateToError(eval_error);
+impl GwPrintStat {
+    fn print_elements(&self, context: &mut EvaluationContext) -> Result<(), GwError> {
+        let target = PrintTarget::with_file_number(&self.file_number, context)?;
+        let width = target.text_width(context);
+        for (element, separator) in &self.expressions {
+            match element {
+                PrintElementWrapper::Expr(expr) => {
+                    let evaluated_expr = expr.eval(context)?;
+                    let text = format_value(&evaluated_expr);
+                    if evaluated_expr.is_numeric() {
+                        // Numbers are not split between two lines
+                        let column = target.current_text_column(context)?;
+                        if column > 1 && column - 1 + text.len() > width {
+                            target.print_line(context, "")?;
                         }
+                        target.print(context, &text)?;
+                        target.print(context, " ")?;
+                    } else {
+                        target.print(context, &text)?;
                     }
                 }
-                (PrintElementWrapper::Tab(position_expr), _) => {
-                    match evaluate_to_usize(position_expr, context) {
-                        Ok(position) => {
-                            context.console.adjust_to_position(position as usize);
+                PrintElementWrapper::Tab(position_expr) => {
+                    match evaluate_to_usize(position_expr, context)? {
+                        position if (1..=255).contains(&position) => {
+                            target.adjust_to_position(context, (position - 1) % width + 1)?;
                         }
-                        Err(error) => {
-                            return InstructionResult::EvaluateToError(error);
+                        _ => return Err(GwError::IllegalFunctionCall)
+                    }
+                }
+                PrintElementWrapper::Spc(count_expr) => {
+                    match evaluate_to_usize(count_expr, context)? {
+                        count if count <= 255 => {
+                            target.print(context, &" ".repeat(count % width))?;
                         }
+                        _ => return Err(GwError::IllegalFunctionCall)
                     }
                 }
-                _ => {}
+                PrintElementWrapper::Nothing => {}
+            }
+            if let Some(PrintSeparator::Comma) = separator {
+                target.advance_to_next_zone(context)?;
             }
-            i += 1;
         }
 
-        let console = &mut context.console;
-        if newline_at_the_end {
-            console.print_line("");
+        // A trailing `;` or `,` keeps the cursor on the same line
+        if let Some((_, Some(_))) = self.expressions.last() {
+            target.print(context, "")
         } else {
-            console.print("");
+            target.print_line(context, "")
         }
-        InstructionResult::EvaluateNext
     }
+}
 
-    fn fill_structure_string(&self, buffer: &mut String) {
-        buffer.push_str(&"PRINT ");
+impl GwInstruction for GwPrintStat {
+    fn eval(
+        &self,
+        _line: i16,
+        _arg: LineExecutionArgument,
+        context: &mut EvaluationContext,
+        _program: &mut GwProgram
+    ) -> InstructionResult {
+        match self.print_elements(context) {
+            Ok(_) => InstructionResult::EvaluateNext,
+            Err(error) => InstructionResult::EvaluateToError(error)
+        }
+    }
 
-        for print_expr in &self.expressions {
-            match print_expr {
-                (PrintElementWrapper::Expr(expr), _) => {
-                    expr.fill_structure_string(buffer);
-                }
-                (PrintElementWrapper::Tab(position), _) => {
-                    buffer.push_str("TAB(");
-                    position.fill_structure_string(buffer);
-                    buffer.push_str(")");
-                }
-                _ => {}
-            }
-            buffer.push_str(";");
+    fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push_str("PRINT");
+        if let Some(file_number) = &self.file_number {
+            buffer.push_str(" #");
+            file_number.fill_structure_string(buffer);
+            buffer.push(',');
         }
+        if !self.expressions.is_empty() {
+            buffer.push(' ');
+        }
+        fill_print_elements_structure_string(&self.expressions, buffer);
     }
 }
 
@@ -959,28 +1148,35 @@ fn read_variable_from_input(
     variable: &Box<dyn GwAssignableExpression>,
     context: &mut EvaluationContext,
     str_value: &str,
-) -> Result<(), String> {
+) -> Result<(), GwError> {
     match variable.get_type(context) {
         ExpressionType::Double => {
             let dbl = str_value
                 .trim_end()
                 .parse::<f64>()
-                .map_err(|_| { "Invalid value".to_string() })?;
-            variable.assign_value(ExpressionEvalResult::DoubleResult(dbl), context)
+                .map_err(|_| { GwError::TypeMismatch })?;
+            variable.assign_value(binary::double_result(dbl)?, context)
         }
         ExpressionType::Single => {
             let svl = str_value
                 .trim_end()
                 .parse::<f32>()
-                .map_err(|_| { "Invalid value".to_string() })?;
-            variable.assign_value(ExpressionEvalResult::SingleResult(svl), context)
+                .map_err(|_| { GwError::TypeMismatch })?;
+            variable.assign_value(binary::single_result(svl)?, context)
+        }
+        ExpressionType::Integer => {
+            let dbl = str_value
+                .trim_end()
+                .parse::<f64>()
+                .map_err(|_| { GwError::TypeMismatch })?;
+            let int_value = binary::to_integer_operand(&ExpressionEvalResult::DoubleResult(dbl))?;
+            variable.assign_value(ExpressionEvalResult::IntegerResult(int_value), context)
         }
         ExpressionType::String => {
             variable.assign_value(
                 ExpressionEvalResult::StringResult(str_value.to_string()),
                 context)
         }
-        _ => panic!("Not implemented INPUT for this type"),
     }
 }
 
@@ -1048,12 +1244,12 @@ impl GwInstruction for GwInputStat {
 }
 
 
-pub fn get_as_integer(value: &Option<&ExpressionEvalResult>) -> Result<i16, String> {
+pub fn get_as_integer(value: &Option<&ExpressionEvalResult>) -> Result<i16, GwError> {
     match value {
         Some(ExpressionEvalResult::IntegerResult(int_value)) =>  Ok(*int_value),
         Some(ExpressionEvalResult::SingleResult(single_value)) => Ok(*single_value as i16),
         Some(ExpressionEvalResult::DoubleResult(double_value)) =>  Ok(*double_value as i16),
-        _ => Err("Type mismatch".to_string())
+        _ => Err(GwError::TypeMismatch)
     }
 }
 
@@ -1064,7 +1260,7 @@ mod eval_tests {
     use std::collections::HashMap;
     use std::rc::Rc;
     use crate::eval::ExpressionEvalResult;
-    use crate::eval::context::Console;
+    use crate::eval::context::{Console, ErrorTrap};
     use crate::eval::*;
 
     #[test]
@@ -1073,7 +1269,7 @@ mod eval_tests {
             line: 10,
             instruction: Rc::new(GwAssign {
                 variable: String::from("X"),
-                expression: Box::new(GwIntegerLiteral { value: 10 }),
+                expression: Box::new(GwIntegerLiteral::with_value(10)),
             }),
             rest_instructions: None,
         };
@@ -1096,7 +1292,14 @@ mod eval_tests {
 //            data: vec![],
             data_position: -1,
             subroutine_stack: vec![],
-            current_real_line: -1
+            current_real_line: -1,
+            error_trap: ErrorTrap::new(),
+            user_functions: HashMap::new(),
+            random: RandomGenerator::new(),
+            option_base: 0,
+            data_line_positions: HashMap::new(),
+            default_types: [ExpressionType::Single; 26],
+            open_files: HashMap::new(),
         };
 
         context
@@ -1120,6 +1323,23 @@ mod eval_tests {
         }
     }
 
+    #[test]
+    fn it_reads_integer_variables_from_input() {
+        let mut context = empty_context();
+        let suffixed: Box<dyn GwAssignableExpression> =
+            Box::new(GwVariableExpression::with_name(String::from("A%")));
+        let defined: Box<dyn GwAssignableExpression> =
+            Box::new(GwVariableExpression::with_name(String::from("B")));
+        context.set_default_type('B', ExpressionType::Integer);
+
+        assert_eq!(Ok(()), read_variable_from_input(&suffixed, &mut context, "4.6"));
+        assert_eq!(Some(&ExpressionEvalResult::IntegerResult(5)), context.lookup_variable("A%"));
+        assert_eq!(Ok(()), read_variable_from_input(&defined, &mut context, "-12"));
+        assert_eq!(Some(&ExpressionEvalResult::IntegerResult(-12)), context.lookup_variable("B"));
+        assert_eq!(Err(GwError::Overflow), read_variable_from_input(&suffixed, &mut context, "40000"));
+        assert_eq!(Err(GwError::TypeMismatch), read_variable_from_input(&defined, &mut context, "ABC"));
+    }
+
     #[test]
     fn it_negates_integer_expressions() -> Result<(), & 'static str> {
         let negation = GwNegExpr {
@@ -1137,6 +1357,21 @@ mod eval_tests {
         }
     }
 
+    #[test]
+    fn it_promotes_overflowing_integer_operations() {
+        let mut context = empty_context();
+        let sum = binary::GwBinaryOperation::new(
+            binary::GwBinaryOperationKind::Plus,
+            Box::new(GwIntegerLiteral::with_value(30000)),
+            Box::new(GwIntegerLiteral::with_value(30000)));
+        assert_eq!(Ok(ExpressionEvalResult::SingleResult(60000.0)), sum.eval(&mut context));
+
+        let negation = GwNegExpr {
+            expr: Box::new(GwIntegerLiteral::with_value(i16::MIN)),
+        };
+        assert_eq!(Ok(ExpressionEvalResult::SingleResult(32768.0)), negation.eval(&mut context));
+    }
+
     #[test]
     fn it_executes_array_access() -> Result<(), String> {
         let array_access = GwCall {
@@ -1144,12 +1379,12 @@ mod eval_tests {
             arguments: vec![Box::new(GwIntegerLiteral::with_value(2))],
         };
         let mut context = empty_context();
-        context.declare_array("arr", 5);
+        context.declare_array("arr", vec![5]).expect("success");
         context.set_array_entry(
             "arr",
             vec![2 as usize],
             &ExpressionEvalResult::IntegerResult(101),
-        );
+        ).expect("success");
 
         match array_access.eval(&mut context) {
             Ok(ExpressionEvalResult::IntegerResult(101)) => Ok(()),
@@ -1181,7 +1416,7 @@ mod eval_tests {
             instruction: Rc::new(GwArrayAssign {
                 variable: String::from("A"),
                 indices_expressions: vec![Box::new(GwIntegerLiteral::with_value(1))],
-                expression: Box::new(GwIntegerLiteral { value: 12 }),
+                expression: Box::new(GwIntegerLiteral::with_value(12)),
             }),
             rest_instructions: None,
         };
@@ -1191,11 +1426,11 @@ mod eval_tests {
         let mut context = EvaluationContext::new(Box::new(DummyConsole{}));
 //        context.real_lines = Some(vec![]);
 
-        context.declare_array("A", 10);
+        context.declare_array("A", vec![10]).expect("success");
 
         let arr1 = context.get_existing_array("A");
 
-        if let ExpressionEvalResult::IntegerResult(value) = arr1.unwrap().get_value(vec![1]) {
+        if let ExpressionEvalResult::IntegerResult(value) = arr1.unwrap().get_value(&[1]).unwrap() {
             let some_value: i16 = 0;
             assert_eq!(some_value, value);
         }
@@ -1204,7 +1439,7 @@ mod eval_tests {
 
         let arr2 = context.get_existing_array("A");
 
-        if let ExpressionEvalResult::IntegerResult(value) = arr2.unwrap().get_value(vec![1]) {
+        if let ExpressionEvalResult::IntegerResult(value) = arr2.unwrap().get_value(&[1]).unwrap() {
             let some_value: i16 = 12;
             assert_eq!(some_value, value);
         }
@@ -1230,7 +1465,14 @@ mod eval_tests {
             //data: vec![],
             data_position: -1,
             subroutine_stack: vec![],
-            current_real_line: -1
+            current_real_line: -1,
+            error_trap: ErrorTrap::new(),
+            user_functions: HashMap::new(),
+            random: RandomGenerator::new(),
+            option_base: 0,
+            data_line_positions: HashMap::new(),
+            default_types: [ExpressionType::Single; 26],
+            open_files: HashMap::new(),
         }
     }
 
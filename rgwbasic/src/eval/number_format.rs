@@ -0,0 +1,111 @@
+use super::ExpressionEvalResult;
+
+const SINGLE_DIGITS: usize = 7;
+const DOUBLE_DIGITS: usize = 16;
+
+/// Text of a value as shown by `PRINT` and `STR$`. Numbers start
+/// with a sign or a space, singles show up to 7 significant digits and
+/// doubles up to 16. Values that need more digits use `E` or `D` notation
+pub fn format_value(value: &ExpressionEvalResult) -> String {
+    match value {
+        ExpressionEvalResult::StringResult(text) => text.clone(),
+        ExpressionEvalResult::IntegerResult(int_value) =>
+            with_sign(*int_value < 0, i32::from(*int_value).abs().to_string()),
+        ExpressionEvalResult::SingleResult(single_value) =>
+            with_sign(*single_value < 0.0,
+                      format_magnitude(f64::from(single_value.abs()), SINGLE_DIGITS, 'E')),
+        ExpressionEvalResult::DoubleResult(double_value) =>
+            with_sign(*double_value < 0.0,
+                      format_magnitude(double_value.abs(), DOUBLE_DIGITS, 'D'))
+    }
+}
+
+fn with_sign(negative: bool, digits: String) -> String {
+    if negative {
+        format!("-{}", digits)
+    } else {
+        format!(" {}", digits)
+    }
+}
+
+fn format_magnitude(value: f64, significant_digits: usize, exponent_char: char) -> String {
+    // Arithmetic reports Overflow before a value can become infinite
+    debug_assert!(value.is_finite());
+    if value == 0.0 {
+        return String::from("0");
+    }
+    // Rust's exponent notation does the rounding: "d.dddddde-n"
+    let scientific = format!("{:.*e}", significant_digits - 1, value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let digits: String = mantissa.chars().filter(|c| c.is_ascii_digit()).collect();
+    let digits = match digits.trim_end_matches('0') {
+        "" => "0",
+        trimmed => trimmed
+    };
+
+    // Position of the decimal point relative to the start of the digits
+    let point_position = exponent + 1;
+    let digit_count = digits.len() as i32;
+    let max_digits = significant_digits as i32;
+    if point_position > 0 && point_position <= max_digits {
+        if digit_count <= point_position {
+            format!("{}{}", digits, "0".repeat((point_position - digit_count) as usize))
+        } else {
+            let (integer_part, fraction) = digits.split_at(point_position as usize);
+            format!("{}.{}", integer_part, fraction)
+        }
+    } else if point_position <= 0 && digit_count - point_position <= max_digits {
+        format!(".{}{}", "0".repeat((-point_position) as usize), digits)
+    } else {
+        let (first_digit, rest) = digits.split_at(1);
+        let exponent_sign = if exponent < 0 { '-' } else { '+' };
+        if rest.is_empty() {
+            format!("{}{}{}{:02}", first_digit, exponent_char, exponent_sign, exponent.abs())
+        } else {
+            format!("{}.{}{}{}{:02}", first_digit, rest, exponent_char, exponent_sign, exponent.abs())
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod number_format_tests {
+    use super::*;
+
+    fn single(value: f32) -> String {
+        format_value(&ExpressionEvalResult::SingleResult(value))
+    }
+
+    fn double(value: f64) -> String {
+        format_value(&ExpressionEvalResult::DoubleResult(value))
+    }
+
+    #[test]
+    fn it_formats_integers_with_sign() {
+        assert_eq!(" 5", format_value(&ExpressionEvalResult::IntegerResult(5)));
+        assert_eq!("-32768", format_value(&ExpressionEvalResult::IntegerResult(-32768)));
+        assert_eq!(" 0", format_value(&ExpressionEvalResult::IntegerResult(0)));
+    }
+
+    #[test]
+    fn it_formats_singles_with_seven_digits() {
+        assert_eq!(" .3333333", single(1.0 / 3.0));
+        assert_eq!("-2.5", single(-2.5));
+        assert_eq!(" .1", single(0.1));
+        assert_eq!(" 1234567", single(1234567.0));
+        assert_eq!(" 1.234568E+07", single(12345678.0));
+        assert_eq!(" 1E+07", single(10000000.0));
+        assert_eq!(" .001", single(0.001));
+        assert_eq!(" 3.333333E-03", single(1.0 / 300.0));
+        assert_eq!(" 0", single(0.0));
+    }
+
+    #[test]
+    fn it_formats_doubles_with_sixteen_digits() {
+        assert_eq!(" .3333333333333333", double(1.0 / 3.0));
+        assert_eq!(" 12345678", double(12345678.0));
+        assert_eq!(" 1D+20", double(1e20));
+        assert_eq!("-2.5D-20", double(-2.5e-20));
+    }
+}
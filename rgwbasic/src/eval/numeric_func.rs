@@ -0,0 +1,218 @@
+use super::{ GwExpression,
+             EvaluationContext,
+             ExpressionEvalResult,
+             EvaluationError,
+             GwError };
+use super::binary::{ get_double_value, to_integer_operand };
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GwNumericFunctionKind {
+    Sqr,
+    Tan,
+    Atn,
+    Exp,
+    Sgn,
+    Fix,
+    Cint,
+    Csng,
+    Cdbl,
+    HexStr,
+    OctStr
+}
+
+impl GwNumericFunctionKind {
+    fn name(&self) -> &'static str {
+        match self {
+            GwNumericFunctionKind::Sqr => "SQR",
+            GwNumericFunctionKind::Tan => "TAN",
+            GwNumericFunctionKind::Atn => "ATN",
+            GwNumericFunctionKind::Exp => "EXP",
+            GwNumericFunctionKind::Sgn => "SGN",
+            GwNumericFunctionKind::Fix => "FIX",
+            GwNumericFunctionKind::Cint => "CINT",
+            GwNumericFunctionKind::Csng => "CSNG",
+            GwNumericFunctionKind::Cdbl => "CDBL",
+            GwNumericFunctionKind::HexStr => "HEX$",
+            GwNumericFunctionKind::OctStr => "OCT$"
+        }
+    }
+}
+
+/// Applies a floating point function keeping the precision of the
+/// argument: double arguments produce double results and integer or
+/// single arguments produce single results. `None` from the function
+/// is a domain error
+pub fn apply_float_function(value: &ExpressionEvalResult,
+                            function: fn(f64) -> Option<f64>)
+                            -> Result<ExpressionEvalResult, GwError> {
+    let argument = get_double_value(value).ok_or(GwError::TypeMismatch)?;
+    let result = function(argument).ok_or(GwError::IllegalFunctionCall)?;
+    match value {
+        ExpressionEvalResult::DoubleResult(_) if result.is_finite() =>
+            Ok(ExpressionEvalResult::DoubleResult(result)),
+        _ if result.is_finite() && (result as f32).is_finite() =>
+            Ok(ExpressionEvalResult::SingleResult(result as f32)),
+        _ => Err(GwError::Overflow)
+    }
+}
+
+/// Applies a rounding function keeping the type of the argument.
+/// Integers are already rounded so they are returned unchanged
+pub fn apply_rounding_function(value: ExpressionEvalResult,
+                               function: fn(f64) -> f64)
+                               -> Result<ExpressionEvalResult, GwError> {
+    match value {
+        ExpressionEvalResult::IntegerResult(_) => Ok(value),
+        ExpressionEvalResult::SingleResult(single_value) =>
+            Ok(ExpressionEvalResult::SingleResult(function(single_value as f64) as f32)),
+        ExpressionEvalResult::DoubleResult(double_value) =>
+            Ok(ExpressionEvalResult::DoubleResult(function(double_value))),
+        ExpressionEvalResult::StringResult(_) => Err(GwError::TypeMismatch)
+    }
+}
+
+/// Converts the argument of `HEX$` and `OCT$` to its unsigned 16 bits
+/// representation, so negative numbers produce two's complement digits
+fn to_unsigned_word(value: &ExpressionEvalResult) -> Result<u16, GwError> {
+    let rounded = get_double_value(value).ok_or(GwError::TypeMismatch)?.round();
+    if rounded < f64::from(i16::MIN) || rounded > f64::from(u16::MAX) {
+        Err(GwError::Overflow)
+    } else if rounded < 0.0 {
+        Ok(rounded as i16 as u16)
+    } else {
+        Ok(rounded as u16)
+    }
+}
+
+/// AST element for the numeric and conversion builtin functions
+/// with a single argument.
+/// For example:
+/// ```basic
+/// PRINT SQR(2), CINT(2.6), HEX$(255)
+/// ```
+pub struct GwNumericFunction {
+    kind: GwNumericFunctionKind,
+    expr: Box<dyn GwExpression>
+}
+
+impl GwNumericFunction {
+    pub fn new(kind: GwNumericFunctionKind, expr: Box<dyn GwExpression>) -> GwNumericFunction {
+        GwNumericFunction { kind, expr }
+    }
+}
+
+impl GwExpression for GwNumericFunction {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let value = self.expr.eval(context)?;
+        if !value.is_numeric() {
+            return Err(GwError::TypeMismatch);
+        }
+        match self.kind {
+            GwNumericFunctionKind::Sqr =>
+                apply_float_function(&value, |x| if x < 0.0 { None } else { Some(x.sqrt()) }),
+            GwNumericFunctionKind::Tan => apply_float_function(&value, |x| Some(x.tan())),
+            GwNumericFunctionKind::Atn => apply_float_function(&value, |x| Some(x.atan())),
+            GwNumericFunctionKind::Exp => apply_float_function(&value, |x| Some(x.exp())),
+            GwNumericFunctionKind::Sgn => {
+                let sign = get_double_value(&value).unwrap_or(0.0);
+                Ok(ExpressionEvalResult::IntegerResult(
+                    if sign > 0.0 { 1 } else if sign < 0.0 { -1 } else { 0 }))
+            }
+            GwNumericFunctionKind::Fix => apply_rounding_function(value, f64::trunc),
+            GwNumericFunctionKind::Cint =>
+                Ok(ExpressionEvalResult::IntegerResult(to_integer_operand(&value)?)),
+            GwNumericFunctionKind::Csng => {
+                let single_value = get_double_value(&value).unwrap_or(0.0) as f32;
+                if single_value.is_finite() {
+                    Ok(ExpressionEvalResult::SingleResult(single_value))
+                } else {
+                    Err(GwError::Overflow)
+                }
+            }
+            GwNumericFunctionKind::Cdbl =>
+                Ok(ExpressionEvalResult::DoubleResult(get_double_value(&value).unwrap_or(0.0))),
+            GwNumericFunctionKind::HexStr =>
+                Ok(ExpressionEvalResult::StringResult(format!("{:X}", to_unsigned_word(&value)?))),
+            GwNumericFunctionKind::OctStr =>
+                Ok(ExpressionEvalResult::StringResult(format!("{:o}", to_unsigned_word(&value)?)))
+        }
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push_str(self.kind.name());
+        buffer.push('(');
+        self.expr.fill_structure_string(buffer);
+        buffer.push(')');
+    }
+}
+
+
+#[cfg(test)]
+mod numeric_func_tests {
+    use crate::eval::{GwStringLiteral, GwIntegerLiteral, GwDoubleLiteral};
+
+    use super::*;
+    use super::super::eval_tests::empty_context;
+
+    fn eval_function(kind: GwNumericFunctionKind,
+                     expr: Box<dyn GwExpression>) -> Result<ExpressionEvalResult, GwError> {
+        let mut ctxt = empty_context();
+        GwNumericFunction::new(kind, expr).eval(&mut ctxt)
+    }
+
+    fn double_lit(value: f64) -> Box<dyn GwExpression> {
+        Box::new(GwDoubleLiteral::with_value(value))
+    }
+
+    fn int_lit(value: i16) -> Box<dyn GwExpression> {
+        Box::new(GwIntegerLiteral::with_value(value))
+    }
+
+    #[test]
+    fn it_evaluates_float_functions() {
+        assert_eq!(Ok(ExpressionEvalResult::SingleResult(3.0)),
+                   eval_function(GwNumericFunctionKind::Sqr, int_lit(9)));
+        assert_eq!(Err(GwError::IllegalFunctionCall),
+                   eval_function(GwNumericFunctionKind::Sqr, int_lit(-1)));
+        assert_eq!(Ok(ExpressionEvalResult::SingleResult(1.0)),
+                   eval_function(GwNumericFunctionKind::Exp, int_lit(0)));
+        assert_eq!(Err(GwError::Overflow),
+                   eval_function(GwNumericFunctionKind::Exp, int_lit(100)));
+        assert_eq!(Ok(ExpressionEvalResult::SingleResult(0.0)),
+                   eval_function(GwNumericFunctionKind::Atn, int_lit(0)));
+        assert_eq!(Err(GwError::TypeMismatch),
+                   eval_function(GwNumericFunctionKind::Tan,
+                                 Box::new(GwStringLiteral::with_value("1".to_string()))));
+    }
+
+    #[test]
+    fn it_evaluates_conversion_functions() {
+        assert_eq!(Ok(ExpressionEvalResult::IntegerResult(-1)),
+                   eval_function(GwNumericFunctionKind::Sgn, double_lit(-2.5)));
+        assert_eq!(Ok(ExpressionEvalResult::DoubleResult(-2.0)),
+                   eval_function(GwNumericFunctionKind::Fix, double_lit(-2.5)));
+        assert_eq!(Ok(ExpressionEvalResult::IntegerResult(7)),
+                   eval_function(GwNumericFunctionKind::Fix, int_lit(7)));
+        assert_eq!(Ok(ExpressionEvalResult::IntegerResult(3)),
+                   eval_function(GwNumericFunctionKind::Cint, double_lit(2.6)));
+        assert_eq!(Err(GwError::Overflow),
+                   eval_function(GwNumericFunctionKind::Cint, double_lit(40000.0)));
+        assert_eq!(Ok(ExpressionEvalResult::DoubleResult(7.0)),
+                   eval_function(GwNumericFunctionKind::Cdbl, int_lit(7)));
+        assert_eq!(Ok(ExpressionEvalResult::SingleResult(7.0)),
+                   eval_function(GwNumericFunctionKind::Csng, int_lit(7)));
+    }
+
+    #[test]
+    fn it_formats_hexadecimal_and_octal_strings() {
+        assert_eq!(Ok(ExpressionEvalResult::StringResult("FF".to_string())),
+                   eval_function(GwNumericFunctionKind::HexStr, int_lit(255)));
+        assert_eq!(Ok(ExpressionEvalResult::StringResult("FFFF".to_string())),
+                   eval_function(GwNumericFunctionKind::HexStr, int_lit(-1)));
+        assert_eq!(Ok(ExpressionEvalResult::StringResult("17".to_string())),
+                   eval_function(GwNumericFunctionKind::OctStr, int_lit(15)));
+        assert_eq!(Err(GwError::Overflow),
+                   eval_function(GwNumericFunctionKind::OctStr, double_lit(70000.0)));
+    }
+}
@@ -1,9 +1,16 @@
-use super:: { GwInstruction, GwExpression, get_as_integer, InstructionResult };
+use super:: { GwInstruction, GwExpression, GwError, get_as_integer, InstructionResult,
+              LineExecutionArgument };
 
 
+/// AST element for the `ON x GOTO` and `ON x GOSUB` statements.
+/// For example:
+/// ```basic
+/// ON CHOICE GOSUB 100, 200, 300
+/// ```
 pub struct GwOnGoto {
     expr: Box<dyn GwExpression>,
-    cases: Vec<i16>
+    cases: Vec<i16>,
+    is_gosub: bool
 }
 
 impl GwOnGoto {
@@ -11,33 +18,55 @@ impl GwOnGoto {
                 cases: Vec<i16>) -> GwOnGoto {
         GwOnGoto {
             expr,
-            cases
+            cases,
+            is_gosub: false
+        }
+    }
+
+    pub fn new_gosub(expr: Box<dyn GwExpression>,
+                     cases: Vec<i16>) -> GwOnGoto {
+        GwOnGoto {
+            expr,
+            cases,
+            is_gosub: true
         }
     }
 }
 
 impl GwInstruction for GwOnGoto {
     fn eval(&self,
-            _line: i16,
-            _argument: super::LineExecutionArgument,
+            line: i16,
+            argument: super::LineExecutionArgument,
             context: &mut super::EvaluationContext,
             _program: &mut super::GwProgram) -> super::InstructionResult {
+        if let LineExecutionArgument::SubReturn = argument {
+            return InstructionResult::EvaluateNext;
+        }
 
         let expr_result = self.expr.eval(context);
         let evaluation: usize;
         match expr_result {
             Ok(ref eval) => {
                 match get_as_integer(&Some(eval)) {
-                    Ok(val) => {
+                    Ok(val) if (0..=255).contains(&val) => {
                         evaluation = val as usize;
                     }
+                    Ok(_) => {
+                        return InstructionResult::EvaluateToError(GwError::IllegalFunctionCall);
+                    }
                     Err(error) => {
                         return InstructionResult::EvaluateToError(error);
                     }
                 }
 
-                if let Some(destination) = self.cases.get(evaluation - 1) {
-                    calculate_jump_result(context, destination)
+                if let Some(destination) = evaluation.checked_sub(1).and_then(|index| self.cases.get(index)) {
+                    let jump_result = calculate_jump_result(context, destination);
+                    if self.is_gosub {
+                        if let InstructionResult::EvaluateLine(_) = jump_result {
+                            context.push_return(line);
+                        }
+                    }
+                    jump_result
                 }
                 else {
                     InstructionResult::EvaluateNext
@@ -50,7 +79,7 @@ impl GwInstruction for GwOnGoto {
     fn fill_structure_string(&self, buffer: &mut String) {
         buffer.push_str("ON ");
         self.expr.fill_structure_string(buffer);
-        buffer.push_str(" GOTO ");
+        buffer.push_str(if self.is_gosub { " GOSUB " } else { " GOTO " });
         let c = self.cases.len();
         let mut i = 0;
         for a_case in &self.cases {
@@ -68,7 +97,7 @@ fn calculate_jump_result(context: &mut super::EvaluationContext, destination: &i
     if let Some(real_destination) = context.get_real_line(*destination) {
         InstructionResult::EvaluateLine(real_destination)
     } else {
-        InstructionResult::EvaluateToError(String::from("Cannot find real line"))
+        InstructionResult::EvaluateToError(GwError::UndefinedLineNumber)
     }
 }
 
@@ -88,7 +117,8 @@ mod on_goto_tests {
         ctxt.jump_table.insert(20, 21);
         let on_goto = Rc::new(GwOnGoto {
             expr: Box::new(GwVariableExpression { name: String::from("x") }),
-            cases: vec![10,20,30]
+            cases: vec![10,20,30],
+            is_gosub: false
         });
 
 
@@ -117,7 +147,8 @@ mod on_goto_tests {
         let mut ctxt = EvaluationContext::new(Box::new(DummyConsole{}));
         let on_goto = Rc::new(GwOnGoto {
             expr: Box::new(GwVariableExpression { name: String::from("x") }),
-            cases: vec![10,20,30]
+            cases: vec![10,20,30],
+            is_gosub: false
         });
 
 
@@ -139,4 +170,39 @@ mod on_goto_tests {
             _ => Err("Unexpected state in ON GOTO")
         }
     }    
+
+    #[test]
+    fn it_performs_on_gosub() -> Result<(),& 'static  str> {
+        let mut ctxt = EvaluationContext::new(Box::new(DummyConsole{}));
+        ctxt.jump_table.insert(10, 11);
+        ctxt.jump_table.insert(20, 21);
+        let on_gosub = Rc::new(GwOnGoto::new_gosub(
+            Box::new(GwVariableExpression { name: String::from("x") }),
+            vec![10,20]));
+        let mut program = GwProgram {
+            lines: vec![],
+            real_lines: vec![on_gosub.clone()],
+            data: vec![],
+        };
+
+        let _ = ctxt.set_variable(
+            &String::from("x"),
+            &ExpressionEvalResult::IntegerResult(0));
+        assert!(matches!(on_gosub.eval(4, LineExecutionArgument::Empty, &mut ctxt, &mut program),
+                         InstructionResult::EvaluateNext));
+        assert_eq!(None, ctxt.pop_return());
+
+        let _ = ctxt.set_variable(
+            &String::from("x"),
+            &ExpressionEvalResult::IntegerResult(2));
+        match on_gosub.eval(4, LineExecutionArgument::Empty, &mut ctxt, &mut program) {
+            InstructionResult::EvaluateLine(21) => {}
+            _ => return Err("Unexpected state in ON GOSUB")
+        }
+        assert_eq!(Some(4), ctxt.pop_return());
+        match on_gosub.eval(4, LineExecutionArgument::SubReturn, &mut ctxt, &mut program) {
+            InstructionResult::EvaluateNext => Ok(()),
+            _ => Err("Unexpected state returning to ON GOSUB")
+        }
+    }
 }
@@ -3,82 +3,109 @@ use crate::eval::ExpressionEvalResult;
 use super::{
     EvaluationContext,
     InstructionResult,
+    GwExpression,
     GwInstruction,
     GwProgram,
     LineExecutionArgument,
     PrintSeparator,
-    PrintElementWrapper
+    PrintElementWrapper,
+    PrintTarget,
+    GwError,
+    fill_print_elements_structure_string
 };
+use super::binary::get_double_value;
 
 pub struct GwPrintUsingStat {
+    pub file_number : Option<Box<dyn GwExpression>>,
     pub expressions : Vec<(PrintElementWrapper, Option<PrintSeparator>)>
 }
 
 impl GwPrintUsingStat {
+    /// Prints the arguments with the fields of the format string. The
+    /// format string is reused while there are arguments left and the
+    /// output stops at the first field without an argument
     pub fn print_formatted_string(&self, format_string: &str, context: &mut EvaluationContext) -> InstructionResult {
-        let mut arg_i = 1;
-        loop {
-            let mut tmp_format = &format_string[..];
+        let mut arguments = self.expressions.iter()
+            .skip(1)
+            .filter_map(|(element, _)| match element {
+                PrintElementWrapper::Expr(expr) => Some(expr),
+                _ => None
+            })
+            .peekable();
+        if arguments.peek().is_none() {
+            return InstructionResult::EvaluateToError(GwError::MissingOperand);
+        }
+
+        let mut output = String::new();
+        'format_reuse: loop {
+            let mut tmp_format = format_string;
+            let mut has_fields = false;
             loop {
-                match tok_format_string(tmp_format) {
+                let (field, rest) = match tok_format_string(tmp_format) {
                     PrintUsingFormatFragment::Literal(literal, rest) => {
-                        context.console.print(literal);
+                        output.push_str(literal);
                         tmp_format = rest;
+                        continue;
                     }
-                    PrintUsingFormatFragment::Numeric { dollar, digits, comma, decimals, rest} => {
-                        //tmp_format = rest;
-                        let  value_to_use: f64;
-                        if let Some((PrintElementWrapper::Expr(arg), _)) = self.expressions.get(arg_i) {
-                            match arg.eval(context) {
-                                Ok(ExpressionEvalResult::DoubleResult(dbl)) => {
-                                    value_to_use  = dbl;
-                                }
-                                Ok(ExpressionEvalResult::SingleResult(dbl)) => {
-                                    value_to_use  = dbl as f64;
-                                }
-
-                                Ok(ExpressionEvalResult::IntegerResult(ival)) => {
-                                    value_to_use  = ival as f64;
-                                }
-                                _ => {
-                                    return InstructionResult::EvaluateToError(String::from("Invalid value"))
-                                }
-                            }
-                        } else {
-                            todo!();
-                        }
-
-                        let mut format_buf = String::new();
-                        format_number(value_to_use,
-                                      dollar,
-                                      digits,
-                                      comma,
-                                      decimals,
-                                      &mut format_buf);
-                        context.console.print(format_buf.as_str());
-                        tmp_format = rest;
-                        arg_i += 1;
-                    },
                     PrintUsingFormatFragment::End(last) => {
-                        context.console.print(last);
+                        output.push_str(last);
                         break;
                     }
+                    PrintUsingFormatFragment::Numeric(format, rest) =>
+                        (PrintUsingField::Numeric(format), rest),
+                    PrintUsingFormatFragment::Text(width, rest) =>
+                        (PrintUsingField::Text(width), rest)
+                };
+                has_fields = true;
+                match arguments.next() {
+                    Some(argument) => {
+                        match format_argument(&field, argument.as_ref(), context) {
+                            Ok(text) => output.push_str(&text),
+                            Err(error) => return InstructionResult::EvaluateToError(error)
+                        }
+                    }
+                    None => break 'format_reuse
                 }
+                tmp_format = rest;
+            }
+            if !has_fields {
+                return InstructionResult::EvaluateToError(GwError::IllegalFunctionCall);
             }
-            if arg_i == self.expressions.len() && arg_i != 1 {
+            if arguments.peek().is_none() {
                 break;
             }
         }
-        match self.expressions.last() {
-            Some((_, Some(PrintSeparator::Semicolon))) => {
-            }
-            _ => { context.console.print_line(""); }
+        let printed = PrintTarget::with_file_number(&self.file_number, context)
+            .and_then(|target| match self.expressions.last() {
+                Some((_, Some(_))) => target.print(context, &output),
+                _ => target.print_line(context, &output)
+            });
+        match printed {
+            Ok(_) => InstructionResult::EvaluateNext,
+            Err(error) => InstructionResult::EvaluateToError(error)
         }
-        InstructionResult::EvaluateNext
     }
 
 }
 
+enum PrintUsingField {
+    Numeric(NumericFormat),
+    Text(Option<usize>)
+}
+
+fn format_argument(field: &PrintUsingField,
+                   argument: &dyn GwExpression,
+                   context: &mut EvaluationContext) -> Result<String, GwError> {
+    let value = argument.eval(context)?;
+    match (field, &value) {
+        (PrintUsingField::Text(width), ExpressionEvalResult::StringResult(text)) =>
+            Ok(format_string_field(text, *width)),
+        (PrintUsingField::Numeric(format), _) if value.is_numeric() =>
+            Ok(format_number(get_double_value(&value).unwrap_or(0.0), format)),
+        _ => Err(GwError::TypeMismatch)
+    }
+}
+
 impl GwInstruction for GwPrintUsingStat {
     fn eval (&self,
              _line: i16,
@@ -86,129 +113,269 @@ impl GwInstruction for GwPrintUsingStat {
              context: &mut EvaluationContext,
              _program: &mut GwProgram) -> InstructionResult {
         if let Some((PrintElementWrapper::Expr(expr), _)) = self.expressions.get(0) {
-            if let Ok(ExpressionEvalResult::StringResult(a_atr)) = expr.eval(context) {
-                let format_string = &a_atr.as_str();
-                return self.print_formatted_string(format_string, context);
-            }
-            else {
-                return InstructionResult::EvaluateToError(String::from("Invalid function call"))
+            match expr.eval(context) {
+                Ok(ExpressionEvalResult::StringResult(a_atr)) => {
+                    let format_string = &a_atr.as_str();
+                    self.print_formatted_string(format_string, context)
+                }
+                Ok(_) => InstructionResult::EvaluateToError(GwError::TypeMismatch),
+                Err(error) => InstructionResult::EvaluateToError(error)
             }
         } else {
-            return InstructionResult::EvaluateToError(String::from("Invalid function call"))
+            InstructionResult::EvaluateToError(GwError::MissingOperand)
         }
     }
 
     fn fill_structure_string(&self, buffer : &mut String) {
-        buffer.push_str("PRINT USING ");
+        buffer.push_str("PRINT ");
+        if let Some(file_number) = &self.file_number {
+            buffer.push('#');
+            file_number.fill_structure_string(buffer);
+            buffer.push_str(", ");
+        }
+        buffer.push_str("USING ");
+        fill_print_elements_structure_string(&self.expressions, buffer);
     }
 }
 
-pub fn format_number(
-    value: f64,
-    dollar: bool,
-    digits:i8,
-    comma: bool,
-    decimals: i8,
-    target: &mut String) {
-//    let mut i = 0;
-    let mut tmp = (value as i32).abs();
-    target.clear();
-    if decimals > 0 {
-        let tenspow = (10 as i32).pow(decimals as u32);
-        let mut start = (((value - value.trunc())  * (tenspow as f64)) as i32).abs();
-        let mut idec = decimals;
-        while idec > 0 {
-            let digit = (start % 10) as u32;
-            target.push(char::from_digit(digit , 10).unwrap());
-            start /= 10;
-            idec -= 1;
-          //  i += 1;
-        }
-        target.push('.');
-        //i += 1;
-    }
-    let mut idigits = digits;
-    let mut i = 0;
-    while idigits > 0 {
-        if comma
-            && i > 0
-            && i % 3 == 0
-            && tmp > 0 {
-            target.push(',');
+/// Numeric field of a `PRINT USING` format string such as `**$#,###.##`
+#[derive(Debug, PartialEq, Default)]
+pub struct NumericFormat {
+    /// Positions to the left of the decimal point, including the ones
+    /// of `+`, `**`, `$$` and commas
+    pub width: usize,
+    /// Digits after the decimal point, `None` without a decimal point
+    pub decimals: Option<usize>,
+    /// `$$`: a dollar sign right before the digits
+    pub dollar: bool,
+    /// `**`: leading spaces are filled with asterisks
+    pub asterisk: bool,
+    /// `,`: digits are grouped by thousands
+    pub comma: bool,
+    /// `+` at the start: the sign is always printed before the number
+    pub leading_plus: bool,
+    /// `+` or `-` at the end: the sign is printed after the number
+    pub trailing_sign: Option<char>,
+    /// `^^^^` or `^^^^^`: exponential format with 2 or 3 exponent digits
+    pub exponent_digits: Option<usize>
+}
+
+/// Formats a number with a numeric field. Numbers that do not fit are
+/// printed complete with a `%` prefix
+pub fn format_number(value: f64, format: &NumericFormat) -> String {
+    let decimals = format.decimals.unwrap_or(0);
+    let (mut integer_part, fraction_part, exponent) = match format.exponent_digits {
+        Some(exponent_digits) => {
+            let (mantissa_integer, mantissa_fraction, exponent) =
+                split_exponential(value.abs(), exponent_integer_digits(format), decimals);
+            let exponent_sign = if exponent < 0 { '-' } else { '+' };
+            (mantissa_integer,
+             mantissa_fraction,
+             format!("E{}{:0width$}", exponent_sign, exponent.abs(), width = exponent_digits))
         }
-        if tmp > 0 {
-            let digit = tmp % 10;
-            target.push(char::from_digit(digit as u32 , 10).unwrap());
-            tmp /= 10;
-            if tmp == 0 &&  dollar {
-                target.push('$');
-            }
-        } else if tmp == 0 && i == 0 {
-             target.push('0');
-        } else {
-            target.push(' ');
+        None => {
+            let rounded = format!("{:.*}", decimals, value.abs());
+            let (integer_part, fraction_part) = rounded.split_once('.').unwrap_or((&rounded, ""));
+            let integer_part = if format.comma {
+                group_thousands(integer_part)
+            } else {
+                integer_part.to_string()
+            };
+            (integer_part, fraction_part.to_string(), String::new())
         }
-        idigits -= 1;
-        i += 1;
+    };
+
+    let negative = value < 0.0 && !format!("{}{}", integer_part, fraction_part).chars().all(|c| c == '0' || c == ',');
+    let sign = match (format.leading_plus, format.trailing_sign, negative) {
+        (true, _, true) => "-",
+        (true, _, false) => "+",
+        (false, None, true) => "-",
+        (false, None, false) if format.exponent_digits.is_some() => " ",
+        _ => ""
+    };
+    let dollar = if format.dollar { "$" } else { "" };
+    // The leading zero is dropped when there is no room for it
+    if integer_part == "0" && sign.len() + dollar.len() + 1 > format.width {
+        integer_part.clear();
     }
-    if value < 0.0 {
-        target.push('-');
+    let mut left_side = format!("{}{}{}", sign, dollar, integer_part);
+    let mut right_side = String::new();
+    if format.decimals.is_some() {
+        right_side.push('.');
+        right_side.push_str(&fraction_part);
+    }
+    right_side.push_str(&exponent);
+    match format.trailing_sign {
+        Some('+') => right_side.push(if negative { '-' } else { '+' }),
+        Some(_) => right_side.push(if negative { '-' } else { ' ' }),
+        None => {}
     }
 
+    if left_side.len() > format.width {
+        left_side.insert(0, '%');
+    } else {
+        let fill = if format.asterisk { "*" } else { " " };
+        left_side.insert_str(0, &fill.repeat(format.width - left_side.len()));
+    }
+    left_side + &right_side
+}
 
-    // TODO there must be a better way without unsafe to do this:
-    let new_result:String = target.chars().rev().collect();
-    target.clear();
-    target.insert_str(0, new_result.as_str());
+/// Digits to the left of the point of an exponential field. One of the
+/// positions is kept for the sign if the format doesn't have one
+fn exponent_integer_digits(format: &NumericFormat) -> usize {
+    let digit_positions = format.width - usize::from(format.leading_plus);
+    if format.leading_plus || format.trailing_sign.is_some() {
+        digit_positions
+    } else {
+        digit_positions.saturating_sub(1)
+    }
+}
+
+/// Splits a number in the integer and fraction digits of a mantissa
+/// with `integer_digits` digits before the point and its exponent
+fn split_exponential(value: f64, integer_digits: usize, decimals: usize) -> (String, String, i32) {
+    let mut exponent = if value == 0.0 {
+        0
+    } else {
+        value.log10().floor() as i32 + 1 - integer_digits as i32
+    };
+    let mut mantissa = format!("{:.*}", decimals, value / 10f64.powi(exponent));
+    // Rounding can add a digit, for example 9.996 as 10.00
+    if value != 0.0 && mantissa.split('.').next().map_or(0, |digits| digits.trim_start_matches('0').len()) > integer_digits {
+        exponent += 1;
+        mantissa = format!("{:.*}", decimals, value / 10f64.powi(exponent));
+    }
+    let (integer_part, fraction_part) = mantissa.split_once('.').unwrap_or((&mantissa, ""));
+    let integer_part = if integer_digits == 0 { "" } else { integer_part };
+    (integer_part.to_string(), fraction_part.to_string(), exponent)
+}
+
+fn group_thousands(digits: &str) -> String {
+    let first_group_length = ((digits.len() + 2) % 3 + 1).min(digits.len());
+    let (first_group, rest) = digits.split_at(first_group_length);
+    let mut result = String::from(first_group);
+    for group in rest.as_bytes().chunks(3) {
+        result.push(',');
+        result.push_str(&String::from_utf8_lossy(group));
+    }
+    result
+}
+
+/// Formats a string with a `!` (width 1), `\  \` or `&` (`None`,
+/// the whole string) field
+pub fn format_string_field(value: &str, width: Option<usize>) -> String {
+    match width {
+        Some(width) => format!("{:width$}", value.chars().take(width).collect::<String>(), width = width),
+        None => value.to_string()
+    }
 }
 
 pub enum PrintUsingFormatFragment<'a> {
     Literal(&'a str, &'a str),
-    Numeric { dollar: bool, digits:i8, comma: bool, decimals: i8, rest: &'a str },
+    Numeric(NumericFormat, &'a str),
+    /// `!`, `&` or `\  \` string field with its width
+    Text(Option<usize>, &'a str),
     End(&'a str)
 }
 
-fn tok_numeric_format(input: &str) -> PrintUsingFormatFragment {
-    let mut dollar = false;
-    let mut digits = 0;
-    let mut after_dot = false;
-    let mut comma = false;
-    let mut decimals = 0;
-    let mut i = 0;
-    for c in input.chars() {
-        if c == '$' {
-            dollar = true;
-        }
-        else if c == '#' && !after_dot {
-            digits += 1;
-        }
-        else if c == '#' && after_dot {
-            decimals += 1;
-        }
-        else if c == '.' {
-            after_dot = true;
+fn tok_numeric_format(input: &str) -> PrintUsingFormatFragment<'_> {
+    let mut format = NumericFormat::default();
+    let mut rest = input;
+    if let Some(after_plus) = rest.strip_prefix('+') {
+        format.leading_plus = true;
+        format.width += 1;
+        rest = after_plus;
+    }
+    if let Some(after_prefix) = rest.strip_prefix("**$") {
+        format.asterisk = true;
+        format.dollar = true;
+        format.width += 3;
+        rest = after_prefix;
+    } else if let Some(after_prefix) = rest.strip_prefix("**") {
+        format.asterisk = true;
+        format.width += 2;
+        rest = after_prefix;
+    } else if let Some(after_prefix) = rest.strip_prefix("$$") {
+        format.dollar = true;
+        format.width += 2;
+        rest = after_prefix;
+    }
+    while let Some(c) = rest.chars().next().filter(|c| *c == '#' || *c == ',') {
+        if c == ',' {
+            format.comma = true;
         }
-        else if c == ',' {
-            comma = true;
-        } else {
-            break;
+        format.width += 1;
+        rest = &rest[1..];
+    }
+    if let Some(after_dot) = rest.strip_prefix('.') {
+        let decimals = after_dot.chars().take_while(|c| *c == '#').count();
+        format.decimals = Some(decimals);
+        rest = &after_dot[decimals..];
+    }
+    if let Some(after_carets) = rest.strip_prefix("^^^^^") {
+        format.exponent_digits = Some(3);
+        rest = after_carets;
+    } else if let Some(after_carets) = rest.strip_prefix("^^^^") {
+        format.exponent_digits = Some(2);
+        rest = after_carets;
+    }
+    if !format.leading_plus {
+        if let Some(sign @ ('+' | '-')) = rest.chars().next() {
+            format.trailing_sign = Some(sign);
+            rest = &rest[1..];
         }
-        i += c.len_utf8();
     }
-    return PrintUsingFormatFragment::Numeric {
-        dollar, digits, comma, decimals, rest: &input[i..]
-    };
+    PrintUsingFormatFragment::Numeric(format, rest)
+}
+
+fn starts_numeric_field(input: &str) -> bool {
+    let input = input.strip_prefix('+').unwrap_or(input);
+    input.starts_with('#')
+        || input.starts_with(".#")
+        || input.starts_with("**")
+        || input.starts_with("$$")
+}
+
+/// Returns the width of a `\  \` field at the start of `input`
+fn string_field_width(input: &str) -> Option<usize> {
+    let after_start = input.strip_prefix('\\')?;
+    let spaces = after_start.chars().take_while(|c| *c == ' ').count();
+    if after_start[spaces..].starts_with('\\') {
+        Some(spaces + 2)
+    } else {
+        None
+    }
 }
 
 pub fn tok_format_string<'a>(input: &'a str) -> PrintUsingFormatFragment<'a> {
     let mut i = 0;
     for c in input.chars() {
-        if c == '#' || c == '$'  {
+        let remaining = &input[i..];
+        let starts_field = starts_numeric_field(remaining)
+            || c == '!'
+            || c == '&'
+            || c == '_'
+            || string_field_width(remaining).is_some();
+        if starts_field {
             if i > 0 {
                 return PrintUsingFormatFragment::Literal(&input[0..i], &input[i..])
-            } else {
+            } else if starts_numeric_field(input) {
                 return tok_numeric_format(input);
             }
+            return match c {
+                '!' => PrintUsingFormatFragment::Text(Some(1), &input[1..]),
+                '&' => PrintUsingFormatFragment::Text(None, &input[1..]),
+                // `_` prints the next character literally
+                '_' => {
+                    let escaped_length = input[1..].chars().next().map_or(0, |c| c.len_utf8());
+                    PrintUsingFormatFragment::Literal(&input[1..1 + escaped_length],
+                                                      &input[1 + escaped_length..])
+                }
+                _ => {
+                    let width = string_field_width(input).unwrap_or(2);
+                    PrintUsingFormatFragment::Text(Some(width), &input[width..])
+                }
+            };
         }
         i += c.len_utf8();
     }
@@ -219,6 +386,17 @@ pub fn tok_format_string<'a>(input: &'a str) -> PrintUsingFormatFragment<'a> {
 mod print_using_tests {
     use crate::eval::print_using::*;
 
+    fn numeric_format(format_string: &str) -> NumericFormat {
+        match tok_format_string(format_string) {
+            PrintUsingFormatFragment::Numeric(format, "") => format,
+            _ => panic!("Format not recognized")
+        }
+    }
+
+    fn format_with(format_string: &str, value: f64) -> String {
+        format_number(value, &numeric_format(format_string))
+    }
+
     #[test]
     fn it_process_string_without_format() {
         let result = tok_format_string("This is a test");
@@ -243,7 +421,7 @@ mod print_using_tests {
     #[test]
     fn it_tokenize_only_format_string() {
         let mut result = tok_format_string("###.##");
-        if let PrintUsingFormatFragment::Numeric{ rest, .. } = result {
+        if let PrintUsingFormatFragment::Numeric(_, rest) = result {
             assert_eq!("", rest);
             result = tok_format_string(rest);
             if let PrintUsingFormatFragment::End(last) = result {
@@ -259,13 +437,15 @@ mod print_using_tests {
     #[test]
     fn it_tokenize_only_format_string_with_currency() -> Result<(), & 'static str> {
         let mut result = tok_format_string("$$###,###,###.##");
-        if let PrintUsingFormatFragment::Numeric {
-                dollar:true,
-                digits: 9,
-                comma: true,
-                decimals: 2,
-                rest
-            } = result {
+        if let PrintUsingFormatFragment::Numeric(
+                NumericFormat {
+                    dollar: true,
+                    width: 13,
+                    comma: true,
+                    decimals: Some(2),
+                    ..
+                },
+                rest) = result {
             assert_eq!("", rest);
             result = tok_format_string(rest);
             if let PrintUsingFormatFragment::End(_) = result {
@@ -285,13 +465,15 @@ mod print_using_tests {
             assert_eq!("This is ", literal);
             assert_eq!("### a test", rest1);
 
-            if let PrintUsingFormatFragment::Numeric{
-                dollar:false,
-                digits: 3,
-                comma: false,
-                decimals: 0,
-                rest: rest2
-            } = tok_format_string(rest1) {
+            if let PrintUsingFormatFragment::Numeric(
+                NumericFormat {
+                    dollar: false,
+                    width: 3,
+                    comma: false,
+                    decimals: None,
+                    ..
+                },
+                rest2) = tok_format_string(rest1) {
                 assert_eq!(" a test", rest2);
             } else {
                 assert!(false);
@@ -304,13 +486,15 @@ mod print_using_tests {
     #[test]
     fn it_process_string_with_numeric_at_start() -> Result<(), & 'static str> {
         let result = tok_format_string("####.#### %");
-        if let PrintUsingFormatFragment::Numeric{
-                dollar:false,
-                digits: 4,
-                comma: false,
-                decimals: 4,
-                rest
-            } = result {
+        if let PrintUsingFormatFragment::Numeric(
+                NumericFormat {
+                    dollar: false,
+                    width: 4,
+                    comma: false,
+                    decimals: Some(4),
+                    ..
+                },
+                rest) = result {
             assert_eq!(" %", rest);
             Ok(())
         } else {
@@ -318,63 +502,77 @@ mod print_using_tests {
         }
     }
 
+    #[test]
+    fn it_tokenizes_string_fields_and_escapes() {
+        assert!(matches!(tok_format_string("!rest"), PrintUsingFormatFragment::Text(Some(1), "rest")));
+        assert!(matches!(tok_format_string("&rest"), PrintUsingFormatFragment::Text(None, "rest")));
+        assert!(matches!(tok_format_string("\\  \\rest"), PrintUsingFormatFragment::Text(Some(4), "rest")));
+        assert!(matches!(tok_format_string("_##"), PrintUsingFormatFragment::Literal("#", "#")));
+        assert!(matches!(tok_format_string("$ \\x"), PrintUsingFormatFragment::End("$ \\x")));
+    }
+
     #[test]
     fn it_formats_less_than_one_num() {
-        let mut the_string = String::new();
-        format_number(0.245,
-                      false,
-                      4,
-                      false,
-                      2,
-                      &mut the_string);
-        assert_eq!("   0.24", the_string);
-    }
-    
+        assert_eq!("   0.24", format_with("####.##", 0.245));
+        assert_eq!(" 0.78", format_with("##.##", 0.78));
+        assert_eq!(".50", format_with(".##", 0.5));
+    }
+
     #[test]
     fn it_formats_simple_num() {
-        let mut the_string = String::new();
-        format_number(482.245,
-                      false,
-                      4,
-                      false,
-                      2,
-                      &mut the_string);
-        assert_eq!(" 482.24", the_string);
+        assert_eq!(" 482.25", format_with("####.##", 482.245));
+        assert_eq!("987.65", format_with("###.##", 987.654));
+        assert_eq!("  6", format_with("###", 5.5));
     }
 
     #[test]
     fn it_formats_simple_negative_num() {
-        let mut the_string = String::new();
-        format_number(-482.245,
-                      false,
-                      3,
-                      false,
-                      2,
-                      &mut the_string);
-        assert_eq!("-482.24", the_string);
+        assert_eq!("-482.25", format_with("####.##", -482.245));
+        assert_eq!("%-482.25", format_with("###.##", -482.245));
     }
 
     #[test]
     fn it_formats_with_currency() {
-        let mut the_string = String::new();
-        format_number(27749.479,
-                      true,
-                      9,
-                      true,
-                      2,
-                      &mut the_string);
-        assert_eq!("    $27,749.47", the_string);
+        assert_eq!("      $27,749.48", format_with("$$###,###,###.##", 27749.479));
+        assert_eq!(" $456.78", format_with("$$###.##", 456.78));
+        assert_eq!("***$2.34", format_with("**$##.##", 2.34));
     }
 
     #[test]
     fn it_formats_simple_num_with_comma() {
-        let mut the_string = String::new();
-        format_number(4331.245,
-                      false,
-                      6,
-                      true,
-                      2,
-                      &mut the_string);
-        assert_eq!("  4,331.24", the_string);
+        assert_eq!("  4,331.24", format_with("######,.##", 4331.245));
+        assert_eq!("1,234.50", format_with("####,.##", 1234.5));
+    }
+
+    #[test]
+    fn it_formats_with_asterisks_and_signs() {
+        assert_eq!("*12.4", format_with("**#.#", 12.39));
+        assert_eq!("*-0.9", format_with("**#.#", -0.9));
+        assert_eq!("-68.95", format_with("+##.##", -68.95));
+        assert_eq!(" +2.40", format_with("+##.##", 2.4));
+        assert_eq!("68.95-", format_with("##.##-", -68.95));
+        assert_eq!("22.45 ", format_with("##.##-", 22.449));
+    }
+
+    #[test]
+    fn it_formats_exponential_numbers() {
+        assert_eq!(" 2.35E+02", format_with("##.##^^^^", 234.56));
+        assert_eq!(".8889E+06-", format_with(".####^^^^-", -888888.0));
+        assert_eq!("+.12E+03", format_with("+.##^^^^", 123.0));
+        assert_eq!(" 1.00E+001", format_with("##.##^^^^^", 9.996));
+    }
+
+    #[test]
+    fn it_marks_overflowing_numbers() {
+        assert_eq!("%111.22", format_with("##.##", 111.22));
+        assert_eq!("%1.00", format_with(".##", 0.999));
+    }
+
+    #[test]
+    fn it_formats_string_fields() {
+        assert_eq!("L", format_string_field("LOOK", Some(1)));
+        assert_eq!("LOO", format_string_field("LOOK", Some(3)));
+        assert_eq!("OK  ", format_string_field("OK", Some(4)));
+        assert_eq!("LOOK", format_string_field("LOOK", None));
     }
 }
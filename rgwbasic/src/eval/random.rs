@@ -0,0 +1,256 @@
+use super::{ EvaluationContext, LineExecutionArgument, ExpressionEvalResult,
+             InstructionResult, GwInstruction, GwExpression, GwProgram,
+             GwError, EvaluationError, AsyncAction };
+use super::binary::to_integer_operand;
+
+const RND_MULTIPLIER: u32 = 214013;
+const RND_INCREMENT: u32 = 2531011;
+const RND_MODULUS: u32 = 1 << 24;
+const RND_INITIAL_SEED: u32 = 0x4F_C752;
+
+/// Linear congruential generator used by `RND`. It uses the
+/// same 24 bits state, constants and initial seed as GW-BASIC so
+/// the sequences are reproducible
+pub struct RandomGenerator {
+    seed: u32,
+    last_value: f32
+}
+
+impl RandomGenerator {
+    pub fn new() -> RandomGenerator {
+        RandomGenerator {
+            seed: RND_INITIAL_SEED,
+            last_value: RND_INITIAL_SEED as f32 / RND_MODULUS as f32
+        }
+    }
+
+    /// Returns the next value of the sequence, between 0 and 1
+    pub fn next_value(&mut self) -> f32 {
+        self.seed = (self.seed.wrapping_mul(RND_MULTIPLIER).wrapping_add(RND_INCREMENT)) % RND_MODULUS;
+        self.last_value = self.seed as f32 / RND_MODULUS as f32;
+        self.last_value
+    }
+
+    pub fn last_value(&self) -> f32 {
+        self.last_value
+    }
+
+    /// Implements `RANDOMIZE n`: the seed replaces the two highest
+    /// bytes of the initial state, so the same seed always produces
+    /// the same sequence
+    pub fn randomize(&mut self, seed: i16) {
+        self.seed = (RND_INITIAL_SEED & 0xFF) | (u32::from(seed as u16) << 8);
+    }
+
+    /// Implements `RND(x)` with `x < 0`: the state is replaced with the
+    /// bytes of the Microsoft Binary Format representation of `x`
+    pub fn reseed(&mut self, value: f32) {
+        let bits = value.to_bits();
+        let exponent = (bits >> 23) & 0xFF;
+        let mbf_exponent = if exponent == 0 { 0 } else { (exponent + 2) & 0xFF };
+        let mantissa = (bits & 0x7F_FFFF) | ((bits >> 31) << 23);
+        self.seed = (mantissa & 0xFF_FF00) | ((mantissa + mbf_exponent) & 0xFF);
+    }
+}
+
+impl Default for RandomGenerator {
+    fn default() -> Self {
+        RandomGenerator::new()
+    }
+}
+
+/// AST element for the `RND` function.
+/// `RND(x)` with `x < 0` reseeds the generator, `RND(0)` repeats the
+/// last value and `RND` or `RND(x)` with `x > 0` returns the next value
+pub struct GwRnd {
+    pub expr: Option<Box<dyn GwExpression>>,
+}
+
+impl GwExpression for GwRnd {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let argument = match &self.expr {
+            Some(expr) => match expr.eval(context)? {
+                ExpressionEvalResult::IntegerResult(value) => value as f32,
+                ExpressionEvalResult::SingleResult(value) => value,
+                ExpressionEvalResult::DoubleResult(value) => value as f32,
+                ExpressionEvalResult::StringResult(_) => return Err(GwError::TypeMismatch)
+            },
+            None => 1.0
+        };
+        let value = if argument == 0.0 {
+            context.random.last_value()
+        } else {
+            if argument < 0.0 {
+                context.random.reseed(argument);
+            }
+            context.random.next_value()
+        };
+        Ok(ExpressionEvalResult::SingleResult(value))
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push_str("RND");
+        if let Some(expr) = &self.expr {
+            buffer.push('(');
+            expr.fill_structure_string(buffer);
+            buffer.push(')');
+        }
+    }
+}
+
+/// AST element for the `TIMER` function, the number of seconds
+/// elapsed since midnight
+pub struct GwTimer {}
+
+impl GwExpression for GwTimer {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        Ok(ExpressionEvalResult::SingleResult(context.console.seconds_since_midnight() as f32))
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push_str("TIMER");
+    }
+}
+
+/// AST element for the `RANDOMIZE` statement.
+/// Without a seed the user is asked for one.
+/// For example:
+/// ```basic
+/// RANDOMIZE TIMER
+/// ```
+pub struct GwRandomize {
+    seed_expr: Option<Box<dyn GwExpression>>
+}
+
+impl GwRandomize {
+    pub fn new(seed_expr: Option<Box<dyn GwExpression>>) -> GwRandomize {
+        GwRandomize { seed_expr }
+    }
+}
+
+fn randomize_with_text(text: &str, context: &mut EvaluationContext) -> InstructionResult {
+    match text.trim().parse::<f64>() {
+        Ok(value) => match to_integer_operand(&ExpressionEvalResult::DoubleResult(value)) {
+            Ok(seed) => {
+                context.random.randomize(seed);
+                InstructionResult::EvaluateNext
+            }
+            Err(error) => InstructionResult::EvaluateToError(error)
+        },
+        Err(_) => InstructionResult::EvaluateToError(GwError::TypeMismatch)
+    }
+}
+
+impl GwInstruction for GwRandomize {
+    fn eval (&self,
+             _line: i16,
+             arg: LineExecutionArgument,
+             context : &mut EvaluationContext,
+             _program: &mut GwProgram) -> InstructionResult {
+        if let LineExecutionArgument::SupplyPendingResult(ref text) = arg {
+            return randomize_with_text(text, context);
+        }
+        match &self.seed_expr {
+            Some(expr) => {
+                let seed = match expr.eval(context) {
+                    Ok(ExpressionEvalResult::SingleResult(value)) if value.abs() > 32767.0 =>
+                        // Large values such as `TIMER` keep their low bits
+                        value as i32 as i16,
+                    Ok(value) => match to_integer_operand(&value) {
+                        Ok(seed) => seed,
+                        Err(error) => return InstructionResult::EvaluateToError(error)
+                    },
+                    Err(error) => return InstructionResult::EvaluateToError(error)
+                };
+                context.random.randomize(seed);
+                InstructionResult::EvaluateNext
+            }
+            None => {
+                context.console.print("Random number seed (-32768 to 32767)? ");
+                if context.console.requires_async_readline() {
+                    InstructionResult::RequestAsyncAction(AsyncAction::ReadLine)
+                } else {
+                    let mut buffer = String::new();
+                    context.console.read_line(&mut buffer);
+                    randomize_with_text(&buffer, context)
+                }
+            }
+        }
+    }
+
+    fn fill_structure_string(&self, buffer : &mut String) {
+        buffer.push_str("RANDOMIZE");
+        if let Some(expr) = &self.seed_expr {
+            buffer.push(' ');
+            expr.fill_structure_string(buffer);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod random_tests {
+    use crate::eval::GwIntegerLiteral;
+    use crate::eval::eval_tests::{empty_context, empty_program};
+
+    use super::*;
+
+    fn rnd_call(argument: Option<i16>) -> GwRnd {
+        GwRnd {
+            expr: argument.map(|value| {
+                let expr: Box<dyn GwExpression> = Box::new(GwIntegerLiteral::with_value(value));
+                expr
+            })
+        }
+    }
+
+    fn next_states(generator: &mut RandomGenerator, count: usize) -> Vec<u32> {
+        (0..count)
+            .map(|_| (generator.next_value() * RND_MODULUS as f32) as u32)
+            .collect()
+    }
+
+    #[test]
+    fn it_generates_the_lcg_sequence() {
+        // States of the first values printed by `PRINT RND` in a new
+        // GW-BASIC session: .1213501 .651861 .8688611 .7297624 .798853
+        let mut generator = RandomGenerator::new();
+        assert_eq!(vec![2035917, 10936412, 14577071, 12243382, 13402529],
+                   next_states(&mut generator, 5));
+
+        generator.randomize(42);
+        assert_eq!(vec![5892045, 1203036, 4717743], next_states(&mut generator, 3));
+    }
+
+    #[test]
+    fn it_repeats_and_reseeds_with_rnd_arguments() -> Result<(), GwError> {
+        let mut ctxt = empty_context();
+        let next = rnd_call(None).eval(&mut ctxt)?;
+        assert_eq!(next, rnd_call(Some(0)).eval(&mut ctxt)?);
+        assert_ne!(next, rnd_call(Some(1)).eval(&mut ctxt)?);
+
+        let reseeded = rnd_call(Some(-3)).eval(&mut ctxt)?;
+        rnd_call(Some(1)).eval(&mut ctxt)?;
+        assert_eq!(reseeded, rnd_call(Some(-3)).eval(&mut ctxt)?);
+        Ok(())
+    }
+
+    #[test]
+    fn it_restarts_sequences_with_randomize() -> Result<(), GwError> {
+        let mut ctxt = empty_context();
+        let mut program = empty_program();
+        let randomize = GwRandomize::new(Some(Box::new(GwIntegerLiteral::with_value(42))));
+
+        randomize.eval(10, LineExecutionArgument::Empty, &mut ctxt, &mut program);
+        let first = rnd_call(None).eval(&mut ctxt)?;
+        randomize.eval(10, LineExecutionArgument::Empty, &mut ctxt, &mut program);
+        assert_eq!(first, rnd_call(None).eval(&mut ctxt)?);
+
+        GwRandomize::new(None).eval(
+            10, LineExecutionArgument::SupplyPendingResult("7".to_string()), &mut ctxt, &mut program);
+        assert_ne!(first, rnd_call(None).eval(&mut ctxt)?);
+        Ok(())
+    }
+}
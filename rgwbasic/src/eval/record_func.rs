@@ -0,0 +1,222 @@
+use super::{ ExpressionType,
+             ExpressionEvalResult,
+             EvaluationContext,
+             EvaluationError,
+             GwExpression,
+             GwError };
+use super::binary::{ get_double_value, to_integer_operand };
+use super::string_func::{ bytes_to_string,
+                          eval_string_argument,
+                          fill_function_structure_string,
+                          string_to_bytes };
+
+/// Converts a single to the 4 bytes of its Microsoft Binary Format.
+/// The last byte has the exponent, biased by 129 for a mantissa
+/// between 1 and 2, and the sign takes the place of the leading
+/// bit of the mantissa
+pub fn single_to_mbf(value: f32) -> Result<[u8; 4], GwError> {
+    let bits = value.to_bits();
+    let ieee_exponent = (bits >> 23) & 0xFF;
+    if ieee_exponent == 0 {
+        return Ok([0; 4]);
+    }
+    let exponent = ieee_exponent + 2;
+    if exponent > 0xFF {
+        return Err(GwError::Overflow);
+    }
+    let [low, middle, high, _] = ((bits & 0x7F_FFFF) | ((bits >> 31) << 23)).to_le_bytes();
+    Ok([low, middle, high, exponent as u8])
+}
+
+/// Converts a double to the 8 bytes of its Microsoft Binary Format,
+/// which has the exponent of a single and a 55 bits mantissa
+pub fn double_to_mbf(value: f64) -> Result<[u8; 8], GwError> {
+    let bits = value.to_bits();
+    let ieee_exponent = ((bits >> 52) & 0x7FF) as i32;
+    if ieee_exponent == 0 {
+        return Ok([0; 8]);
+    }
+    let exponent = ieee_exponent - 1023 + 129;
+    if exponent <= 0 {
+        return Ok([0; 8]);
+    }
+    if exponent > 0xFF {
+        return Err(GwError::Overflow);
+    }
+    let mantissa = ((bits & 0xF_FFFF_FFFF_FFFF) << 3) | ((bits >> 63) << 55);
+    let mut result = mantissa.to_le_bytes();
+    result[7] = exponent as u8;
+    Ok(result)
+}
+
+/// Value of a Microsoft Binary Format number given its exponent byte
+/// and the other bytes, which have the sign followed by the
+/// `mantissa_bits` of the mantissa
+fn mbf_value(exponent: u8, sign_and_mantissa: u64, mantissa_bits: i32) -> f64 {
+    if exponent == 0 {
+        return 0.0;
+    }
+    let mantissa = (sign_and_mantissa & ((1 << mantissa_bits) - 1)) | (1 << mantissa_bits);
+    let value = mantissa as f64 * 2f64.powi(i32::from(exponent) - 129 - mantissa_bits);
+    if sign_and_mantissa >> mantissa_bits & 1 == 1 { -value } else { value }
+}
+
+pub fn mbf_to_single(bytes: &[u8; 4]) -> f32 {
+    let sign_and_mantissa = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]);
+    mbf_value(bytes[3], u64::from(sign_and_mantissa), 23) as f32
+}
+
+/// Converts the 8 bytes of a double in Microsoft Binary Format, the
+/// last 3 bits of the mantissa are rounded
+pub fn mbf_to_double(bytes: &[u8; 8]) -> f64 {
+    let mut sign_and_mantissa = *bytes;
+    sign_and_mantissa[7] = 0;
+    mbf_value(bytes[7], u64::from_le_bytes(sign_and_mantissa), 55)
+}
+
+/// Number of bytes of the strings made by `MKI$`, `MKS$` and `MKD$`
+fn record_size(number_type: ExpressionType) -> usize {
+    match number_type {
+        ExpressionType::Integer => 2,
+        ExpressionType::Double => 8,
+        _ => 4
+    }
+}
+
+/// AST element for `MKI$(n)`, `MKS$(n)` and `MKD$(n)` which convert
+/// a number to the string of bytes used to store it in a random
+/// file record
+pub struct GwMakeStr {
+    number_type: ExpressionType,
+    expr: Box<dyn GwExpression>
+}
+
+impl GwMakeStr {
+    pub fn new(number_type: ExpressionType, expr: Box<dyn GwExpression>) -> GwMakeStr {
+        GwMakeStr { number_type, expr }
+    }
+}
+
+impl GwExpression for GwMakeStr {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let value = self.expr.eval(context)?;
+        let number = get_double_value(&value).ok_or(GwError::TypeMismatch)?;
+        let bytes = match self.number_type {
+            ExpressionType::Integer => to_integer_operand(&value)?.to_le_bytes().to_vec(),
+            ExpressionType::Double => double_to_mbf(number)?.to_vec(),
+            _ => single_to_mbf(number as f32)?.to_vec()
+        };
+        Ok(ExpressionEvalResult::StringResult(bytes_to_string(&bytes)))
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        let name = match self.number_type {
+            ExpressionType::Integer => "MKI$",
+            ExpressionType::Double => "MKD$",
+            _ => "MKS$"
+        };
+        fill_function_structure_string(name, &[self.expr.as_ref()], buffer);
+    }
+}
+
+/// AST element for `CVI(s$)`, `CVS(s$)` and `CVD(s$)` which convert
+/// the strings made by `MKI$`, `MKS$` and `MKD$` back to numbers
+pub struct GwConvertStr {
+    number_type: ExpressionType,
+    expr: Box<dyn GwExpression>
+}
+
+impl GwConvertStr {
+    pub fn new(number_type: ExpressionType, expr: Box<dyn GwExpression>) -> GwConvertStr {
+        GwConvertStr { number_type, expr }
+    }
+}
+
+impl GwExpression for GwConvertStr {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let bytes = string_to_bytes(&eval_string_argument(self.expr.as_ref(), context)?);
+        let size = record_size(self.number_type);
+        if bytes.len() < size {
+            return Err(GwError::IllegalFunctionCall);
+        }
+        Ok(match self.number_type {
+            ExpressionType::Integer =>
+                ExpressionEvalResult::IntegerResult(i16::from_le_bytes([bytes[0], bytes[1]])),
+            ExpressionType::Double => {
+                let mut number_bytes = [0; 8];
+                number_bytes.copy_from_slice(&bytes[..size]);
+                ExpressionEvalResult::DoubleResult(mbf_to_double(&number_bytes))
+            }
+            _ => {
+                let mut number_bytes = [0; 4];
+                number_bytes.copy_from_slice(&bytes[..size]);
+                ExpressionEvalResult::SingleResult(mbf_to_single(&number_bytes))
+            }
+        })
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        let name = match self.number_type {
+            ExpressionType::Integer => "CVI",
+            ExpressionType::Double => "CVD",
+            _ => "CVS"
+        };
+        fill_function_structure_string(name, &[self.expr.as_ref()], buffer);
+    }
+}
+
+#[cfg(test)]
+mod record_func_tests {
+    use crate::eval::{GwStringLiteral, GwIntegerLiteral};
+    use super::*;
+    use super::super::eval_tests::empty_context;
+
+    #[test]
+    fn it_converts_singles_to_mbf() -> Result<(), GwError> {
+        assert_eq!([0x00, 0x00, 0x00, 0x81], single_to_mbf(1.0)?);
+        assert_eq!([0x00, 0x00, 0x80, 0x81], single_to_mbf(-1.0)?);
+        assert_eq!([0x00, 0x00, 0x20, 0x84], single_to_mbf(10.0)?);
+        assert_eq!([0xCD, 0xCC, 0x4C, 0x7D], single_to_mbf(0.1)?);
+        assert_eq!([0x00; 4], single_to_mbf(0.0)?);
+        assert_eq!(Err(GwError::Overflow), single_to_mbf(f32::MAX));
+        for value in [1.0, -2.5, 0.1, 123456.7, -1.0e-30, 1.0e38] {
+            assert_eq!(value, mbf_to_single(&single_to_mbf(value)?));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_converts_doubles_to_mbf() -> Result<(), GwError> {
+        assert_eq!([0, 0, 0, 0, 0, 0, 0x00, 0x81], double_to_mbf(1.0)?);
+        assert_eq!([0, 0, 0, 0, 0, 0, 0x20, 0x84], double_to_mbf(10.0)?);
+        assert_eq!([0, 0, 0, 0, 0, 0, 0x80, 0x81], double_to_mbf(-1.0)?);
+        assert_eq!([0x00; 8], double_to_mbf(1.0e-300)?);
+        assert_eq!(Err(GwError::Overflow), double_to_mbf(1.0e300));
+        for value in [1.0, -2.5, 0.1, 1234567890.125, -1.0e-30, 1.0e38] {
+            assert_eq!(value, mbf_to_double(&double_to_mbf(value)?));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_makes_and_converts_record_strings() -> Result<(), GwError> {
+        let mut ctxt = empty_context();
+        let mki = GwMakeStr::new(ExpressionType::Integer, Box::new(GwIntegerLiteral::with_value(-2)));
+        assert_eq!("\u{FE}\u{FF}", mki.eval(&mut ctxt)?.assume_string_value()?);
+
+        let cvi = GwConvertStr::new(ExpressionType::Integer,
+                                    Box::new(GwStringLiteral::with_value("\u{FE}\u{FF}".to_string())));
+        assert_eq!(ExpressionEvalResult::IntegerResult(-2), cvi.eval(&mut ctxt)?);
+
+        let cvs = GwConvertStr::new(ExpressionType::Single,
+                                    Box::new(GwStringLiteral::with_value("\0\0\u{20}\u{84}".to_string())));
+        assert_eq!(ExpressionEvalResult::SingleResult(10.0), cvs.eval(&mut ctxt)?);
+
+        let cvd = GwConvertStr::new(ExpressionType::Double,
+                                    Box::new(GwStringLiteral::with_value("\0\0\u{20}\u{84}".to_string())));
+        assert_eq!(Err(GwError::IllegalFunctionCall), cvd.eval(&mut ctxt));
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use super::{ EvaluationContext,
+             GwExpression,
+             GwInstruction,
+             GwProgram,
+             LineExecutionArgument,
+             InstructionResult,
+             GwError };
+use super::file_instr::eval_file_name;
+use super::string_func::string_to_bytes;
+use crate::extractor::{ protect, tokenize_program };
+
+/// Format of the files written by `SAVE`, chosen by its `A` and `P` options
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SaveFormat {
+    Tokenized,
+    Ascii,
+    Protected
+}
+
+/// AST element for the `SAVE` statement which writes the program to
+/// a file in the tokenized format of GW-BASIC, with `, A` the listing
+/// of the program is saved as text and with `, P` it is encrypted
+/// ```basic
+/// SAVE "INVENTRY.BAS", A
+/// ```
+pub struct GwSave {
+    file_name: Box<dyn GwExpression>,
+    format: SaveFormat
+}
+
+impl GwSave {
+    pub fn new(file_name: Box<dyn GwExpression>, format: SaveFormat) -> GwSave {
+        GwSave { file_name, format }
+    }
+
+    fn save(&self, context: &mut EvaluationContext, program: &GwProgram) -> Result<(), GwError> {
+        let file_name = eval_file_name(self.file_name.as_ref(), context)?;
+        let listing = program.listing();
+        let contents = match self.format {
+            SaveFormat::Ascii => {
+                let mut text = String::new();
+                for line_text in listing {
+                    text.push_str(&line_text);
+                    text.push_str("\r\n");
+                }
+                string_to_bytes(&text)
+            }
+            SaveFormat::Tokenized => tokenize_program(&listing)?,
+            SaveFormat::Protected => protect(&tokenize_program(&listing)?)
+        };
+        let file_system = context.console.file_system().ok_or(GwError::DeviceUnavailable)?;
+        file_system.write_file(&file_name, &contents)
+    }
+}
+
+impl GwInstruction for GwSave {
+    fn eval(&self,
+            _line: i16,
+            _arg: LineExecutionArgument,
+            context: &mut EvaluationContext,
+            program: &mut GwProgram) -> InstructionResult {
+        match self.save(context, program) {
+            Ok(_) => InstructionResult::EvaluateNext,
+            Err(error) => InstructionResult::EvaluateToError(error)
+        }
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        buffer.push_str("SAVE ");
+        self.file_name.fill_structure_string(buffer);
+        match self.format {
+            SaveFormat::Ascii => buffer.push_str(", A"),
+            SaveFormat::Protected => buffer.push_str(", P"),
+            SaveFormat::Tokenized => {}
+        }
+    }
+}
@@ -0,0 +1,478 @@
+use super::{ GwExpression,
+             EvaluationContext,
+             ExpressionEvalResult,
+             EvaluationError,
+             GwError };
+use super::binary::{ to_integer_operand, single_result, double_result };
+use super::number_format::format_value;
+
+/// Evaluates an integer argument of a function, raising
+/// "Illegal function call" if it is outside `min..=max`
+pub fn eval_integer_argument(expr: &dyn GwExpression,
+                             context: &mut EvaluationContext,
+                             min: i16,
+                             max: i16) -> Result<i16, GwError> {
+    let value = to_integer_operand(&expr.eval(context)?)?;
+    if value < min || value > max {
+        Err(GwError::IllegalFunctionCall)
+    } else {
+        Ok(value)
+    }
+}
+
+pub fn eval_string_argument(expr: &dyn GwExpression,
+                            context: &mut EvaluationContext) -> Result<String, GwError> {
+    match expr.eval(context)? {
+        ExpressionEvalResult::StringResult(value) => Ok(value),
+        _ => Err(GwError::TypeMismatch)
+    }
+}
+
+pub fn fill_function_structure_string(name: &str,
+                                      arguments: &[&dyn GwExpression],
+                                      buffer: &mut String) {
+    buffer.push_str(name);
+    buffer.push('(');
+    let mut c = arguments.len();
+    for argument in arguments {
+        argument.fill_structure_string(buffer);
+        c -= 1;
+        if c != 0 {
+            buffer.push_str(", ");
+        }
+    }
+    buffer.push(')');
+}
+
+/// AST element for `RIGHT$(x$, n)`, the rightmost `n` characters of `x$`
+pub struct GwRightStr {
+    string_expr: Box<dyn GwExpression>,
+    length_expr: Box<dyn GwExpression>
+}
+
+impl GwRightStr {
+    pub fn new(string_expr: Box<dyn GwExpression>,
+               length_expr: Box<dyn GwExpression>) -> GwRightStr {
+        GwRightStr { string_expr, length_expr }
+    }
+}
+
+impl GwExpression for GwRightStr {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let string_value = eval_string_argument(self.string_expr.as_ref(), context)?;
+        let length = eval_integer_argument(self.length_expr.as_ref(), context, 0, 255)? as usize;
+        let char_count = string_value.chars().count();
+        Ok(ExpressionEvalResult::StringResult(
+            string_value.chars().skip(char_count.saturating_sub(length)).collect()))
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        fill_function_structure_string("RIGHT$", &[self.string_expr.as_ref(), self.length_expr.as_ref()], buffer);
+    }
+}
+
+/// AST element for `LEN(x$)`
+pub struct GwLen {
+    expr: Box<dyn GwExpression>
+}
+
+impl GwLen {
+    pub fn new(expr: Box<dyn GwExpression>) -> GwLen {
+        GwLen { expr }
+    }
+}
+
+impl GwExpression for GwLen {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let string_value = eval_string_argument(self.expr.as_ref(), context)?;
+        Ok(ExpressionEvalResult::IntegerResult(string_value.chars().count() as i16))
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        fill_function_structure_string("LEN", &[self.expr.as_ref()], buffer);
+    }
+}
+
+/// AST element for `INSTR([n,] x$, y$)`, the position of `y$` in `x$`
+/// starting the search at position `n`
+pub struct GwInstr {
+    start_expr: Option<Box<dyn GwExpression>>,
+    string_expr: Box<dyn GwExpression>,
+    pattern_expr: Box<dyn GwExpression>
+}
+
+impl GwInstr {
+    pub fn new(start_expr: Option<Box<dyn GwExpression>>,
+               string_expr: Box<dyn GwExpression>,
+               pattern_expr: Box<dyn GwExpression>) -> GwInstr {
+        GwInstr { start_expr, string_expr, pattern_expr }
+    }
+}
+
+impl GwExpression for GwInstr {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let start = match &self.start_expr {
+            Some(start_expr) => eval_integer_argument(start_expr.as_ref(), context, 1, 255)? as usize,
+            None => 1
+        };
+        let string_value: Vec<char> = eval_string_argument(self.string_expr.as_ref(), context)?.chars().collect();
+        let pattern: Vec<char> = eval_string_argument(self.pattern_expr.as_ref(), context)?.chars().collect();
+
+        if start > string_value.len() {
+            return Ok(ExpressionEvalResult::IntegerResult(0));
+        }
+        if pattern.is_empty() {
+            return Ok(ExpressionEvalResult::IntegerResult(start as i16));
+        }
+        let position = string_value[start - 1..]
+            .windows(pattern.len())
+            .position(|window| window == &pattern[..])
+            .map_or(0, |index| index + start);
+        Ok(ExpressionEvalResult::IntegerResult(position as i16))
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        match &self.start_expr {
+            Some(start_expr) =>
+                fill_function_structure_string(
+                    "INSTR", &[start_expr.as_ref(), self.string_expr.as_ref(), self.pattern_expr.as_ref()], buffer),
+            None =>
+                fill_function_structure_string(
+                    "INSTR", &[self.string_expr.as_ref(), self.pattern_expr.as_ref()], buffer)
+        }
+    }
+}
+
+/// Bytes of a string, each character is a code from 0 to 255
+/// as made by `CHR$`
+pub fn string_to_bytes(text: &str) -> Vec<u8> {
+    text.chars().map(|c| c as u32 as u8).collect()
+}
+
+/// String with one character for each byte, the reverse of `string_to_bytes`
+pub fn bytes_to_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| char::from(*byte)).collect()
+}
+
+/// AST element for `CHR$(n)`, the character with ASCII code `n`
+pub struct GwChrStr {
+    expr: Box<dyn GwExpression>
+}
+
+impl GwChrStr {
+    pub fn new(expr: Box<dyn GwExpression>) -> GwChrStr {
+        GwChrStr { expr }
+    }
+}
+
+impl GwExpression for GwChrStr {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let code = eval_integer_argument(self.expr.as_ref(), context, 0, 255)?;
+        Ok(ExpressionEvalResult::StringResult(char::from(code as u8).to_string()))
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        fill_function_structure_string("CHR$", &[self.expr.as_ref()], buffer);
+    }
+}
+
+/// AST element for `ASC(x$)`, the ASCII code of the first character of `x$`
+pub struct GwAsc {
+    expr: Box<dyn GwExpression>
+}
+
+impl GwAsc {
+    pub fn new(expr: Box<dyn GwExpression>) -> GwAsc {
+        GwAsc { expr }
+    }
+}
+
+impl GwExpression for GwAsc {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let string_value = eval_string_argument(self.expr.as_ref(), context)?;
+        if let Some(first_byte) = string_to_bytes(&string_value).first() {
+            Ok(ExpressionEvalResult::IntegerResult(i16::from(*first_byte)))
+        } else {
+            Err(GwError::IllegalFunctionCall)
+        }
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        fill_function_structure_string("ASC", &[self.expr.as_ref()], buffer);
+    }
+}
+
+/// AST element for `STR$(n)`, the string representation of a number.
+/// Positive numbers get a leading space, as when printed
+pub struct GwStrStr {
+    expr: Box<dyn GwExpression>
+}
+
+impl GwStrStr {
+    pub fn new(expr: Box<dyn GwExpression>) -> GwStrStr {
+        GwStrStr { expr }
+    }
+}
+
+impl GwExpression for GwStrStr {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let value = self.expr.eval(context)?;
+        if !value.is_numeric() {
+            return Err(GwError::TypeMismatch);
+        }
+        Ok(ExpressionEvalResult::StringResult(format_value(&value)))
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        fill_function_structure_string("STR$", &[self.expr.as_ref()], buffer);
+    }
+}
+
+/// AST element for `VAL(x$)`, the numeric value of a string.
+/// Blanks are ignored and the conversion stops at the first
+/// character that is not part of a number
+pub struct GwVal {
+    expr: Box<dyn GwExpression>
+}
+
+impl GwVal {
+    pub fn new(expr: Box<dyn GwExpression>) -> GwVal {
+        GwVal { expr }
+    }
+}
+
+impl GwExpression for GwVal {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let string_value = eval_string_argument(self.expr.as_ref(), context)?;
+        parse_numeric_prefix(&string_value)
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        fill_function_structure_string("VAL", &[self.expr.as_ref()], buffer);
+    }
+}
+
+fn parse_radix_prefix(chars: &[char], radix: u32) -> ExpressionEvalResult {
+    let digits: String = chars.iter().take_while(|c| c.is_digit(radix)).collect();
+    let value = u16::from_str_radix(&digits, radix).unwrap_or(0);
+    ExpressionEvalResult::IntegerResult(value as i16)
+}
+
+/// Number at the start of a text, as converted by `VAL`
+pub fn parse_numeric_prefix(text: &str) -> Result<ExpressionEvalResult, GwError> {
+    let chars: Vec<char> = text.chars()
+        .filter(|c| !matches!(c, ' ' | '\t' | '\n'))
+        .collect();
+    match chars.as_slice() {
+        ['&', 'H' | 'h', rest @ ..] => return Ok(parse_radix_prefix(rest, 16)),
+        ['&', 'O' | 'o', rest @ ..] => return Ok(parse_radix_prefix(rest, 8)),
+        ['&', rest @ ..] => return Ok(parse_radix_prefix(rest, 8)),
+        _ => {}
+    }
+
+    let mut number = String::new();
+    let mut index = 0;
+    let mut significant_digits = 0;
+    let mut is_double = false;
+    if let Some(sign@('+' | '-')) = chars.get(index) {
+        number.push(*sign);
+        index += 1;
+    }
+    let mut seen_dot = false;
+    while let Some(c) = chars.get(index) {
+        if c.is_ascii_digit() {
+            if significant_digits > 0 || *c != '0' {
+                significant_digits += 1;
+            }
+            number.push(*c);
+        } else if *c == '.' && !seen_dot {
+            seen_dot = true;
+            number.push(*c);
+        } else {
+            break;
+        }
+        index += 1;
+    }
+    if let Some(exponent@('E' | 'e' | 'D' | 'd')) = chars.get(index) {
+        let mut exponent_part = String::from("E");
+        let mut exponent_index = index + 1;
+        if let Some(sign@('+' | '-')) = chars.get(exponent_index) {
+            exponent_part.push(*sign);
+            exponent_index += 1;
+        }
+        let exponent_digits: String =
+            chars[exponent_index..].iter().take_while(|c| c.is_ascii_digit()).collect();
+        if !exponent_digits.is_empty() {
+            exponent_part.push_str(&exponent_digits);
+            number.push_str(&exponent_part);
+            index = exponent_index + exponent_digits.len();
+            is_double = matches!(exponent, 'D' | 'd');
+        }
+    }
+    if let Some('#') = chars.get(index) {
+        is_double = true;
+    }
+
+    let value = number.parse::<f64>().unwrap_or(0.0);
+    if is_double || significant_digits > 7 {
+        double_result(value)
+    } else {
+        single_result(value as f32)
+    }
+}
+
+/// AST element for `STRING$(n, m)` and `STRING$(n, x$)`, a string of
+/// length `n` with the character of code `m` or the first character of `x$`
+pub struct GwStringStr {
+    count_expr: Box<dyn GwExpression>,
+    char_expr: Box<dyn GwExpression>
+}
+
+impl GwStringStr {
+    pub fn new(count_expr: Box<dyn GwExpression>,
+               char_expr: Box<dyn GwExpression>) -> GwStringStr {
+        GwStringStr { count_expr, char_expr }
+    }
+}
+
+impl GwExpression for GwStringStr {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let count = eval_integer_argument(self.count_expr.as_ref(), context, 0, 255)? as usize;
+        let fill_char = match self.char_expr.eval(context)? {
+            ExpressionEvalResult::StringResult(value) =>
+                value.chars().next().ok_or(GwError::IllegalFunctionCall)?,
+            numeric_value => {
+                let code = to_integer_operand(&numeric_value)?;
+                if !(0..=255).contains(&code) {
+                    return Err(GwError::IllegalFunctionCall);
+                }
+                char::from(code as u8)
+            }
+        };
+        Ok(ExpressionEvalResult::StringResult(fill_char.to_string().repeat(count)))
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        fill_function_structure_string("STRING$", &[self.count_expr.as_ref(), self.char_expr.as_ref()], buffer);
+    }
+}
+
+/// AST element for `SPACE$(n)`, a string of `n` spaces
+pub struct GwSpaceStr {
+    count_expr: Box<dyn GwExpression>
+}
+
+impl GwSpaceStr {
+    pub fn new(count_expr: Box<dyn GwExpression>) -> GwSpaceStr {
+        GwSpaceStr { count_expr }
+    }
+}
+
+impl GwExpression for GwSpaceStr {
+    fn eval(&self, context: &mut EvaluationContext)
+            -> Result<ExpressionEvalResult, EvaluationError> {
+        let count = eval_integer_argument(self.count_expr.as_ref(), context, 0, 255)? as usize;
+        Ok(ExpressionEvalResult::StringResult(" ".repeat(count)))
+    }
+
+    fn fill_structure_string(&self, buffer: &mut String) {
+        fill_function_structure_string("SPACE$", &[self.count_expr.as_ref()], buffer);
+    }
+}
+
+
+#[cfg(test)]
+mod string_func_tests {
+    use crate::eval::{GwStringLiteral, GwIntegerLiteral};
+
+    use super::*;
+    use super::super::eval_tests::empty_context;
+
+    fn str_lit(value: &str) -> Box<dyn GwExpression> {
+        Box::new(GwStringLiteral::with_value(value.to_string()))
+    }
+
+    fn int_lit(value: i16) -> Box<dyn GwExpression> {
+        Box::new(GwIntegerLiteral::with_value(value))
+    }
+
+    fn eval_to_string(expr: &dyn GwExpression) -> Result<String, GwError> {
+        let mut ctxt = empty_context();
+        Ok(expr.eval(&mut ctxt)?.assume_string_value()?.clone())
+    }
+
+    fn eval_to_integer(expr: &dyn GwExpression) -> Result<i16, GwError> {
+        let mut ctxt = empty_context();
+        expr.eval(&mut ctxt)?.as_i16()
+    }
+
+    #[test]
+    fn it_extracts_characters_with_right() -> Result<(), GwError> {
+        assert_eq!("PLE", eval_to_string(&GwRightStr::new(str_lit("APPLE"), int_lit(3)))?);
+        assert_eq!("APPLE", eval_to_string(&GwRightStr::new(str_lit("APPLE"), int_lit(30)))?);
+        assert_eq!(Err(GwError::IllegalFunctionCall),
+                   eval_to_string(&GwRightStr::new(str_lit("APPLE"), int_lit(-1))));
+        Ok(())
+    }
+
+    #[test]
+    fn it_searches_with_instr() -> Result<(), GwError> {
+        assert_eq!(2, eval_to_integer(&GwInstr::new(None, str_lit("ABCABC"), str_lit("BC")))?);
+        assert_eq!(5, eval_to_integer(&GwInstr::new(Some(int_lit(3)), str_lit("ABCABC"), str_lit("BC")))?);
+        assert_eq!(0, eval_to_integer(&GwInstr::new(None, str_lit("ABCABC"), str_lit("X")))?);
+        assert_eq!(3, eval_to_integer(&GwInstr::new(Some(int_lit(3)), str_lit("ABC"), str_lit("")))?);
+        assert_eq!(0, eval_to_integer(&GwInstr::new(Some(int_lit(7)), str_lit("ABC"), str_lit("")))?);
+        assert_eq!(Err(GwError::IllegalFunctionCall),
+                   eval_to_integer(&GwInstr::new(Some(int_lit(0)), str_lit("ABC"), str_lit("A"))));
+        Ok(())
+    }
+
+    #[test]
+    fn it_converts_characters() -> Result<(), GwError> {
+        assert_eq!("A", eval_to_string(&GwChrStr::new(int_lit(65)))?);
+        assert_eq!(66, eval_to_integer(&GwAsc::new(str_lit("BOX")))?);
+        assert_eq!(200, eval_to_integer(&GwAsc::new(Box::new(GwChrStr::new(int_lit(200)))))?);
+        assert_eq!(5, eval_to_integer(&GwLen::new(str_lit("APPLE")))?);
+        assert_eq!(Err(GwError::IllegalFunctionCall), eval_to_integer(&GwAsc::new(str_lit(""))));
+        assert_eq!(Err(GwError::IllegalFunctionCall), eval_to_string(&GwChrStr::new(int_lit(256))));
+        Ok(())
+    }
+
+    #[test]
+    fn it_converts_numbers_to_strings() -> Result<(), GwError> {
+        assert_eq!(" 42", eval_to_string(&GwStrStr::new(int_lit(42)))?);
+        assert_eq!("-42", eval_to_string(&GwStrStr::new(int_lit(-42)))?);
+        assert_eq!(Err(GwError::TypeMismatch), eval_to_string(&GwStrStr::new(str_lit("1"))));
+        Ok(())
+    }
+
+    #[test]
+    fn it_converts_strings_to_numbers() {
+        assert_eq!(Ok(ExpressionEvalResult::SingleResult(-12.5)), parse_numeric_prefix(" -1 2.5ABC"));
+        assert_eq!(Ok(ExpressionEvalResult::SingleResult(1500.0)), parse_numeric_prefix("1.5E3"));
+        assert_eq!(Ok(ExpressionEvalResult::SingleResult(0.0)), parse_numeric_prefix("ABC"));
+        assert_eq!(Ok(ExpressionEvalResult::SingleResult(2.0)), parse_numeric_prefix("2E"));
+        assert_eq!(Ok(ExpressionEvalResult::DoubleResult(1.5e3)), parse_numeric_prefix("1.5D3"));
+        assert_eq!(Ok(ExpressionEvalResult::IntegerResult(255)), parse_numeric_prefix("&HFF"));
+        assert_eq!(Ok(ExpressionEvalResult::IntegerResult(15)), parse_numeric_prefix("&O17"));
+        assert_eq!(Err(GwError::Overflow), parse_numeric_prefix("1E39"));
+    }
+
+    #[test]
+    fn it_builds_repeated_strings() -> Result<(), GwError> {
+        assert_eq!("***", eval_to_string(&GwStringStr::new(int_lit(3), int_lit(42)))?);
+        assert_eq!("XX", eval_to_string(&GwStringStr::new(int_lit(2), str_lit("XYZ")))?);
+        assert_eq!("   ", eval_to_string(&GwSpaceStr::new(int_lit(3)))?);
+        assert_eq!(Err(GwError::IllegalFunctionCall),
+                   eval_to_string(&GwStringStr::new(int_lit(2), str_lit(""))));
+        Ok(())
+    }
+}
@@ -43,10 +43,10 @@ impl GwInstruction for GwSwap {
                 check_result![ self.right.assign_value(result1, context) ];
             }
             (Err(error), _) => {
-                return InstructionResult::EvaluateToError(error.to_string());
+                return InstructionResult::EvaluateToError(error);
             }
             (_, Err(error)) => {
-                return InstructionResult::EvaluateToError(error.to_string());
+                return InstructionResult::EvaluateToError(error);
             }
         }
         InstructionResult::EvaluateNext
@@ -75,17 +75,17 @@ mod swap_tests {
         let mut ctx = empty_context();
         let stat =
             GwSwap::new(
-                Box::new(GwVariableExpression::with_name("x".to_string())),
-                Box::new(GwVariableExpression::with_name("y".to_string())));
-        ctx.set_variable("x", &ExpressionEvalResult::IntegerResult(100)).expect("success");
-        ctx.set_variable("y", &ExpressionEvalResult::IntegerResult(200)).expect("success");
+                Box::new(GwVariableExpression::with_name("x%".to_string())),
+                Box::new(GwVariableExpression::with_name("y%".to_string())));
+        ctx.set_variable("x%", &ExpressionEvalResult::IntegerResult(100)).expect("success");
+        ctx.set_variable("y%", &ExpressionEvalResult::IntegerResult(200)).expect("success");
 
         assert!(
-            if let Some(ExpressionEvalResult::IntegerResult(100)) = ctx.lookup_variable("x")
+            if let Some(ExpressionEvalResult::IntegerResult(100)) = ctx.lookup_variable("x%")
             { true }
             else { false });
         assert!(
-            if let Some(ExpressionEvalResult::IntegerResult(200)) = ctx.lookup_variable("y")
+            if let Some(ExpressionEvalResult::IntegerResult(200)) = ctx.lookup_variable("y%")
             { true }
             else { false });
 
@@ -97,11 +97,11 @@ mod swap_tests {
         assert!(if let InstructionResult::EvaluateNext = eval_result { true } else { false });
 
         assert!(
-            if let Some(ExpressionEvalResult::IntegerResult(200)) = ctx.lookup_variable("x")
+            if let Some(ExpressionEvalResult::IntegerResult(200)) = ctx.lookup_variable("x%")
             { true }
             else { false });
         assert!(
-            if let Some(ExpressionEvalResult::IntegerResult(100)) = ctx.lookup_variable("y")
+            if let Some(ExpressionEvalResult::IntegerResult(100)) = ctx.lookup_variable("y%")
             { true }
             else { false });
     }
@@ -4,7 +4,8 @@ use super::{ GwExpression,
              InstructionResult,
              EvaluationContext,
              LineExecutionArgument,
-             GwProgram };
+             GwProgram,
+             GwError };
 
 pub struct GwWhile {
     pub condition : Box<dyn GwExpression>,
@@ -25,7 +26,7 @@ impl GwInstruction for GwWhile {
             // Try to look for the WEND statement in the program lines
             let index_of_wend = find_wend(line, &program.real_lines);
             if index_of_wend == -1 {
-                return InstructionResult::EvaluateToError(String::from("WHILE WITHOUT WEND"));
+                return InstructionResult::EvaluateToError(GwError::WhileWithoutWend);
             } else {
                 context.pair_instruction_table.insert(line, index_of_wend);
                 context.pair_instruction_table.insert(index_of_wend, line);
@@ -44,9 +45,12 @@ impl GwInstruction for GwWhile {
                 InstructionResult::EvaluateNext
             }
             
-            _ => {
-                InstructionResult::EvaluateToError(
-                    String::from(format!("Type mismatch in line: {}", context.current_real_line)))
+            Ok(_) => {
+                InstructionResult::EvaluateToError(GwError::TypeMismatch)
+            }
+
+            Err(error) => {
+                InstructionResult::EvaluateToError(error)
             }
         }
     }
@@ -94,7 +98,7 @@ impl GwInstruction for GwWend {
         if let Some(corresponding_while) =  context.pair_instruction_table.get(&line) {
             InstructionResult::EvaluateLine(*corresponding_while)
         } else {
-            InstructionResult::EvaluateToError(String::from("WEND WITHOUT WHILE"))
+            InstructionResult::EvaluateToError(GwError::WendWithoutWhile)
         }
     }
     fn fill_structure_string(&self, buffer : &mut String) {
@@ -1,344 +1,564 @@
-use std::io::Read;
-use std::io::Cursor;
+//! Conversion between program listings and the tokenized format
+//! used by GW-BASIC to save programs.
+//!
+//! A tokenized file starts with `0xFF` followed by the lines of the
+//! program. Each line has the link to the next line, the line
+//! number and the tokens of the line ending with a `0`. A link of
+//! `0` marks the end of the program:
+//! ```text
+//! FF | link | line number | tokens ... 00 | ... | 00 00 | 1A
+//! ```
+//! Programs saved with `SAVE "F", P` start with `0xFE` instead and
+//! the rest of the file is encrypted.
 
-use std::io::prelude::*;
-use std::fs::File;
-use std::collections::HashMap; 
+use crate::eval::{ ExpressionEvalResult, GwError };
+use crate::eval::number_format::format_value;
+use crate::eval::record_func::{ double_to_mbf, mbf_to_double, mbf_to_single, single_to_mbf };
+use crate::eval::string_func::{ bytes_to_string, string_to_bytes };
+use crate::parser::{ convert_numeric_string, GwToken };
+use crate::tokens::GwBasicToken;
 
+/// First byte of a tokenized program file
+pub const TOKENIZED_FILE_HEADER: u8 = 0xFF;
 
+/// First byte of a protected program file
+pub const PROTECTED_FILE_HEADER: u8 = 0xFE;
 
-fn add_token( byte1 : u8, byte2 : u8, tok : GwBasicToken, dict : &mut HashMap<u8, HashMap<u8, GwBasicToken>>) {
-    let inner :  &mut HashMap<u8, GwBasicToken>;
+/// Keys used to encrypt protected files, each byte is combined with
+/// the keys at its position modulo 13 and 11 counting backwards
+const PROTECTION_KEY_1: [u8; 13] = [
+    0x9A, 0xF7, 0x19, 0x83, 0x24, 0x63, 0x43, 0x83, 0x75, 0xCD, 0x8D, 0x84, 0xA9
+];
+const PROTECTION_KEY_2: [u8; 11] = [
+    0x7C, 0x88, 0x59, 0x74, 0xE0, 0x97, 0x26, 0x77, 0xC4, 0x1D, 0x1E
+];
 
-    if !dict.contains_key(&byte1) {
-        dict.insert(byte1, HashMap::new());
+/// End of file mark written after the program
+const END_OF_FILE_MARK: u8 = 0x1A;
+
+const OCTAL_CONSTANT: u8 = 0x0B;
+const HEX_CONSTANT: u8 = 0x0C;
+const LINE_POINTER: u8 = 0x0D;
+const LINE_NUMBER: u8 = 0x0E;
+const BYTE_CONSTANT: u8 = 0x0F;
+const FIRST_DIGIT_CONSTANT: u8 = 0x11;
+const LAST_DIGIT_CONSTANT: u8 = 0x1B;
+const INTEGER_CONSTANT: u8 = 0x1C;
+const SINGLE_CONSTANT: u8 = 0x1D;
+const DOUBLE_CONSTANT: u8 = 0x1F;
+
+/// Keywords followed by line numbers, which are stored as line
+/// references instead of number constants
+const LINE_NUMBER_KEYWORDS: [GwBasicToken; 11] = [
+    GwBasicToken::GotoTok, GwBasicToken::GosubTok, GwBasicToken::ThenTok,
+    GwBasicToken::ElseTok, GwBasicToken::RestoreTok, GwBasicToken::ResumeTok,
+    GwBasicToken::RunTok, GwBasicToken::ListTok, GwBasicToken::LlistTok,
+    GwBasicToken::DeleteTok, GwBasicToken::EditTok
+];
+
+/// Code of a token stored in a single byte
+fn single_byte_code(token: &GwBasicToken) -> Option<u8> {
+    token.code().filter(|code| *code <= 0xFF).map(|code| code as u8)
+}
+
+fn push_keyword(token: &GwBasicToken, tokens: &mut Vec<u8>) {
+    let code = token.code().unwrap_or_default();
+    if code > 0xFF {
+        tokens.extend_from_slice(&code.to_be_bytes());
+    } else {
+        tokens.push(code as u8);
     }
-    
-    if let Some(existing_dict) = dict.get_mut(&byte1) {
-        inner = existing_dict;
+}
+
+/// Lines of a program file, which is either tokenized, protected or
+/// a text listing as written by `SAVE "F", A`
+pub fn program_file_lines(contents: &[u8]) -> Result<Vec<String>, GwError> {
+    if contents.first() == Some(&TOKENIZED_FILE_HEADER) {
+        detokenize_program(contents)
+    } else if contents.first() == Some(&PROTECTED_FILE_HEADER) {
+        detokenize_program(&unprotect(contents))
     } else {
-        panic!("error getting inner dictionary");
+        Ok(bytes_to_string(contents)
+           .lines()
+           .map(|line| line.trim_end_matches(char::from(END_OF_FILE_MARK)))
+           .filter(|line| !line.trim().is_empty())
+           .map(String::from)
+           .collect())
+    }
+}
+
+/// Lines of the listing of a tokenized program file
+pub fn detokenize_program(contents: &[u8]) -> Result<Vec<String>, GwError> {
+    if contents.first() != Some(&TOKENIZED_FILE_HEADER) {
+        return Err(GwError::BadFileMode);
+    }
+    let mut lines = vec![];
+    let mut position = 1;
+    // The end of file mark may follow the last line without a zero link
+    while position + 1 < contents.len() {
+        let link = take_bytes(contents, &mut position, 2)?;
+        if link == [0, 0] {
+            break;
+        }
+        let line_number = read_u16(contents, &mut position)?;
+        let line_text = detokenize_line(contents, &mut position)?;
+        lines.push(format!("{} {}", line_number, line_text));
+    }
+    Ok(lines)
+}
+
+fn take_bytes<'a>(contents: &'a [u8], position: &mut usize, count: usize)
+                  -> Result<&'a [u8], GwError> {
+    let bytes = contents.get(*position..*position + count).ok_or(GwError::BadFileMode)?;
+    *position += count;
+    Ok(bytes)
+}
+
+fn read_u16(contents: &[u8], position: &mut usize) -> Result<u16, GwError> {
+    let bytes = take_bytes(contents, position, 2)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+/// Text of the tokens starting at `position` up to the `0` that ends
+/// the line, without the line number. Programs typed as `FORI=1TO3`
+/// are stored without spaces, so one is added where a keyword touches
+/// a name or a number
+fn detokenize_line(contents: &[u8], position: &mut usize) -> Result<String, GwError> {
+    let mut text = String::new();
+    let mut after_keyword = false;
+    loop {
+        let byte = take_bytes(contents, position, 1)?[0];
+        let (piece, token) = match byte {
+            0 => return Ok(text),
+            b'"' => {
+                let end = contents[*position..].iter()
+                    .position(|item| *item == b'"' || *item == 0)
+                    .map_or(contents.len(), |length| *position + length);
+                let mut literal = format!("\"{}", bytes_to_string(&contents[*position..end]));
+                *position = end;
+                if contents.get(end) == Some(&b'"') {
+                    literal.push('"');
+                    *position += 1;
+                }
+                (literal, None)
+            }
+            // `ELSE` and `'` are stored as `:ELSE` and `:REM'`
+            b':' if contents.get(*position).copied() == single_byte_code(&GwBasicToken::ElseTok) =>
+                continue,
+            b':' if contents.get(*position).copied() == single_byte_code(&GwBasicToken::RemTok)
+                && contents.get(*position + 1).copied()
+                    == single_byte_code(&GwBasicToken::SingleQuoteTok) => {
+                *position += 1;
+                continue;
+            }
+            OCTAL_CONSTANT => (format!("&O{:o}", read_u16(contents, position)?), None),
+            HEX_CONSTANT => (format!("&H{:X}", read_u16(contents, position)?), None),
+            LINE_POINTER => return Err(GwError::BadFileMode),
+            LINE_NUMBER => (read_u16(contents, position)?.to_string(), None),
+            BYTE_CONSTANT => (take_bytes(contents, position, 1)?[0].to_string(), None),
+            FIRST_DIGIT_CONSTANT..=LAST_DIGIT_CONSTANT =>
+                ((byte - FIRST_DIGIT_CONSTANT).to_string(), None),
+            INTEGER_CONSTANT => ((read_u16(contents, position)? as i16).to_string(), None),
+            SINGLE_CONSTANT => {
+                let mut mbf = [0; 4];
+                mbf.copy_from_slice(take_bytes(contents, position, 4)?);
+                (single_constant_text(mbf_to_single(&mbf)), None)
+            }
+            DOUBLE_CONSTANT => {
+                let mut mbf = [0; 8];
+                mbf.copy_from_slice(take_bytes(contents, position, 8)?);
+                (double_constant_text(mbf_to_double(&mbf)), None)
+            }
+            0x80..=0xFF => {
+                let code = if byte >= 0xFD {
+                    u16::from_be_bytes([byte, take_bytes(contents, position, 1)?[0]])
+                } else {
+                    u16::from(byte)
+                };
+                let (token, keyword) = GwBasicToken::from_code(code).ok_or(GwError::BadFileMode)?;
+                (keyword.to_string(), Some(token))
+            }
+            _ => (char::from(byte).to_string(), None)
+        };
+        let starts_word = piece.starts_with(|c: char| c.is_ascii_alphanumeric());
+        if starts_word
+            && (after_keyword
+                || (token.is_some() && text.ends_with(|c: char| c.is_ascii_alphanumeric()))) {
+            text.push(' ');
+        }
+        text.push_str(&piece);
+        // The name after `FN` is part of the name of the function
+        after_keyword = token.as_ref().is_some_and(|token| *token != GwBasicToken::FnTok)
+            && piece.ends_with(|c: char| c.is_ascii_alphabetic());
+        let following_text = match token {
+            Some(GwBasicToken::RemTok | GwBasicToken::SingleQuoteTok) =>
+                bytes_to_string(&take_text(contents, position, false)),
+            Some(GwBasicToken::DataTok) => bytes_to_string(&take_text(contents, position, true)),
+            Some(GwBasicToken::WhileTok)
+                if contents.get(*position).copied() == single_byte_code(&GwBasicToken::PlusTok) => {
+                *position += 1;
+                continue;
+            }
+            _ => continue
+        };
+        if after_keyword && following_text.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+            text.push(' ');
+        }
+        text.push_str(&following_text);
+        after_keyword = false;
     }
-    inner.insert(byte2, tok);
 }
 
-fn init_multi_byte_tokens() -> HashMap<u8, HashMap<u8, GwBasicToken>> {
-    let mut dict = HashMap::new();
-add_token(0xFD, 0x81, GwBasicToken::CviTok,&mut dict);
-add_token(0xFD, 0x82, GwBasicToken::CvsTok,&mut dict);
-add_token(0xFD, 0x83, GwBasicToken::CvdTok,&mut dict);
-add_token(0xFD, 0x84, GwBasicToken::MkiDTok,&mut dict);
-add_token(0xFD, 0x85, GwBasicToken::MksDTok,&mut dict);
-add_token(0xFD, 0x86, GwBasicToken::MkdDTok,&mut dict);
-add_token(0xFD, 0x8B, GwBasicToken::ExterrTok,&mut dict);
-add_token(0xFE, 0x81, GwBasicToken::FilesTok,&mut dict);
-add_token(0xFE, 0x82, GwBasicToken::FieldTok,&mut dict);
-add_token(0xFE, 0x83, GwBasicToken::SystemTok,&mut dict);
-add_token(0xFE, 0x84, GwBasicToken::NameTok,&mut dict);
-add_token(0xFE, 0x85, GwBasicToken::LsetTok,&mut dict);
-add_token(0xFE, 0x86, GwBasicToken::RsetTok,&mut dict);
-add_token(0xFE, 0x87, GwBasicToken::KillTok,&mut dict);
-add_token(0xFE, 0x88, GwBasicToken::PutTok,&mut dict);
-add_token(0xFE, 0x89, GwBasicToken::GetTok,&mut dict);
-add_token(0xFE, 0x8A, GwBasicToken::ResetTok,&mut dict);
-add_token(0xFE, 0x8B, GwBasicToken::CommonTok,&mut dict);
-add_token(0xFE, 0x8C, GwBasicToken::ChainTok,&mut dict);
-add_token(0xFE, 0x8D, GwBasicToken::DateDTok,&mut dict);
-add_token(0xFE, 0x8E, GwBasicToken::TimeDTok,&mut dict);
-add_token(0xFE, 0x8F, GwBasicToken::PaintTok,&mut dict);
-add_token(0xFE, 0x90, GwBasicToken::ComTok,&mut dict);
-add_token(0xFE, 0x91, GwBasicToken::CircleTok,&mut dict);
-add_token(0xFE, 0x92, GwBasicToken::DrawTok,&mut dict);
-add_token(0xFE, 0x93, GwBasicToken::PlayTok,&mut dict);
-add_token(0xFE, 0x94, GwBasicToken::TimerTok,&mut dict);
-add_token(0xFE, 0x95, GwBasicToken::ErdevTok,&mut dict);
-add_token(0xFE, 0x96, GwBasicToken::IoctlTok,&mut dict);
-add_token(0xFE, 0x97, GwBasicToken::ChdirTok,&mut dict);
-add_token(0xFE, 0x98, GwBasicToken::MkdirTok,&mut dict);
-add_token(0xFE, 0x99, GwBasicToken::RmdirTok,&mut dict);
-add_token(0xFE, 0x9A, GwBasicToken::ShellTok,&mut dict);
-add_token(0xFE, 0x9B, GwBasicToken::EnvironTok,&mut dict);
-add_token(0xFE, 0x9C, GwBasicToken::ViewTok,&mut dict);
-add_token(0xFE, 0x9D, GwBasicToken::WindowTok,&mut dict);
-add_token(0xFE, 0x9E, GwBasicToken::PmapTok,&mut dict);
-add_token(0xFE, 0x9F, GwBasicToken::PaletteTok,&mut dict);
-add_token(0xFE, 0xA0, GwBasicToken::LcopyTok,&mut dict);
-add_token(0xFE, 0xA1, GwBasicToken::CallsTok,&mut dict);
-add_token(0xFE, 0xA4, GwBasicToken::NoiseTok,&mut dict);
-add_token(0xFE, 0xA5, GwBasicToken::PcopyTok,&mut dict);
-add_token(0xFE, 0xA6, GwBasicToken::TermTok,&mut dict);
-add_token(0xFE, 0xA7, GwBasicToken::LockTok,&mut dict);
-add_token(0xFE, 0xA8, GwBasicToken::UnlockTok,&mut dict);
-add_token(0xFF, 0x81, GwBasicToken::LeftDTok,&mut dict);
-add_token(0xFF, 0x82, GwBasicToken::RightDTok,&mut dict);
-add_token(0xFF, 0x83, GwBasicToken::MidDTok,&mut dict);
-add_token(0xFF, 0x84, GwBasicToken::SgnTok,&mut dict);
-add_token(0xFF, 0x85, GwBasicToken::IntTok,&mut dict);
-add_token(0xFF, 0x86, GwBasicToken::AbsTok,&mut dict);
-add_token(0xFF, 0x87, GwBasicToken::SqrTok,&mut dict);
-add_token(0xFF, 0x88, GwBasicToken::RndTok,&mut dict);
-add_token(0xFF, 0x89, GwBasicToken::SinTok,&mut dict);
-add_token(0xFF, 0x8A, GwBasicToken::LogTok,&mut dict);
-add_token(0xFF, 0x8B, GwBasicToken::ExpTok,&mut dict);
-add_token(0xFF, 0x8C, GwBasicToken::CosTok,&mut dict);
-add_token(0xFF, 0x8D, GwBasicToken::TanTok,&mut dict);
-add_token(0xFF, 0x8E, GwBasicToken::AtnTok,&mut dict);
-add_token(0xFF, 0x8F, GwBasicToken::FreTok,&mut dict);
-add_token(0xFF, 0x90, GwBasicToken::InpTok,&mut dict);
-add_token(0xFF, 0x91, GwBasicToken::PosTok,&mut dict);
-add_token(0xFF, 0x92, GwBasicToken::LenTok,&mut dict);
-add_token(0xFF, 0x93, GwBasicToken::StrDTok,&mut dict);
-add_token(0xFF, 0x94, GwBasicToken::ValTok,&mut dict);
-add_token(0xFF, 0x95, GwBasicToken::AscTok,&mut dict);
-add_token(0xFF, 0x96, GwBasicToken::ChrDTok,&mut dict);
-add_token(0xFF, 0x97, GwBasicToken::PeekTok,&mut dict);
-add_token(0xFF, 0x98, GwBasicToken::SpaceDTok,&mut dict);
-add_token(0xFF, 0x99, GwBasicToken::OctDTok,&mut dict);
-add_token(0xFF, 0x9A, GwBasicToken::HexDTok,&mut dict);
-add_token(0xFF, 0x9B, GwBasicToken::LposTok,&mut dict);
-add_token(0xFF, 0x9C, GwBasicToken::CintTok,&mut dict);
-add_token(0xFF, 0x9D, GwBasicToken::CsngTok,&mut dict);
-add_token(0xFF, 0x9E, GwBasicToken::CdblTok,&mut dict);
-add_token(0xFF, 0x9F, GwBasicToken::FixTok,&mut dict);
-add_token(0xFF, 0xA0, GwBasicToken::PenTok,&mut dict);
-add_token(0xFF, 0xA1, GwBasicToken::StickTok,&mut dict);
-add_token(0xFF, 0xA2, GwBasicToken::StrigTok,&mut dict);
-add_token(0xFF, 0xA3, GwBasicToken::EofTok,&mut dict);
-add_token(0xFF, 0xA4, GwBasicToken::LocTok,&mut dict);
-add_token(0xFF, 0xA5, GwBasicToken::LofTok,&mut dict);
+/// Takes the characters that are not tokenized after `REM` and
+/// `DATA`, up to the end of the line or the `:` ending the `DATA`
+fn take_text(contents: &[u8], position: &mut usize, stop_at_colon: bool) -> Vec<u8> {
+    let mut text = vec![];
+    let mut quoted = false;
+    while let Some(&byte) = contents.get(*position) {
+        if byte == 0 || (stop_at_colon && byte == b':' && !quoted) {
+            break;
+        }
+        quoted ^= byte == b'"';
+        text.push(byte);
+        *position += 1;
+    }
+    text
+}
+
+/// Text of a single constant, with `!` when it would be read as an integer
+fn single_constant_text(value: f32) -> String {
+    let text = format_value(&ExpressionEvalResult::SingleResult(value)).trim_start().to_string();
+    if text.contains(['.', 'E']) || value.abs() > f32::from(i16::MAX) {
+        text
+    } else {
+        text + "!"
+    }
+}
 
-    dict
+/// Text of a double constant, with `#` unless it has a `D` exponent
+fn double_constant_text(value: f64) -> String {
+    let text = format_value(&ExpressionEvalResult::DoubleResult(value)).trim_start().to_string();
+    if text.contains('D') {
+        text
+    } else {
+        text + "#"
+    }
 }
 
-fn init_single_byte_tokens() -> HashMap<u8, GwBasicToken> {
-    let mut single_byte_tokens = HashMap::new();
-    single_byte_tokens.insert(0x81, GwBasicToken::EndTok);
-    single_byte_tokens.insert(0x82, GwBasicToken::ForTok);
-    single_byte_tokens.insert(0x83, GwBasicToken::NextTok);
-    single_byte_tokens.insert(0x84, GwBasicToken::DataTok);
-    single_byte_tokens.insert(0x85, GwBasicToken::InputTok);
-    single_byte_tokens.insert(0x86, GwBasicToken::DimTok);
-    single_byte_tokens.insert(0x87, GwBasicToken::ReadTok);
-    single_byte_tokens.insert(0x88, GwBasicToken::LetTok);
-    single_byte_tokens.insert(0x89, GwBasicToken::GotoTok);
-    single_byte_tokens.insert(0x8A, GwBasicToken::RunTok);
-    single_byte_tokens.insert(0x8B, GwBasicToken::IfTok);
-    single_byte_tokens.insert(0x8C, GwBasicToken::RestoreTok);
-    single_byte_tokens.insert(0x8D, GwBasicToken::GosubTok);
-    single_byte_tokens.insert(0x8E, GwBasicToken::ReturnTok);
-    single_byte_tokens.insert(0x8F, GwBasicToken::RemTok);
-    single_byte_tokens.insert(0x90, GwBasicToken::StopTok);
-    single_byte_tokens.insert(0x91, GwBasicToken::PrintTok);
-    single_byte_tokens.insert(0x92, GwBasicToken::ClearTok);
-    single_byte_tokens.insert(0x93, GwBasicToken::ListTok);
-    single_byte_tokens.insert(0x94, GwBasicToken::NewTok);
-    single_byte_tokens.insert(0x95, GwBasicToken::OnTok);
-    single_byte_tokens.insert(0x96, GwBasicToken::WaitTok);
-    single_byte_tokens.insert(0x97, GwBasicToken::DefTok);
-    single_byte_tokens.insert(0x98, GwBasicToken::PokeTok);
-    single_byte_tokens.insert(0x99, GwBasicToken::ContTok);
-    single_byte_tokens.insert(0x9C, GwBasicToken::OutTok);
-    single_byte_tokens.insert(0x9D, GwBasicToken::LprintTok);
-    single_byte_tokens.insert(0x9E, GwBasicToken::LlistTok);
-    single_byte_tokens.insert(0xA0, GwBasicToken::WidthTok);
-    single_byte_tokens.insert(0xA1, GwBasicToken::ElseTok);
-    single_byte_tokens.insert(0xA2, GwBasicToken::TronTok);
-    single_byte_tokens.insert(0xA3, GwBasicToken::TroffTok);
-    single_byte_tokens.insert(0xA4, GwBasicToken::SwapTok);
-    single_byte_tokens.insert(0xA5, GwBasicToken::EraseTok);
-    single_byte_tokens.insert(0xA6, GwBasicToken::EditTok);
-    single_byte_tokens.insert(0xA7, GwBasicToken::ErrorTok);
-    single_byte_tokens.insert(0xA8, GwBasicToken::ResumeTok);
-    single_byte_tokens.insert(0xA9, GwBasicToken::DeleteTok);
-    single_byte_tokens.insert(0xAA, GwBasicToken::AutoTok);
-    single_byte_tokens.insert(0xAB, GwBasicToken::RenumTok);
-    single_byte_tokens.insert(0xAC, GwBasicToken::DefstrTok);
-    single_byte_tokens.insert(0xAD, GwBasicToken::DefintTok);
-    single_byte_tokens.insert(0xAE, GwBasicToken::DefsngTok);
-    single_byte_tokens.insert(0xAF, GwBasicToken::DefdblTok);
-    single_byte_tokens.insert(0xB0, GwBasicToken::LineTok);
-    single_byte_tokens.insert(0xB1, GwBasicToken::WhileTok);
-    single_byte_tokens.insert(0xB2, GwBasicToken::WendTok);
-    single_byte_tokens.insert(0xB3, GwBasicToken::CallTok);
-    single_byte_tokens.insert(0xB7, GwBasicToken::WriteTok);
-    single_byte_tokens.insert(0xB8, GwBasicToken::OptionTok);
-    single_byte_tokens.insert(0xB9, GwBasicToken::RandomizeTok);
-    single_byte_tokens.insert(0xBA, GwBasicToken::OpenTok);
-    single_byte_tokens.insert(0xBB, GwBasicToken::CloseTok);
-    single_byte_tokens.insert(0xBC, GwBasicToken::LoadTok);
-    single_byte_tokens.insert(0xBD, GwBasicToken::MergeTok);
-    single_byte_tokens.insert(0xBE, GwBasicToken::SaveTok);
-    single_byte_tokens.insert(0xBF, GwBasicToken::ColorTok);
-    single_byte_tokens.insert(0xC0, GwBasicToken::ClsTok);
-    single_byte_tokens.insert(0xC1, GwBasicToken::MotorTok);
-    single_byte_tokens.insert(0xC2, GwBasicToken::BsaveTok);
-    single_byte_tokens.insert(0xC3, GwBasicToken::BloadTok);
-    single_byte_tokens.insert(0xC4, GwBasicToken::SoundTok);
-    single_byte_tokens.insert(0xC5, GwBasicToken::BeepTok);
-    single_byte_tokens.insert(0xC6, GwBasicToken::PsetTok);
-    single_byte_tokens.insert(0xC7, GwBasicToken::PresetTok);
-    single_byte_tokens.insert(0xC8, GwBasicToken::ScreenTok);
-    single_byte_tokens.insert(0xC9, GwBasicToken::KeyTok);
-    single_byte_tokens.insert(0xCA, GwBasicToken::LocateTok);
-    single_byte_tokens.insert(0xCC, GwBasicToken::ToTok);
-    single_byte_tokens.insert(0xCD, GwBasicToken::ThenTok);
-    single_byte_tokens.insert(0xCE, GwBasicToken::TabTok);
-    single_byte_tokens.insert(0xCF, GwBasicToken::StepTok);
-    single_byte_tokens.insert(0xD0, GwBasicToken::UsrTok);
-    single_byte_tokens.insert(0xD1, GwBasicToken::FnTok);
-    single_byte_tokens.insert(0xD2, GwBasicToken::SpcTok);
-    single_byte_tokens.insert(0xD3, GwBasicToken::NotTok);
-    single_byte_tokens.insert(0xD4, GwBasicToken::ErlTok);
-    single_byte_tokens.insert(0xD5, GwBasicToken::ErrTok);
-    single_byte_tokens.insert(0xD6, GwBasicToken::StringDTok);
-    single_byte_tokens.insert(0xD7, GwBasicToken::UsingTok);
-    single_byte_tokens.insert(0xD8, GwBasicToken::InstrTok);
-    single_byte_tokens.insert(0xD9, GwBasicToken::SingleQuoteTok);
-    single_byte_tokens.insert(0xDA, GwBasicToken::VarptrTok);
-    single_byte_tokens.insert(0xDB, GwBasicToken::CsrlinTok);
-    single_byte_tokens.insert(0xDC, GwBasicToken::PointTok);
-    single_byte_tokens.insert(0xDD, GwBasicToken::OffTok);
-    single_byte_tokens.insert(0xDE, GwBasicToken::InkeyDTok);
-    single_byte_tokens.insert(0xE6, GwBasicToken::GtTok);
-    single_byte_tokens.insert(0xE7, GwBasicToken::EqlTok);
-    single_byte_tokens.insert(0xE8, GwBasicToken::LtTok);
-    single_byte_tokens.insert(0xE9, GwBasicToken::PlusTok);
-    single_byte_tokens.insert(0xEA, GwBasicToken::MinusTok);
-    single_byte_tokens.insert(0xEB, GwBasicToken::TimesTok);
-    single_byte_tokens.insert(0xEC, GwBasicToken::DivTok);
-    single_byte_tokens.insert(0xED, GwBasicToken::PowOperatorTok);
-    single_byte_tokens.insert(0xEE, GwBasicToken::AndTok);
-    single_byte_tokens.insert(0xEF, GwBasicToken::OrTok);
-    single_byte_tokens.insert(0xF0, GwBasicToken::XorTok);
-    single_byte_tokens.insert(0xF1, GwBasicToken::EqvTok);
-    single_byte_tokens.insert(0xF2, GwBasicToken::ImpTok);
-    single_byte_tokens.insert(0xF3, GwBasicToken::ModTok);
-    single_byte_tokens.insert(0xF4, GwBasicToken::Div2Tok);
-    single_byte_tokens
+/// Tokenized program file with the contents of a protected file
+pub fn unprotect(contents: &[u8]) -> Vec<u8> {
+    let mut result = vec![TOKENIZED_FILE_HEADER];
+    for (index, byte) in contents.iter().skip(1).enumerate() {
+        let (key_1_index, key_2_index) = protection_key_indexes(index);
+        let decrypted = (byte.wrapping_sub(key_2_index as u8 + 1)
+                         ^ PROTECTION_KEY_1[key_1_index]
+                         ^ PROTECTION_KEY_2[key_2_index])
+            .wrapping_add(key_1_index as u8 + 1);
+        result.push(decrypted);
+    }
+    result
 }
 
-fn read_u16(reader : &mut dyn Read) -> u16 {
-    let mut two_bytes = [0; 2];
-    reader.read(&mut two_bytes).expect("error");
-    let result : u16 = (u16::from(two_bytes[1]) << 8)
-                       | (u16::from(two_bytes[0]));
+/// Protected program file with the contents of a tokenized file,
+/// the end of file mark is not encrypted
+pub fn protect(contents: &[u8]) -> Vec<u8> {
+    let program = contents.get(1..).unwrap_or(&[]);
+    let program = program.strip_suffix(&[END_OF_FILE_MARK]).unwrap_or(program);
+    let mut result = vec![PROTECTED_FILE_HEADER];
+    for (index, byte) in program.iter().enumerate() {
+        let (key_1_index, key_2_index) = protection_key_indexes(index);
+        let encrypted = (byte.wrapping_sub(key_1_index as u8 + 1)
+                         ^ PROTECTION_KEY_2[key_2_index]
+                         ^ PROTECTION_KEY_1[key_1_index])
+            .wrapping_add(key_2_index as u8 + 1);
+        result.push(encrypted);
+    }
+    result.push(END_OF_FILE_MARK);
     result
 }
 
-fn recognize_tokens(reader : &mut dyn Read, dict : &HashMap<u8, GwBasicToken>) {
-    let mut item = [0; 1];
-    let multi_token_dict = init_multi_byte_tokens();
+fn protection_key_indexes(index: usize) -> (usize, usize) {
+    (PROTECTION_KEY_1.len() - 1 - index % PROTECTION_KEY_1.len(),
+     PROTECTION_KEY_2.len() - 1 - index % PROTECTION_KEY_2.len())
+}
 
-    let mut secondary_header = [0; 4];
-    reader.read(&mut secondary_header);
+/// Tokenized program file with the given listing lines. Each line
+/// starts with its number, as written by `LIST`
+pub fn tokenize_program(lines: &[String]) -> Result<Vec<u8>, GwError> {
+    let mut contents = vec![TOKENIZED_FILE_HEADER];
+    for line in lines {
+        let digits_length = line.find(|c: char| !c.is_ascii_digit()).unwrap_or(line.len());
+        let line_number = line[..digits_length].parse::<u16>()
+            .map_err(|_| GwError::DirectStatementInFile)?;
+        let line_text = line[digits_length..].strip_prefix(' ').unwrap_or(&line[digits_length..]);
+        let tokens = tokenize_line(line_text)?;
+        // GW-BASIC links the lines again when loading the program,
+        // only a link of zero matters
+        let link = (contents.len() + 4 + tokens.len() + 1) as u16;
+        contents.extend_from_slice(&link.to_le_bytes());
+        contents.extend_from_slice(&line_number.to_le_bytes());
+        contents.extend_from_slice(&tokens);
+        contents.push(0);
+    }
+    contents.extend_from_slice(&[0, 0, END_OF_FILE_MARK]);
+    Ok(contents)
+}
 
-    
-    loop {
-        let read_bytes = reader.read(&mut item).expect("Error reading");
-        if read_bytes == 0 {
-            break;
-        }
-        if let Some(tok) = dict.get(&item[0]) {
-            println!("Recognized: {:?}, {}", tok, item[0]);            
-        } else if item[0] >= 0x20 && item[0] <= 0x7E {
-            println!("Unrecognized {}", char::from(item[0]));
-        } else if item[0] == 0xF {
-            reader.read(&mut item).expect("Error reading");
-            println!("Single byte constant {}", item[0] );
-        } else if item[0] == 0x1C {
-            let two_byte_constant = read_u16(reader);            
-            println!("Two byte constant {}", two_byte_constant );
-        } else if item[0] >= 0xf5 && item[0] <= 0xFC {
-           println!("Undefined {}", item[0]);
-        } else if item[0] == 0x1D {
-            let mut floating_number = [0; 4];
-            reader.read(&mut floating_number);
-            println!("Floating point literal ...");
-        } else if item[0] == 0xE {
-            let gosub_line = read_u16( reader );
-            println!("Line reference {}", gosub_line);
-        } else if item[0] >= 0x11 && item[0] <= 0x1B {
-            println!("Number constant {}", item[0] - 0x11);
-        } else if let Some(inner_dict) = multi_token_dict.get(&item[0]) {
-            reader.read(&mut item).expect("Error reading");
-            if let Some(tok2) = inner_dict.get(&item[0]) {
-                println!("Multi recognized {:?}", tok2);
-            } else {
-                panic!("Unknown multibyte");
+/// Tokens of the text of a line without its line number
+fn tokenize_line(text: &str) -> Result<Vec<u8>, GwError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = vec![];
+    let mut position = 0;
+    let mut line_numbers_expected = false;
+    while position < chars.len() {
+        let c = chars[position];
+        if c == '"' {
+            let end = chars[position + 1..].iter()
+                .position(|item| *item == '"')
+                .map_or(chars.len(), |length| position + length + 2);
+            tokens.extend(string_to_bytes(&chars[position..end].iter().collect::<String>()));
+            position = end;
+        } else if c.is_ascii_digit()
+                  || (c == '.' && chars.get(position + 1).is_some_and(char::is_ascii_digit)) {
+            let end = number_end(&chars, position);
+            let number_text: String = chars[position..end].iter().collect();
+            match number_text.parse::<u16>() {
+                Ok(line_number) if line_numbers_expected => {
+                    tokens.push(LINE_NUMBER);
+                    tokens.extend_from_slice(&line_number.to_le_bytes());
+                }
+                _ => push_number(&number_text, &mut tokens)?
+            }
+            position = end;
+        } else if c == '&' {
+            position = push_radix_number(&chars, position, &mut tokens);
+        } else if c.is_alphabetic() {
+            let end = chars[position..].iter()
+                .position(|item| !item.is_alphanumeric())
+                .map_or(chars.len(), |length| position + length);
+            let word: String = chars[position..end].iter().collect::<String>().to_uppercase();
+            let (keyword, end) = match chars.get(end) {
+                Some(next @ ('$' | '('))
+                    if GwBasicToken::from_keyword_text(&format!("{}{}", word, next)).is_some() =>
+                    (GwBasicToken::from_keyword_text(&format!("{}{}", word, next)), end + 1),
+                _ => (GwBasicToken::from_keyword_text(&word), end)
+            };
+            match keyword {
+                Some(GwBasicToken::RemTok) => {
+                    push_keyword(&GwBasicToken::RemTok, &mut tokens);
+                    tokens.extend(string_to_bytes(&chars[end..].iter().collect::<String>()));
+                    return Ok(tokens);
+                }
+                Some(keyword) => {
+                    if keyword == GwBasicToken::ElseTok {
+                        tokens.push(b':');
+                    }
+                    push_keyword(&keyword, &mut tokens);
+                    match keyword {
+                        GwBasicToken::WhileTok => push_keyword(&GwBasicToken::PlusTok, &mut tokens),
+                        GwBasicToken::DataTok => {
+                            let data_text = take_text(&string_to_bytes(&chars[end..].iter().collect::<String>()),
+                                                      &mut 0, true);
+                            position = end + data_text.len();
+                            tokens.extend(data_text);
+                            line_numbers_expected = false;
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    line_numbers_expected = LINE_NUMBER_KEYWORDS.contains(&keyword);
+                }
+                None if word.starts_with("FN") => {
+                    push_keyword(&GwBasicToken::FnTok, &mut tokens);
+                    tokens.extend(string_to_bytes(&chars[position + 2..end].iter().collect::<String>()));
+                    line_numbers_expected = false;
+                }
+                None => {
+                    tokens.extend(string_to_bytes(&chars[position..end].iter().collect::<String>()));
+                    line_numbers_expected = false;
+                }
             }
-        } else if item[0] == 0xC {
-              let mut hex_lit = [0; 2];
-              reader.read(&mut hex_lit).expect("error");
-              println!("Hex literal");       
-        } else if item[0] == 0x0 {
-            let mut next_line_number = [0; 4];
-            reader.read(&mut next_line_number).expect("error");
-            let new_offset : u16 = (u16::from(next_line_number[1]) << 8) | (u16::from(next_line_number[0]));
-            
-            let current_line : u16 = (u16::from(next_line_number[3]) << 8) | (u16::from(next_line_number[2]));
-            println!("Line number: {}, {}", current_line, new_offset);
-            
+            position = end;
+        } else if c == '\'' {
+            tokens.push(b':');
+            push_keyword(&GwBasicToken::RemTok, &mut tokens);
+            push_keyword(&GwBasicToken::SingleQuoteTok, &mut tokens);
+            tokens.extend(string_to_bytes(&chars[position + 1..].iter().collect::<String>()));
+            return Ok(tokens);
         } else {
-            println!("About to fail with {:x}", item[0]);
-            panic!("Not implemented");
-        }        
+            match GwBasicToken::from_keyword_text(&c.to_string()) {
+                Some(keyword) => push_keyword(&keyword, &mut tokens),
+                None => tokens.extend(string_to_bytes(&c.to_string()))
+            }
+            // Line numbers are also expected in lists such as
+            // `ON X GOTO 10, 20` and ranges such as `LIST 10-20`
+            line_numbers_expected &= matches!(c, ' ' | ',' | '-');
+            position += 1;
+        }
     }
+    Ok(tokens)
 }
 
-fn main() {
-    println!("Experimental extractor");
-    let mut file = File::open("IDSLOT.BAS")
-    
-        .expect("Cannot open file");
-    let mut header = [0; 1];
-    file.read(&mut header)
-        .expect("Cannot read from input file");
-    let key_1 : [u8; 13] = [ 0x9A, 0xF7, 0x19, 0x83, 0x24, 0x63,
-                             0x43, 0x83, 0x75, 0xCD, 0x8D, 0x84,
-                             0xA9 ];
-    let key_2 : [u8; 11] = [ 0x7C, 0x88, 0x59, 0x74, 0xE0, 0x97,
-                            0x26, 0x77, 0xC4, 0x1D, 0x1E ];
-        let toks = init_single_byte_tokens();
-    if header[0] == 0xFF {
-        println!("File not protected");
+/// Position after the number starting at `start`, including its
+/// exponent and type suffix
+fn number_end(chars: &[char], start: usize) -> usize {
+    let mut position = start;
+    let mut has_dot = false;
+    while let Some(&c) = chars.get(position) {
+        if c == '.' && !has_dot {
+            has_dot = true;
+        } else if !c.is_ascii_digit() {
+            break;
+        }
+        position += 1;
+    }
+    if let Some('E' | 'D') = chars.get(position) {
+        position += 1;
+        if let Some('+' | '-') = chars.get(position) {
+            position += 1;
+        }
+        while chars.get(position).is_some_and(char::is_ascii_digit) {
+            position += 1;
+        }
+    }
+    if let Some('%' | '!' | '#') = chars.get(position) {
+        position += 1;
+    }
+    position
+}
 
-        recognize_tokens(&mut file, &toks);
-        //match toks.get(&secondary_header[4]) {
-        //    Some(tok) => println!("Recognized: {:?}, {}", tok, secondary_header[5]),
-        //    None => println!("Token not recognized {}", secondary_header[4])
-        //}
-        
-    } else {
-        println!("File protected. Input byte: {}", header[0]);
-        let mut contents = vec![];
-        let full_file = file.read_to_end(&mut contents)
-            .expect("error reading");
-        let mut idx1 : u8 = 0x0D;
-        let mut idx2 : u8 = 0x0B;
-        let mut i = 0;
-        while i < contents.len() {
-            let sub_result = contents[i].wrapping_sub(idx2);
-            let first_xor = sub_result ^ key_1[usize::from(idx1 - 1)];
-            let second_xor = first_xor ^ key_2[usize::from(idx2 - 1)];
-            contents[i] = second_xor.wrapping_add(idx1);
-            idx1 = idx1 - 1;
-            idx2 = idx2 - 1;
-            if idx2 == 0 {
-                idx2 = 0x0B;
+fn push_number(number_text: &str, tokens: &mut Vec<u8>) -> Result<(), GwError> {
+    match convert_numeric_string(number_text) {
+        Some(GwToken::Integer(value)) | Some(GwToken::FormattedInteger(value, _)) => {
+            match value {
+                0..=10 => tokens.push(FIRST_DIGIT_CONSTANT + value as u8),
+                11..=255 => tokens.extend_from_slice(&[BYTE_CONSTANT, value as u8]),
+                _ => {
+                    tokens.push(INTEGER_CONSTANT);
+                    tokens.extend_from_slice(&value.to_le_bytes());
+                }
             }
+        }
+        Some(GwToken::Single(value, _)) => {
+            tokens.push(SINGLE_CONSTANT);
+            tokens.extend_from_slice(&single_to_mbf(value)?);
+        }
+        Some(GwToken::Double(value, _)) => {
+            tokens.push(DOUBLE_CONSTANT);
+            tokens.extend_from_slice(&double_to_mbf(value)?);
+        }
+        _ => tokens.extend(string_to_bytes(number_text))
+    }
+    Ok(())
+}
 
-            if idx1 == 0 {
-                idx1 = 0x0D;
-            }
-            i = i + 1;
+/// Adds the `&H1F`, `&O17` or `&17` number starting at `start`,
+/// returns the position after it
+fn push_radix_number(chars: &[char], start: usize, tokens: &mut Vec<u8>) -> usize {
+    let (radix, code, digits_start) = match chars.get(start + 1) {
+        Some('H') | Some('h') => (16, HEX_CONSTANT, start + 2),
+        Some('O') | Some('o') => (8, OCTAL_CONSTANT, start + 2),
+        _ => (8, OCTAL_CONSTANT, start + 1)
+    };
+    let digits_end = chars[digits_start..].iter()
+        .position(|c| !c.is_digit(radix))
+        .map_or(chars.len(), |length| digits_start + length);
+    let digits: String = chars[digits_start..digits_end].iter().collect();
+    match u16::from_str_radix(&digits, radix) {
+        Ok(value) => {
+            tokens.push(code);
+            tokens.extend_from_slice(&value.to_le_bytes());
+            digits_end
+        }
+        Err(_) => {
+            tokens.push(b'&');
+            start + 1
         }
-        let off_2 : u16 = (u16::from(contents[1]) << 8) | (u16::from(contents[0]));
-        let current_line : u16 = (u16::from(contents[3]) << 8) | (u16::from(contents[2]));
-        println!("{} <> {}", off_2, current_line);
-        
-        recognize_tokens(&mut Cursor::new(contents), &toks);
-                                                               
-                                                           
-                                                           
+    }
+}
+
+#[cfg(test)]
+mod extractor_tests {
+    use super::*;
+
+    #[test]
+    fn it_detokenizes_programs() -> Result<(), GwError> {
+        let contents = [
+            0xFF,
+            // 10 PRINT "HI";1.5;10:GOTO 10
+            0x20, 0x12, 0x0A, 0x00, 0x91, b' ', b'"', b'H', b'I', b'"', b';',
+            0x1D, 0x00, 0x00, 0x40, 0x81, b';', 0x1B, b':', 0x89, b' ', 0x0E, 0x0A, 0x00, 0x00,
+            // 20 IF X THEN 10 ELSE A$=LEFT$(B$,300)' DONE
+            0x40, 0x12, 0x14, 0x00, 0x8B, b' ', b'X', b' ', 0xCD, b' ', 0x0E, 0x0A, 0x00,
+            b' ', b':', 0xA1, b' ', b'A', b'$', 0xE7, 0xFF, 0x81, b'(', b'B', b'$', b',',
+            0x1C, 0x2C, 0x01, b')', b':', 0x8F, 0xD9, b' ', b'D', b'O', b'N', b'E', 0x00,
+            // 30 WHILE CVD(D$)<5#:DATA 1,"A:B":WEND
+            0x60, 0x12, 0x1E, 0x00, 0xB1, 0xE9, b' ', 0xFD, 0x83, b'(', b'D', b'$', b')',
+            0xE8, 0x1F, 0, 0, 0, 0, 0, 0, 0x20, 0x83,
+            b':', 0x84, b' ', b'1', b',', b'"', b'A', b':', b'B', b'"', b':', 0xB2, 0x00,
+            0x00, 0x00, 0x1A
+        ];
+        assert_eq!(vec!["10 PRINT \"HI\";1.5;10:GOTO 10".to_string(),
+                        "20 IF X THEN 10 ELSE A$=LEFT$(B$,300)' DONE".to_string(),
+                        "30 WHILE CVD(D$)<5#:DATA 1,\"A:B\":WEND".to_string()],
+                   detokenize_program(&contents)?);
+        assert_eq!(Err(GwError::BadFileMode), detokenize_program(&contents[..20]));
+        Ok(())
+    }
+
+    #[test]
+    fn it_separates_keywords_of_compact_lines() -> Result<(), GwError> {
+        let contents = [
+            0xFF,
+            // 10 FORI=1TO3STEP1:PRINTI;:NEXTI
+            0x1A, 0x12, 0x0A, 0x00, 0x82, b'I', 0xE7, 0x12, 0xCC, 0x14, 0xCF, 0x12,
+            b':', 0x91, b'I', b';', b':', 0x83, b'I', 0x00,
+            // 20 IFA$="Y"THEN10ELSEGOSUB20:REMARK
+            0x36, 0x12, 0x14, 0x00, 0x8B, b'A', b'$', 0xE7, b'"', b'Y', b'"', 0xCD, 0x0E, 0x0A, 0x00,
+            b':', 0xA1, 0x8D, 0x0E, 0x14, 0x00, b':', 0x8F, b'A', b'R', b'K', 0x00,
+            0x00, 0x00, 0x1A
+        ];
+        assert_eq!(vec!["10 FOR I=1 TO 3 STEP 1:PRINT I;:NEXT I".to_string(),
+                        "20 IF A$=\"Y\"THEN 10 ELSE GOSUB 20:REM ARK".to_string()],
+                   detokenize_program(&contents)?);
+        Ok(())
+    }
+
+    #[test]
+    fn it_tokenizes_lines_that_detokenize_back() -> Result<(), GwError> {
+        let lines: Vec<String> = [
+            "10 PRINT \"HI\";1.5;10 :GOTO 10",
+            "20 IF X THEN 10 ELSE A$ = LEFT$(B$,300) ' DONE",
+            "30 ON I GOSUB 100, 200 :X = &H1F + &O17 + 40000 + 2! + 1.25# + 1D+30 + FNA(TOTAL)",
+            "40 DATA 1,\"A:B\" :REM PRINT 5",
+            "50 WHILE TAB(3) :WEND"
+        ].iter().map(|line| line.to_string()).collect();
+        let contents = tokenize_program(&lines)?;
+        assert_eq!([0xFF, 0x1B, 0x00, 0x0A, 0x00, 0x91, b' ', b'"'], contents[..8]);
+        assert!(contents.ends_with(&[0x00, 0x00, 0x00, 0x1A]));
+        assert_eq!(lines, detokenize_program(&contents)?);
+        assert_eq!(lines, program_file_lines(&contents)?);
+        Ok(())
+    }
+
+    #[test]
+    fn it_protects_and_unprotects_programs() -> Result<(), GwError> {
+        let lines = vec!["10 PRINT \"SECRET\"".to_string(), "20 GOTO 10".to_string()];
+        let contents = tokenize_program(&lines)?;
+        let protected = protect(&contents);
+        assert_eq!(Some(&0xFE), protected.first());
+        assert_eq!(Some(&0x1A), protected.last());
+        assert_eq!(contents.len(), protected.len());
+        assert!(!protected.windows(6).any(|bytes| bytes == b"SECRET"));
+        assert_eq!(contents[..contents.len() - 1], unprotect(&protected)[..contents.len() - 1]);
+        assert_eq!(lines, program_file_lines(&protected)?);
+        Ok(())
+    }
+
+    #[test]
+    fn it_reads_program_listings() -> Result<(), GwError> {
+        assert_eq!(vec!["10 PRINT 1".to_string(), "20 END".to_string()],
+                   program_file_lines(b"10 PRINT 1\r\n20 END\r\n\x1A")?);
+        Ok(())
     }
 }
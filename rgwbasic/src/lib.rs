@@ -1,14 +1,17 @@
 pub mod eval;
 pub mod tokens;
 pub mod parser;
+pub mod extractor;
 
 #[cfg(test)]
 mod integration_tests {
     use std::rc::Rc;
     use std::cell::RefCell;
 
-    use crate::eval::context::Console;
+    use crate::eval::context::{ Console, advance_column_position };
+    use crate::eval::files::{ FileSystem, MemoryFileSystem };
     use super::eval::*;
+    use super::parser;
 
     #[test]
     fn it_should_run_builtins() -> Result<(), String> {
@@ -36,14 +39,619 @@ mod integration_tests {
         Ok(())
     }
 
+    #[test]
+    fn it_should_report_runtime_errors_with_line() -> Result<(), String> {
+        let code = "\
+10 A = 1
+20 PRINT A + \"X\"
+30 PRINT \"UNREACHABLE\"";
+        let output = run_program(code);
+
+        assert_eq!("Type mismatch in 20\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_evaluate_logical_operators() -> Result<(), String> {
+        let code = "\
+10 A = 5 : B$ = \"X\"
+20 IF A > 0 AND B$ <> \"\" THEN PRINT \"BOTH\"
+30 PRINT 12 AND 10; 12 OR 3; 12 XOR 10; NOT 0; NOT 5
+40 PRINT 0 EQV 0; -1 IMP 0; 2.6 AND 7
+50 PRINT 40000.0 AND 1";
+        let output = run_program(code);
+
+        assert_eq!("BOTH\n 8  15  6 -1 -6 \n-1  0  3 \nOverflow in 50\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_evaluate_integer_division_and_mod() -> Result<(), String> {
+        let code = "\
+10 PRINT 7 \\ 2; -7 \\ 2; 10.6 \\ 3; 7 MOD 3; -7 MOD 3; 19 MOD 6.7
+20 PRINT 5 \\ 0.2";
+        let output = run_program(code);
+
+        assert_eq!(" 3 -3  3  1 -1  5 \nDivision by zero in 20\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_promote_overflowing_integer_arithmetic() -> Result<(), String> {
+        let code = "\
+10 X% = -32768
+20 PRINT 30000 + 30000; -200 * 200; 2 ^ 24; 2 ^ -1; -X%
+30 A% = 30000 + 30000";
+        let output = run_program(code);
+
+        assert_eq!(" 60000 -40000  1.677722E+07  .5  32768 \nOverflow in 30\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_report_overflowing_floating_point_arithmetic() -> Result<(), String> {
+        let code = "\
+10 ON ERROR GOTO 100
+20 PRINT 1E+38 * 10
+30 PRINT 1D+300 * 1D+10
+40 X = 1D+300
+50 PRINT 1 / 0
+60 END
+100 PRINT ERR; ERL
+110 RESUME NEXT";
+        let output = run_program(code);
+
+        assert_eq!(" 6  20 \n 6  30 \n 6  40 \n 11  50 \n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_call_user_defined_functions() -> Result<(), String> {
+        let code = "\
+10 X = 100 : Y = 2
+20 DEF FNSCALE(X) = X * Y
+30 DEF FNGREET$(N$) = LEFT$(N$, 2)
+40 PRINT FNSCALE(21)
+50 PRINT X
+60 PRINT FNGREET$(\"BOB\")
+70 PRINT FNMISSING(1)";
+        let output = run_program(code);
+
+        assert_eq!(" 42 \n 100 \nBO\nUndefined user function in 70\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_type_user_defined_functions_with_deftype() -> Result<(), String> {
+        let code = "\
+10 DEFSTR F, S : DEFINT N
+20 DEF FNF(S) = S + \"!\"
+30 DEF FNN(X) = X * 2
+40 PRINT FNF(\"HI\"); FNN(1.3)
+50 PRINT FNF(1)";
+        let output = run_program(code);
+
+        assert_eq!("HI! 3 \nType mismatch in 50\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_run_string_functions() -> Result<(), String> {
+        let code = "\
+10 A$ = \"HELLO WORLD\"
+20 PRINT MID$(A$, 7, 3); \"|\"; RIGHT$(A$, 5); \"|\"; LEN(A$)
+30 PRINT INSTR(A$, \"O\"); INSTR(6, A$, \"O\"); ASC(A$); CHR$(33)
+40 PRINT STR$(12); STR$(-3); VAL(\" 1 2ABC\"); \"|\"; STRING$(3, \"*\"); SPACE$(2); \"|\"
+50 MID$(A$, 1, 5) = \"JELLYFISH\"
+60 PRINT A$
+70 PRINT MID$(A$, 0)";
+        let output = run_program(code);
+
+        assert_eq!("WOR|WORLD| 11 \n 5  8  72 !\n 12-3 12 |***  |\nJELLY WORLD\nIllegal function call in 70\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_run_numeric_functions() -> Result<(), String> {
+        let code = "\
+10 PRINT SQR(16); SGN(-3); FIX(-2.5); INT(-2.5); CINT(2.6); CDBL(3)
+20 PRINT HEX$(255); \" \"; OCT$(8); \" \"; HEX$(-1)
+30 PRINT USING \"##.###\"; EXP(1); ATN(1); TAN(0)
+40 ON ERROR GOTO 100
+50 PRINT CINT(40000.0)
+60 PRINT SQR(-1)
+70 PRINT LOG(0)
+80 END
+100 PRINT ERR; ERL
+110 RESUME NEXT";
+        let output = run_program(code);
+
+        assert_eq!(" 4 -1 -2 -3  3  3 \nFF 10 FFFF\n 2.718 0.785 0.000\n 6  50 \n 5  60 \n 5  70 \n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_reproduce_random_sequences() -> Result<(), String> {
+        let code = "\
+10 RANDOMIZE 3
+20 A = RND : B = RND(1) : C = RND(0)
+30 RANDOMIZE 3
+40 IF A = RND AND B = RND AND B = C THEN PRINT \"SAME\"
+50 D = RND(-2) : E = RND
+60 IF D = RND(-2) AND E = RND THEN PRINT \"RESEEDED\"";
+        let output = run_program(code);
+
+        assert_eq!("SAME\nRESEEDED\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_use_multidimensional_arrays() -> Result<(), String> {
+        let code = "\
+10 DIM A(2, 3), B$(1)
+20 FOR I = 0 TO 2
+25 FOR J = 0 TO 3
+30 A(I, J) = I * 10 + J
+40 NEXT J : NEXT I
+50 B$(1) = \"X\" : C(10) = 5
+60 PRINT A(1, 2); A(2, 3); A(0, 1); B$(1); C(10)
+70 PRINT A(3, 0)";
+        let output = run_program(code);
+
+        assert_eq!(" 12  23  1 X 5 \nSubscript out of range in 70\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_erase_arrays_and_clear_variables() -> Result<(), String> {
+        let code = "\
+10 DIM A(5) : A(5) = 7 : X = 3
+20 DEF FNT(N) = N * 2
+30 ERASE A
+40 DIM A(20) : A(20) = 1
+50 PRINT A(5); A(20); X; FNT(X)
+60 CLEAR
+70 PRINT X; A(2)
+80 PRINT FNT(1)";
+        let output = run_program(code);
+
+        assert_eq!(" 0  1  3  6 \n 0  0 \nUndefined user function in 80\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_keep_separate_variables_by_type_suffix() -> Result<(), String> {
+        let code = "\
+10 DEFINT I-N
+20 A% = 2.6 : A! = 1.5 : A# = 2.25 : A$ = \"S\"
+30 I = 7.4 : J% = 3
+40 PRINT A%; A!; A#; A$; I; J; A
+50 DEFSTR S
+60 S = \"TEXT\" : PRINT S
+70 S = 1";
+        let output = run_program(code);
+
+        assert_eq!(" 3  1.5  2.25 S 7  3  1.5 \nTEXT\nType mismatch in 70\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_evaluate_radix_and_exponent_literals() -> Result<(), String> {
+        let code = "\
+10 PRINT &H1F; &O17; &17; &HFFFF; 100%
+20 PRINT 1.5E+3; 2.5D-3; 3# / 2";
+        let output = run_program(code);
+
+        assert_eq!(" 31  15  15 -1  100 \n 1500  .0025  1.5 \n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_format_printed_numbers() -> Result<(), String> {
+        let code = "\
+10 PRINT 5; -7; 1/3; -2.5; 1E+7; 1#/3
+20 PRINT 1/300; 0.001; 2.5D-20; STR$(0.5)";
+        let output = run_program(code);
+
+        assert_eq!(" 5 -7  .3333333 -2.5  1E+07  .3333333333333333 \n 3.333333E-03  .001  2.5D-20  .5\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_use_print_zones_tab_and_spc() -> Result<(), String> {
+        let code = "\
+10 PRINT 1, \"AB\", -2
+20 PRINT \"X\"; TAB(5); \"Y\"; SPC(3); \"Z\"
+30 PRINT \"ABCDEFGH\"; TAB(3); \"N\"
+40 PRINT 1,2,3,4,5,6
+50 PRINT STRING$(78, \"-\"); 123
+60 PRINT TAB(0)";
+        let output = run_program(code);
+
+        let zones = format!("{:14}{:14}{:14}{:14} 5 \n 6 \n", " 1 ", " 2 ", " 3 ", " 4 ");
+        assert_eq!(format!("{:14}{:14}-2 \nX   Y   Z\nABCDEFGH\n  N\n{}{}\n 123 \nIllegal function call in 60\n",
+                           " 1 ", "AB", zones, "-".repeat(78)),
+                   output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_format_with_print_using() -> Result<(), String> {
+        let code = "\
+10 PRINT USING \"##.## \"; 1.5, 2, 3.25
+20 PRINT USING \"!-\\  \\-&\"; \"ALPHA\", \"BETA\", \"GAMMA\"
+30 PRINT USING \"**$##,###.##\"; 1234.567
+40 PRINT USING \"+.##^^^^_%\"; 123
+50 PRINT USING \"##\"; 123; 4
+60 PRINT USING \"##\"; \"X\"";
+        let output = run_program(code);
+
+        assert_eq!(" 1.50  2.00  3.25 \nA-BETA-GAMMA\n***$1,234.57\n+.12E+03%\n%123 4\nType mismatch in 60\n",
+                   output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_write_and_read_sequential_files() -> Result<(), String> {
+        let code = "\
+10 OPEN \"REPORT.DAT\" FOR OUTPUT AS #1
+20 WRITE #1, \"SMITH, JOHN\", 42, -1.5
+30 PRINT #1, \"TOTAL\"; 7, \"X\"
+40 PRINT #1, USING \"##.##\"; 3.14159
+50 CLOSE #1
+60 OPEN \"A\", #2, \"REPORT.DAT\"
+70 PRINT #2, \"END\" : CLOSE
+80 OPEN \"REPORT.DAT\" FOR INPUT AS 1
+90 PRINT LOF(1); LOC(1); EOF(1)
+100 INPUT #1, N$, A%, B
+110 PRINT N$; A%; B
+120 WHILE NOT EOF(1)
+130 LINE INPUT #1, L$ : PRINT \"[\"; L$; \"]\"
+140 WEND
+150 LINE INPUT #1, L$";
+        let output = run_program(code);
+
+        assert_eq!(format!(" 52  0  0 \nSMITH, JOHN 42 -1.5 \n[{:14}X]\n[ 3.14]\n[END]\nInput past end in 150\n",
+                           "TOTAL 7"),
+                   output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_report_file_errors() -> Result<(), String> {
+        let code = "\
+10 ON ERROR GOTO 100
+20 OPEN \"MISSING.DAT\" FOR INPUT AS #1
+30 OPEN \"O\", #1, \"OUT.DAT\" : OPEN \"OUT.DAT\" FOR INPUT AS #2
+40 INPUT #1, A
+50 PRINT #3, A
+60 OPEN \"X\", 2, \"OUT.DAT\"
+70 OPEN \"I\", 16, \"OUT.DAT\"
+80 END
+100 PRINT ERR; ERL : RESUME NEXT";
+        let output = run_program(code);
+
+        assert_eq!(" 53  20 \n 55  30 \n 54  40 \n 52  50 \n 54  60 \n 52  70 \n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_read_and_write_random_files() -> Result<(), String> {
+        let code = "\
+10 OPEN \"STOCK.DAT\" AS #1 LEN = 26
+20 FIELD #1, 20 AS N$, 2 AS Q$, 4 AS P$
+30 LSET N$ = \"HAMMER\" : LSET Q$ = MKI$(12) : LSET P$ = MKS$(9.75)
+40 PUT #1, 1
+50 LSET N$ = \"SCREWDRIVER SET DELUXE\" : LSET Q$ = MKI$(-3) : LSET P$ = MKS$(.1)
+60 PUT #1
+70 PRINT LOF(1); LOC(1)
+80 GET #1, 1
+90 PRINT \"[\"; N$; \"]\"; CVI(Q$); CVS(P$)
+100 GET #1 : PRINT N$; CVI(Q$); CVS(P$); EOF(1)
+110 GET #1 : PRINT EOF(1); LOC(1); LEN(N$)
+120 CLOSE
+130 OPEN \"R\", #2, \"STOCK.DAT\", 26
+140 FIELD #2, 20 AS A$(1), 6 AS B$
+150 GET #2, 2 : PRINT A$(1)
+160 A$ = \"ABCDE\" : RSET A$ = \"XY\" : PRINT \"[\"; A$; \"]\"
+170 PRINT CVD(MKD$(1234567.125#))
+180 ON ERROR GOTO 300
+190 FIELD #2, 20 AS C$, 7 AS D$
+200 PUT #2, 0
+210 END
+300 PRINT ERR; ERL : RESUME NEXT";
+        let output = run_program(code);
+
+        assert_eq!(" 52  2 \n\
+[HAMMER              ] 12  9.75 \n\
+SCREWDRIVER SET DELU-3  .1  0 \n\
+-1  3  20 \n\
+SCREWDRIVER SET DELU\n\
+[   XY]\n 1234567.125 \n 50  190 \n 63  200 \n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_save_programs_that_load_back() -> Result<(), GwError> {
+        let code = "\
+10 DEFINT I-N : DIM A(5), B$(2, 3)
+20 FOR I = 1 TO 5 STEP 2 : A(I) = I * (I + 1) : NEXT I
+30 IF A(1) > 1 THEN PRINT \"BIG\"; ELSE PRINT \"SMALL\",
+40 GOSUB 100 : PRINT USING \"##.##\"; 3.14159
+50 REM the end: \"quoted\"
+60 SAVE \"PROG.BAS\", A
+70 END
+100 PRINT -(1 + 2) * 3, \"A;B\" ; A(3) : RETURN";
+        let files = MemoryFileSystem::new();
+        let output = run_program_with_files(code, &files);
+
+        let mut console: Box<dyn Console> =
+            Box::new(TestConsole::with_files(Rc::new(RefCell::new(String::new())), files.clone()));
+        let mut original = GwProgram::new();
+        let lines: Vec<String> = code.split("\n").map(|s| s.to_string()).collect();
+        original.load_from(&mut console, Box::new(lines.into_iter()))?;
+
+        let saved = String::from_utf8(files.read_file("PROG.BAS")?).unwrap();
+        assert!(saved.ends_with("70 END\r\n100 PRINT (-(1 + 2) * 3),\"A;B\";A(3) :RETURN\r\n"));
+        let mut loaded = GwProgram::new();
+        let saved_lines: Vec<String> = saved.lines().map(|s| s.to_string()).collect();
+        loaded.load_from(&mut console, Box::new(saved_lines.clone().into_iter()))?;
+        assert_eq!(original.listing(), loaded.listing());
+        assert_eq!(saved_lines, loaded.listing());
+
+        let reloaded_output = run_program_with_files(&saved, &files);
+        assert_eq!("BIG-9         A;B 12 \n 3.14\n", output);
+        assert_eq!(output, reloaded_output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_load_tokenized_programs() -> Result<(), GwError> {
+        let code = "\
+10 DEF FNAREA(R) = 3.14159 * R ^ 2 : TOTAL = 0
+20 FOR I% = 1 TO 3 STEP 1 : TOTAL = TOTAL + FNAREA(I%) : NEXT I%
+30 IF TOTAL > 40000 THEN 60 ELSE PRINT USING \"###.##\"; TOTAL : REM area
+40 ON 2 GOSUB 70, 80 : DATA 12, \"X:Y\"
+50 SAVE \"AREA.BAS\" : END
+60 PRINT \"TOO BIG\" : END
+70 RETURN
+80 READ A, B$ : PRINT A; B$; LEN(B$); &H10 : RETURN";
+        let files = MemoryFileSystem::new();
+        let output = run_program_with_files(code, &files);
+        assert_eq!(" 43.98\n 12 X:Y 3  16 \n", output);
+        assert_eq!(Some(&0xFF), files.read_file("AREA.BAS")?.first());
+
+        let mut original = GwProgram::new();
+        let mut console: Box<dyn Console> =
+            Box::new(TestConsole::with_files(Rc::new(RefCell::new(String::new())), files.clone()));
+        let lines: Vec<String> = code.split("\n").map(|s| s.to_string()).collect();
+        original.load_from(&mut console, Box::new(lines.into_iter()))?;
+
+        let mut loaded = GwProgram::new();
+        assert!(matches!(eval_direct_statement("LOAD \"AREA.BAS\"", &mut loaded, console),
+                         InstructionResult::EvaluateNext));
+        assert_eq!(original.listing(), loaded.listing());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_load_tokenized_programs_typed_without_spaces() -> Result<(), GwError> {
+        // 10 FORI=1TO3STEP1:PRINTI;:NEXTI as saved by GW-BASIC
+        let contents = [
+            0xFF, 0x1A, 0x12, 0x0A, 0x00, 0x82, b'I', 0xE7, 0x12, 0xCC, 0x14, 0xCF, 0x12,
+            b':', 0x91, b'I', b';', b':', 0x83, b'I', 0x00, 0x00, 0x00, 0x1A
+        ];
+        let mut files = MemoryFileSystem::new();
+        files.write_file("COUNT.BAS", &contents)?;
+        let output = Rc::new(RefCell::new(String::new()));
+        let console: Box<dyn Console> = Box::new(TestConsole::with_files(output.clone(), files));
+
+        let mut program = GwProgram::new();
+        assert!(matches!(eval_direct_statement("LOAD \"COUNT.BAS\"", &mut program, console),
+                         InstructionResult::EvaluateNext));
+        assert_eq!(vec!["10 FOR I = 1 TO 3 STEP 1 :PRINT I;:NEXT I".to_string()],
+                   program.listing());
+        assert_eq!("", output.borrow().as_str());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_load_protected_programs() -> Result<(), GwError> {
+        let code = "\
+10 K$ = \"CODE\" : PRINT K$; LEN(K$)
+20 SAVE \"SECRET.BAS\", P";
+        let files = MemoryFileSystem::new();
+        let output = run_program_with_files(code, &files);
+        assert_eq!("CODE 4 \n", output);
+        let contents = files.read_file("SECRET.BAS")?;
+        assert_eq!(Some(&0xFE), contents.first());
+        assert!(!contents.windows(4).any(|bytes| bytes == b"CODE"));
+
+        let mut loaded = GwProgram::new();
+        let console: Box<dyn Console> =
+            Box::new(TestConsole::with_files(Rc::new(RefCell::new(String::new())), files.clone()));
+        assert!(matches!(eval_direct_statement("LOAD \"SECRET.BAS\"", &mut loaded, console),
+                         InstructionResult::EvaluateNext));
+        assert_eq!(vec!["10 K$ = \"CODE\" :PRINT K$;LEN(K$)",
+                        "20 SAVE \"SECRET.BAS\", P"], loaded.listing());
+        assert_eq!(output, run_program_with_files(&loaded.listing().join("\n"), &files));
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_delete_program_lines() -> Result<(), GwError> {
+        let rc_str = Rc::new(RefCell::new(String::new()));
+        let console = || -> Box<dyn Console> { Box::new(TestConsole::new(rc_str.clone())) };
+        let mut program = GwProgram::new();
+        for line in ["10 PRINT 1", "20 PRINT 2", "30 PRINT 3", "40 DELETE 30- : PRINT 4", "50 PRINT 5"] {
+            program.enter_line(line.to_string())?;
+        }
+        assert!(matches!(eval_direct_statement("DELETE 20", &mut program, console()),
+                         InstructionResult::EvaluateEnd));
+        assert!(matches!(eval_direct_statement("DELETE 20", &mut program, console()),
+                         InstructionResult::EvaluateToError(GwError::IllegalFunctionCall)));
+        program.run(&console());
+        assert_eq!(vec!["10 PRINT 1"], program.listing());
+
+        eval_direct_statement("NEW", &mut program, console());
+        program.run(&console());
+        assert!(program.listing().is_empty());
+        assert_eq!(" 1 \n 3 \n", rc_str.borrow().as_str());
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_read_and_restore_data() -> Result<(), String> {
+        let code = "\
+10 READ A, B$, C
+20 PRINT A; B$; C
+30 RESTORE 70
+40 READ D : PRINT D
+50 RESTORE : READ E : PRINT E
+60 DATA 40, \"LAST\" : DATA 50
+70 DATA 1, 2, 3
+80 READ F$, G, H, I, J, K";
+        let output = run_program(code);
+
+        assert_eq!(" 40 LAST 50 \n 1 \n 40 \nOut of DATA in 80\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_read_numeric_data_as_val() -> Result<(), String> {
+        let code = "\
+10 DATA &H1F, 2.5D-3,, 7!, 1.5E2
+20 READ A%, B#, C, D, E
+30 PRINT A%; B#; C; D; E";
+        let output = run_program(code);
+
+        assert_eq!(" 31  .0025  0  7  150 \n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_dispatch_with_on_gosub() -> Result<(), String> {
+        let code = "\
+10 FOR I = 0 TO 3
+20 PRINT \"<\"; : ON I GOSUB 100, 200, 100 : PRINT \">\"
+30 NEXT I
+40 END
+100 PRINT \"A\"; : RETURN
+200 PRINT \"B\"; : RETURN";
+        let output = run_program(code);
+
+        assert_eq!("<>\n<A>\n<B>\n<A>\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_run_if_else() -> Result<(), String> {
+        let code = "\
+10 A = 1
+20 IF A = 2 THEN PRINT \"TWO\" ELSE PRINT \"NOT TWO\" : PRINT \"SAME LINE\"
+30 IF A = 1 THEN IF A = 2 THEN 60 ELSE 50 ELSE 60
+40 END
+50 PRINT \"FIFTY\"
+60 IF A = 1 GOTO 80
+70 PRINT \"UNREACHABLE\"
+80 PRINT \"DONE\"";
+        let output = run_program(code);
+
+        assert_eq!("NOT TWO\nSAME LINE\nFIFTY\nDONE\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_report_syntax_errors_while_loading() -> Result<(), String> {
+        let code = "\
+10 PRINT \"A\"
+20 PRINT 1 ~ 2
+30 PRINT \"B\"";
+        let output = run_program(code);
+
+        assert_eq!("Syntax error in 20\nA\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_trap_errors_with_on_error_goto() -> Result<(), String> {
+        let code = "\
+10 ON ERROR GOTO 100
+20 A = 1 / 0
+30 PRINT \"NEXT\"
+40 ERROR 53
+50 PRINT \"DONE\"
+60 END
+100 PRINT ERR
+110 PRINT ERL
+120 RESUME NEXT";
+        let output = run_program(code);
+
+        assert_eq!(" 11 \n 20 \nNEXT\n 53 \n 40 \nDONE\n", output);
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_report_errors_inside_error_handler() -> Result<(), String> {
+        let code = "\
+10 ON ERROR GOTO 100
+20 ERROR 5
+30 END
+100 ON ERROR GOTO 0";
+        let output = run_program(code);
+
+        assert_eq!("Illegal function call in 20\n", output);
+        Ok(())
+    }
+
+    fn run_program(code: &str) -> String {
+        run_program_with_files(code, &MemoryFileSystem::new())
+    }
+
+    fn run_program_with_files(code: &str, files: &MemoryFileSystem) -> String {
+        let mut program = GwProgram::new();
+        let rc_str = Rc::new(RefCell::new(String::new()));
+        let mut console: Box<dyn Console> = Box::new(TestConsole::with_files(rc_str.clone(), files.clone()));
+        let lines: Vec<String> = code.split("\n").map(|s| s.to_string()).collect();
+        if let Err(error) = program.load_from(&mut console, Box::new(lines.into_iter())) {
+            console.print_line(error.to_string().as_str());
+        }
+
+        program.run(&console);
+
+        let result = rc_str.borrow().clone();
+        result
+    }
+
+
+    fn eval_direct_statement(text: &str,
+                             program: &mut GwProgram,
+                             console: Box<dyn Console>) -> InstructionResult {
+        let instruction = match parser::parse_repl_instruction_string(String::from(text)) {
+            parser::ParserResult::Success(instruction) => instruction,
+            _ => panic!("Direct statement not parsed: {}", text)
+        };
+        let mut context = EvaluationContext::with_program(program, console);
+        instruction.eval(-1, LineExecutionArgument::Empty, &mut context, program)
+    }
 
     pub struct TestConsole {
-        contents: Rc<RefCell<String>>
+        contents: Rc<RefCell<String>>,
+        column_position: usize,
+        files: MemoryFileSystem
     }
     
     impl TestConsole {
         pub fn new(string_ref: Rc<RefCell<String>>) -> TestConsole {
-            TestConsole { contents: string_ref }
+            TestConsole::with_files(string_ref, MemoryFileSystem::new())
+        }
+
+        pub fn with_files(string_ref: Rc<RefCell<String>>, files: MemoryFileSystem) -> TestConsole {
+            TestConsole {
+                contents: string_ref,
+                column_position: 0,
+                files
+            }
         }
     }
 
@@ -51,12 +659,15 @@ mod integration_tests {
         fn print(&mut self, value: &str) {
             let mut m = self.contents.borrow_mut();
             m.push_str(value);
+            self.column_position =
+                advance_column_position(self.column_position, value, self.text_width());
         }
 
         fn print_line(&mut self, value: &str) {
             let mut m = self.contents.borrow_mut();
             m.push_str(value);
             m.push_str("\n");
+            self.column_position = 0;
         }
 
         fn read_line(&mut self, _buffer: &mut String) {
@@ -68,7 +679,7 @@ mod integration_tests {
         }
 
         fn current_text_column(&self) -> usize {
-            todo!()
+            self.column_position + 1
         }
 
         fn read_file_lines(&self, _file_name: &str) -> Box<dyn Iterator<Item=String>> {
@@ -83,8 +694,16 @@ mod integration_tests {
             todo!()
         }
 
+        fn file_system(&mut self) -> Option<&mut dyn FileSystem> {
+            Some(&mut self.files)
+        }
+
         fn clone(&self) -> Box<dyn Console> {
-            Box::new(TestConsole::new(self.contents.clone()))
+            Box::new(TestConsole {
+                contents: self.contents.clone(),
+                column_position: self.column_position,
+                files: self.files.clone()
+            })
         }
     }
 }
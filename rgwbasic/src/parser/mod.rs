@@ -1,14 +1,18 @@
 use std::rc::Rc;
 use crate::eval::GwAssignableExpression;
 use crate::eval::GwInkey;
+use crate::eval::GwNotExpr;
 use crate::tokens;
 use std::str::Chars;
 use std::str::FromStr;
+use std::ops::RangeInclusive;
 use crate::eval::binary::GwBinaryOperationKind;
 use crate::eval::SwitchIndicator;
 use crate::eval::GwExpression;
+use crate::eval::GwError;
 use crate::eval::GwVariableExpression;
 use crate::eval::GwIntegerLiteral;
+use crate::eval::GwSingleLiteral;
 use crate::eval::GwDoubleLiteral;
 use crate::eval::GwStringLiteral;
 use crate::eval::binary::GwBinaryOperation;
@@ -29,15 +33,31 @@ use crate::eval::GwGotoStat;
 use crate::eval::GwAssign;
 use crate::eval::GwArrayAssign;
 use crate::eval::GwCall;
-use crate::eval::data_instr::{ GwData, GwRead };
+use crate::eval::data_instr::{ GwData, GwRead, GwRestore };
 use crate::eval::while_instr::{ GwWhile, GwWend };
 use crate::eval::for_instr::{ GwFor, GwNext };
-use crate::eval::dim_instr::{ GwDim, GwDimDecl };
+use crate::eval::dim_instr::{ GwDim, GwDimDecl, GwOptionBase };
 use crate::eval::def_instr::{GwDefType, DefVarRange};
 use crate::eval::swap_instr::GwSwap;
+use crate::eval::numeric_func::{ GwNumericFunction, GwNumericFunctionKind };
+use crate::eval::clear_instr::{ GwErase, GwClear };
+use crate::eval::random::{ GwRandomize, GwTimer };
+use crate::eval::midstr_func::{ GwMidStr, GwMidStrAssign };
+use crate::eval::string_func::{ GwRightStr, GwLen, GwInstr, GwChrStr, GwAsc,
+                                GwStrStr, GwVal, GwStringStr, GwSpaceStr };
 use crate::eval::gosub_instr::{ GwGosub, GwReturn };
 use crate::eval::ongoto_instr::GwOnGoto;
+use crate::eval::deffn_instr::{ GwDefFn, GwUserFunction, GwFnCall };
+use crate::eval::error_instr::{ GwOnErrorGoto, GwResume, ResumeTarget, GwErrorStat, GwErr, GwErl };
 use crate::eval::stop_instr::GwStop;
+use crate::eval::file_instr::{ GwOpen, GwOpenMode, GwClose, GwWrite, GwInputFile,
+                               GwFileFunction, GwFileFunctionKind,
+                               GwField, GwFileRecord, GwSetStr };
+use crate::eval::record_func::{ GwMakeStr, GwConvertStr };
+use crate::eval::save_instr::{ GwSave, SaveFormat };
+use crate::eval::edit_instr::{ GwNew, GwDelete };
+use crate::eval::LineRange;
+use crate::eval::files::FileMode;
 use crate::eval::{GwAbs, GwLog, GwInt, GwCos, GwSin, GwRnd,
                   leftstr_func::GwLeftStr};
 use crate::eval::ProgramLine;
@@ -73,6 +93,14 @@ impl PushbackCharsIterator<'_> {
     fn push_back(&mut self, char_to_push : char) {
         self.pushed_back = Some(char_to_push);
     }
+
+    fn peek(&self) -> Option<char> {
+        self.pushed_back.or_else(|| self.chars.clone().next())
+    }
+
+    fn remaining_chars(&self) -> usize {
+        self.chars.as_str().chars().count() + self.pushed_back.map_or(0, |_| 1)
+    }
 }
 
 #[derive(Debug)]
@@ -82,9 +110,16 @@ pub enum GwToken {
     Identifier(String),
     String(String),
     Integer(i16),
-    Double(f64),
+    /// Integer literal written as `&H1F`, `&O17` or `100%` with its text
+    FormattedInteger(i16, String),
+    Single(f32, String),
+    Double(f64, String),
     Comma,
-    Colon
+    Colon,
+    /// Character that does not start any valid token
+    Unrecognized(char),
+    /// Numeric literal too large for its type, such as `40000%`
+    NumberOverflow(String)
 }
 
 
@@ -158,7 +193,7 @@ fn recognize_word<'a>(iterator : &mut PushbackCharsIterator<'a>) -> Option<Strin
                 if let Some(next_char) = iterator.next() {
                     if next_char.is_alphabetic() || next_char.is_digit(10) {
                         result.push(next_char);
-                    } else if next_char == '$' {
+                    } else if ExpressionType::from_suffix(next_char).is_some() {
                         result.push(next_char);
                         return Some(result);
                     } else {
@@ -201,68 +236,128 @@ fn recognize_string_literal<'a>(iterator : &mut PushbackCharsIterator<'a>) -> Op
 
 
 ///
-///  cases for single 45.32, -1.09E-03,22.5!
+///  cases for integer 10, 100%
+///  cases for single 45.32, -1.09E-03, 22.5!, 40000
 ///  cases for double 34234234, -1.03432D-06, 342342.0#
 ///
-fn convert_numeric_string(tmp_string : &String)  ->  Option<GwToken> {
-    if tmp_string.contains(".")  {
-        if let Ok(result) = f64::from_str(&tmp_string.to_string()) {
-            Some(GwToken::Double(result))
-        } else {
-            None
-        }
+pub(crate) fn convert_numeric_string(tmp_string : &str)  ->  Option<GwToken> {
+    let type_suffix = tmp_string.chars().last().filter(|c| matches!(c, '%' | '!' | '#'));
+    let number_text = match type_suffix {
+        Some(_) => &tmp_string[..tmp_string.len() - 1],
+        None => tmp_string
+    };
+    let has_double_exponent = number_text.contains('D');
+    let has_exponent = has_double_exponent || number_text.contains('E');
+    // As in GW-BASIC a missing exponent or mantissa is read as zero
+    // so `1E`, `1E+` and `.` are valid numbers
+    let mut parsed_text = number_text.replace('D', "E");
+    if parsed_text.starts_with('.') && !parsed_text[1..].starts_with(|c: char| c.is_ascii_digit()) {
+        parsed_text.insert(0, '0');
+    }
+    if parsed_text.ends_with(['E', '+', '-']) {
+        parsed_text.push('0');
+    }
+    let value = f64::from_str(&parsed_text).ok()?;
+    let token = match type_suffix {
+        Some('%') => {
+            let rounded = value.round();
+            if rounded < f64::from(i16::MIN) || rounded > f64::from(i16::MAX) {
+                return Some(GwToken::NumberOverflow(tmp_string.to_string()));
+            }
+            GwToken::FormattedInteger(rounded as i16, tmp_string.to_string())
+        }
+        Some('#') => GwToken::Double(value, tmp_string.to_string()),
+        Some(_) => GwToken::Single(value as f32, tmp_string.to_string()),
+        None if has_double_exponent =>
+            GwToken::Double(value, tmp_string.to_string()),
+        None if !has_exponent && !number_text.contains('.') && value <= f64::from(i16::MAX) =>
+            GwToken::Integer(value as i16),
+        None if !has_exponent
+                && number_text.chars().filter(|c| c.is_ascii_digit()).count() > 7 =>
+            GwToken::Double(value, tmp_string.to_string()),
+        None => GwToken::Single(value as f32, tmp_string.to_string())
+    };
+    match token {
+        GwToken::Single(single_value, _) if single_value.is_infinite() =>
+            Some(GwToken::NumberOverflow(tmp_string.to_string())),
+        GwToken::Double(double_value, _) if double_value.is_infinite() =>
+            Some(GwToken::NumberOverflow(tmp_string.to_string())),
+        _ => Some(token)
+    }
+}
+
+/// Recognizes `&H1F`, `&O17` and `&17`. Values above `&H7FFF`
+/// are negative as in GW-BASIC, an `&` without digits is returned
+/// as an unrecognized `&`
+fn recognize_radix_number_str<'a>(iterator : &mut PushbackCharsIterator<'a>) -> Option<GwToken> {
+    let mut text = String::from("&");
+    let radix = match iterator.peek() {
+        Some('H') | Some('h') => {
+            iterator.next();
+            text.push('H');
+            16
+        }
+        Some('O') | Some('o') => {
+            iterator.next();
+            text.push('O');
+            8
+        }
+        _ => 8
+    };
+    let mut value: u32 = 0;
+    let mut digit_count = 0;
+    while let Some(digit) = iterator.peek().and_then(|c| c.to_digit(radix)) {
+        text.push(iterator.next()?.to_ascii_uppercase());
+        value = value.saturating_mul(radix).saturating_add(digit);
+        digit_count += 1;
+    }
+    if value > u32::from(u16::MAX) {
+        return Some(GwToken::NumberOverflow(text));
+    }
+    if digit_count == 0 {
+        Some(GwToken::Unrecognized('&'))
     } else {
-        if let Ok(result) = i16::from_str_radix(&tmp_string.to_string(), 10) {
-            Some(GwToken::Integer(result))
-        } else {
-            None
-        }
+        Some(GwToken::FormattedInteger(value as u16 as i16, text))
     }
 }
 
-
 fn recognize_float_number_str<'a>(iterator : &mut PushbackCharsIterator<'a>) -> Option<GwToken> {
     if let Some(c) = iterator.next()  {
-        let mut has_dot = false;
-        if c.is_digit(10) || c == '.'  {
-            if c == '.' {
-                has_dot = true;
-            }
+        if c == '&' {
+            return recognize_radix_number_str(iterator);
+        }
+        if c.is_ascii_digit() || c == '.'  {
+            let mut has_dot = c == '.';
             let mut tmp_string = String::new();
             tmp_string.push(c);
-            loop {
-                if let Some(c) = iterator.next() {
-                    if c.is_digit(10) {
-                        tmp_string.push(c);
-                    } else if c == '.' && !has_dot {
-                        tmp_string.push(c);
-                        has_dot = true;
-                    } else {
-                        if c == 'e' || c == 'E' {
-                            if !has_dot {
-                                has_dot = true;
-                                tmp_string.push('.');
-                                tmp_string.push('0');
-                            }
-                            tmp_string.push(c);
-                            if let Some(chr) = iterator.next() {
-                                if chr == '-' {
-                                    tmp_string.push(chr);
-                                } else {
-                                    iterator.push_back(chr);
-                                }
-                            }
-                        } else {
-                            iterator.push_back(c);
-                            break;
-                        }
-
-                    }
+            while let Some(c) = iterator.peek() {
+                if c.is_ascii_digit() {
+                    tmp_string.push(c);
+                } else if c == '.' && !has_dot {
+                    tmp_string.push(c);
+                    has_dot = true;
                 } else {
                     break;
                 }
+                iterator.next();
             }
-            convert_numeric_string(&tmp_string.to_string())
+            if let Some(exponent_char @ ('e' | 'E' | 'd' | 'D')) = iterator.peek() {
+                iterator.next();
+                tmp_string.push(exponent_char.to_ascii_uppercase());
+                if let Some(sign @ ('+' | '-')) = iterator.peek() {
+                    iterator.next();
+                    tmp_string.push(sign);
+                }
+                while let Some(digit) = iterator.peek().filter(|c| c.is_ascii_digit()) {
+                    iterator.next();
+                    tmp_string.push(digit);
+                }
+            }
+            if let Some(suffix @ ('%' | '!' | '#')) = iterator.peek() {
+                iterator.next();
+                tmp_string.push(suffix);
+            }
+            convert_numeric_string(&tmp_string)
         } else {
             iterator.push_back(c);
             None
@@ -277,18 +372,45 @@ fn recognize_float_number_str<'a>(iterator : &mut PushbackCharsIterator<'a>) ->
 pub struct PushbackTokensIterator<'a> {
     chars_iterator: PushbackCharsIterator<'a>,
     tokens_info: tokens::GwTokenInfo,
-    pushed_back: Option<GwToken>
+    pushed_back: Option<GwToken>,
+    line_length: usize,
+    token_column: usize
 }
 
 impl<'a> PushbackTokensIterator<'a> {
     fn create(chars_iterator : PushbackCharsIterator<'a>) -> PushbackTokensIterator<'a> {
+        let line_length = chars_iterator.remaining_chars();
         PushbackTokensIterator {
             chars_iterator: chars_iterator,
             tokens_info: tokens::GwTokenInfo::create(),
-            pushed_back: None
+            pushed_back: None,
+            line_length,
+            token_column: 1
         }
     }
 
+    /// Column (starting at 1) of the last token read
+    pub fn current_column(&self) -> usize {
+        self.token_column
+    }
+
+    /// Creates a syntax error located at the last token read
+    pub fn syntax_error(&self) -> ParserError {
+        ParserError::new(GwError::SyntaxError, self.token_column)
+    }
+
+    /// Recognizes a keyword followed by a character that was read as
+    /// a type suffix, returning the suffix to the characters iterator
+    fn keyword_before_suffix(&mut self, word: &str) -> Option<tokens::GwBasicToken> {
+        let suffix = word.chars().last()?;
+        if !matches!(suffix, '%' | '!' | '#') {
+            return None;
+        }
+        let keyword = self.tokens_info.get_token(&word[..word.len() - 1].to_string())?.clone();
+        self.chars_iterator.push_back(suffix);
+        Some(keyword)
+    }
+
     pub fn get_internal_iterator(&mut self) -> &mut PushbackCharsIterator<'a> {
         &mut self.chars_iterator
     }
@@ -304,6 +426,7 @@ impl<'a> PushbackTokensIterator<'a> {
         }
 
         consume_whitespace(&mut self.chars_iterator);
+        self.token_column = self.line_length - self.chars_iterator.remaining_chars() + 1;
         if let Some(word) = recognize_word(&mut self.chars_iterator) {
             // Controversial! but it seems that GwBasic
             // changes the case of identifers
@@ -312,6 +435,9 @@ impl<'a> PushbackTokensIterator<'a> {
 
             if let Some(kw) =  self.tokens_info.get_token(&upper_case_word) {
                 return Some(GwToken::Keyword(kw.clone()))
+            } else if let Some(kw) = self.keyword_before_suffix(&upper_case_word) {
+                // For example `PRINT#1`
+                return Some(GwToken::Keyword(kw))
             } else {
                 return Some(GwToken::Identifier(upper_case_word));
             }
@@ -325,6 +451,8 @@ impl<'a> PushbackTokensIterator<'a> {
             return Some(GwToken::Keyword(tokens::GwBasicToken::PowOperatorTok));
         } else if recognize_specific_char(&mut self.chars_iterator, '/') {
             return Some(GwToken::Keyword(tokens::GwBasicToken::DivTok));
+        } else if recognize_specific_char(&mut self.chars_iterator, '\\') {
+            return Some(GwToken::Keyword(tokens::GwBasicToken::Div2Tok));
         } else if recognize_specific_char(&mut self.chars_iterator, '-') {
             return Some(GwToken::Keyword(tokens::GwBasicToken::MinusTok));
 
@@ -359,7 +487,7 @@ impl<'a> PushbackTokensIterator<'a> {
         } else if recognize_eol(&mut self.chars_iterator) {
             return None;
         }
-        panic!("Not implemented scenario of non recognized token!");
+        self.chars_iterator.next().map(GwToken::Unrecognized)
     }
 
     pub fn push_back(&mut self, tok_to_push : GwToken) {
@@ -378,10 +506,34 @@ impl<'a> PushbackTokensIterator<'a> {
 
 pub enum ParserResult<T> {
     Success(T),
-    Error(String),
+    Error(ParserError),
     Nothing
 }
 
+/// Error found while parsing a line, with the column where it was detected
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserError {
+    pub error: GwError,
+    pub column: usize
+}
+
+impl ParserError {
+    pub fn new(error: GwError, column: usize) -> ParserError {
+        ParserError { error, column }
+    }
+
+    /// Returns the same error associated with the given source line
+    pub fn at_line(self, line: i16) -> ParserError {
+        ParserError::new(self.error.at_line(line), self.column)
+    }
+}
+
+impl std::fmt::Display for ParserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
 pub enum IdExpressionResult<T> {
     Var(GwVariableExpression),
     Arr(GwCall),
@@ -389,35 +541,6 @@ pub enum IdExpressionResult<T> {
     Error(ParserResult<T>)
 }
 
-fn is_builtin_function(func_name: &String) -> bool {
-    match func_name.as_str() {
-        "ABS" => true,
-        "LOG" => true,
-        "INT" => true,
-        "COS" => true,
-        "SIN" => true,
-        "RND" => true,
-        "LEFT$" => true,
-        _ => false
-    }
-}
-fn create_builtin_function(func_name: &String, args: Vec<Box<dyn GwExpression>>)
-                           -> Option<Box<dyn GwExpression>> {
-    let mut mut_args = args;
-    match func_name.as_str() {
-        "ABS" if mut_args.len() == 1 => Some(Box::new(GwAbs { expr: mut_args.remove(0) })),
-        "LOG" if mut_args.len() == 1 => Some(Box::new(GwLog { expr: mut_args.remove(0) })),
-        "INT" if mut_args.len() == 1 => Some(Box::new(GwInt { expr: mut_args.remove(0) })),
-        "COS" if mut_args.len() == 1 => Some(Box::new(GwCos { expr: mut_args.remove(0) })),
-        "SIN" if mut_args.len() == 1 => Some(Box::new(GwSin { expr: mut_args.remove(0) })),
-        "RND" if mut_args.len() == 1 => Some(Box::new(GwRnd { expr: mut_args.remove(0) })),
-        "LEFT$" if mut_args.len() == 2 => Some(Box::new(GwLeftStr::new(
-            mut_args.remove(0),
-            mut_args.remove(0)))),        
-        _ => None
-    }
-}
-
 fn parse_id_expression_aux<'a, T>(iterator : &mut PushbackTokensIterator<'a>,
                            id : String)
 			      -> IdExpressionResult<T>
@@ -431,20 +554,16 @@ fn parse_id_expression_aux<'a, T>(iterator : &mut PushbackTokensIterator<'a>,
                                      tokens::GwBasicToken::CommaSeparatorTok);
             if let ParserResult::Success(array) = array_indices_result {
                 if let Some(GwToken::Keyword(tokens::GwBasicToken::RparTok))  = iterator.next() {
-                    if is_builtin_function(&id) {
-                        return IdExpressionResult::Builtin(create_builtin_function(&id, array).expect("Builtin function not created"))
-                    } else {
-                        return IdExpressionResult::Arr(
-                            GwCall {
-                                array_or_function: id,
-                                arguments: array
-                            } );
-                    }
+                    return IdExpressionResult::Arr(
+                        GwCall {
+                            array_or_function: id,
+                            arguments: array
+                        } );
                 } else {
-                    return IdExpressionResult::Error(ParserResult::Error(String::from("Right parenthesis expected")));
+                    return IdExpressionResult::Error(ParserResult::Error(iterator.syntax_error()));
                 }
             } else {
-                return IdExpressionResult::Error(ParserResult::Error(String::from("Error parsing  array indices")));
+                return IdExpressionResult::Error(ParserResult::Error(iterator.syntax_error()));
             }
         } else {
             iterator.push_back(next_token);
@@ -481,7 +600,7 @@ fn parse_restrict_identifier_expression(iterator : &mut PushbackTokensIterator)
 		    Box::new(arr_access);
 		result = ParserResult::Success(tmp);
 	    },
-            IdExpressionResult::Builtin(_) => { result = ParserResult::Error(String::from("Cannot assign to builtin function")) },
+            IdExpressionResult::Builtin(_) => { result = ParserResult::Error(iterator.syntax_error()) },
             IdExpressionResult::Error(err) => { result = err;}
 
         }
@@ -503,7 +622,7 @@ fn parse_single_int<'a>(iterator: &mut PushbackTokensIterator<'a>)
 
 
 fn parse_args(iterator: &mut PushbackTokensIterator)
-	      -> Result<Vec<Box<dyn GwExpression>>, String>
+	      -> Result<Vec<Box<dyn GwExpression>>, ParserError>
 
 {
     if let Some(next_token) = iterator.next() {
@@ -516,33 +635,121 @@ fn parse_args(iterator: &mut PushbackTokensIterator)
                 if let Some(GwToken::Keyword(tokens::GwBasicToken::RparTok))  = iterator.next() {
                     return Ok(array);
                 } else {
-                    return Err(String::from("Right parenthesis expected"));
+                    return Err(iterator.syntax_error());
                 }
             } else {
-                return Err(String::from("Error parsing  array indices"));
+                return Err(iterator.syntax_error());
             }
         } else {
             iterator.push_back(next_token);
         }
     }
-    Err("Syntax error".to_string())
+    Err(iterator.syntax_error())
 }
 
+/// Creates the expression of a builtin function from its arguments
+type BuiltinConstructor = fn(Vec<Box<dyn GwExpression>>) -> Box<dyn GwExpression>;
+
 pub fn try_parsing_builtin_function(
                   iterator : &mut PushbackTokensIterator,
                   current_token: &GwToken)
     -> Option<ParserResult<Box<dyn GwExpression>>> {
-    match current_token {
-        GwToken::Keyword(tokens::GwBasicToken::LeftDTok) => {
-            match parse_args(iterator) {
-                Ok(mut args) if args.len() == 2  => 
-                    Some(ParserResult::Success(
-                        Box::new(GwLeftStr::new(args.remove(0), args.remove(0))))),
-                Ok(_) => Some(ParserResult::Error("Syntax error".to_string())),
-                Err(err) => Some(ParserResult::Error(err)),
-            }            
-        }
-        _ => None
+    let (arg_count_range, constructor): (RangeInclusive<usize>, BuiltinConstructor) = match current_token {
+        GwToken::Keyword(tokens::GwBasicToken::LeftDTok) =>
+            (2..=2, |mut args| Box::new(GwLeftStr::new(args.remove(0), args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::RightDTok) =>
+            (2..=2, |mut args| Box::new(GwRightStr::new(args.remove(0), args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::StringDTok) =>
+            (2..=2, |mut args| Box::new(GwStringStr::new(args.remove(0), args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::MidDTok) =>
+            (2..=3, |mut args| Box::new(GwMidStr::new(args.remove(0), args.remove(0), args.pop()))),
+        GwToken::Keyword(tokens::GwBasicToken::InstrTok) =>
+            (2..=3, |mut args| if args.len() == 3 {
+                Box::new(GwInstr::new(Some(args.remove(0)), args.remove(0), args.remove(0)))
+            } else {
+                Box::new(GwInstr::new(None, args.remove(0), args.remove(0)))
+            }),
+        GwToken::Keyword(tokens::GwBasicToken::LenTok) =>
+            (1..=1, |mut args| Box::new(GwLen::new(args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::ChrDTok) =>
+            (1..=1, |mut args| Box::new(GwChrStr::new(args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::AscTok) =>
+            (1..=1, |mut args| Box::new(GwAsc::new(args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::StrDTok) =>
+            (1..=1, |mut args| Box::new(GwStrStr::new(args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::ValTok) =>
+            (1..=1, |mut args| Box::new(GwVal::new(args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::SpaceDTok) =>
+            (1..=1, |mut args| Box::new(GwSpaceStr::new(args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::EofTok) =>
+            (1..=1, |mut args| Box::new(GwFileFunction::new(GwFileFunctionKind::Eof, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::LofTok) =>
+            (1..=1, |mut args| Box::new(GwFileFunction::new(GwFileFunctionKind::Lof, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::LocTok) =>
+            (1..=1, |mut args| Box::new(GwFileFunction::new(GwFileFunctionKind::Loc, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::MkiDTok) =>
+            (1..=1, |mut args| Box::new(GwMakeStr::new(ExpressionType::Integer, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::MksDTok) =>
+            (1..=1, |mut args| Box::new(GwMakeStr::new(ExpressionType::Single, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::MkdDTok) =>
+            (1..=1, |mut args| Box::new(GwMakeStr::new(ExpressionType::Double, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::CviTok) =>
+            (1..=1, |mut args| Box::new(GwConvertStr::new(ExpressionType::Integer, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::CvsTok) =>
+            (1..=1, |mut args| Box::new(GwConvertStr::new(ExpressionType::Single, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::CvdTok) =>
+            (1..=1, |mut args| Box::new(GwConvertStr::new(ExpressionType::Double, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::AbsTok) =>
+            (1..=1, |mut args| Box::new(GwAbs { expr: args.remove(0) })),
+        GwToken::Keyword(tokens::GwBasicToken::LogTok) =>
+            (1..=1, |mut args| Box::new(GwLog { expr: args.remove(0) })),
+        GwToken::Keyword(tokens::GwBasicToken::IntTok) =>
+            (1..=1, |mut args| Box::new(GwInt { expr: args.remove(0) })),
+        GwToken::Keyword(tokens::GwBasicToken::SinTok) =>
+            (1..=1, |mut args| Box::new(GwSin { expr: args.remove(0) })),
+        GwToken::Keyword(tokens::GwBasicToken::CosTok) =>
+            (1..=1, |mut args| Box::new(GwCos { expr: args.remove(0) })),
+        GwToken::Keyword(tokens::GwBasicToken::RndTok) => {
+            // RND may be used without an argument
+            match iterator.next() {
+                Some(lpar@GwToken::Keyword(tokens::GwBasicToken::LparTok)) => iterator.push_back(lpar),
+                Some(other) => {
+                    iterator.push_back(other);
+                    return Some(ParserResult::Success(Box::new(GwRnd { expr: None })));
+                }
+                None => return Some(ParserResult::Success(Box::new(GwRnd { expr: None })))
+            }
+            (1..=1, |mut args| Box::new(GwRnd { expr: Some(args.remove(0)) }))
+        }
+        GwToken::Keyword(tokens::GwBasicToken::SqrTok) =>
+            (1..=1, |mut args| Box::new(GwNumericFunction::new(GwNumericFunctionKind::Sqr, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::TanTok) =>
+            (1..=1, |mut args| Box::new(GwNumericFunction::new(GwNumericFunctionKind::Tan, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::AtnTok) =>
+            (1..=1, |mut args| Box::new(GwNumericFunction::new(GwNumericFunctionKind::Atn, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::ExpTok) =>
+            (1..=1, |mut args| Box::new(GwNumericFunction::new(GwNumericFunctionKind::Exp, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::SgnTok) =>
+            (1..=1, |mut args| Box::new(GwNumericFunction::new(GwNumericFunctionKind::Sgn, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::FixTok) =>
+            (1..=1, |mut args| Box::new(GwNumericFunction::new(GwNumericFunctionKind::Fix, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::CintTok) =>
+            (1..=1, |mut args| Box::new(GwNumericFunction::new(GwNumericFunctionKind::Cint, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::CsngTok) =>
+            (1..=1, |mut args| Box::new(GwNumericFunction::new(GwNumericFunctionKind::Csng, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::CdblTok) =>
+            (1..=1, |mut args| Box::new(GwNumericFunction::new(GwNumericFunctionKind::Cdbl, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::HexDTok) =>
+            (1..=1, |mut args| Box::new(GwNumericFunction::new(GwNumericFunctionKind::HexStr, args.remove(0)))),
+        GwToken::Keyword(tokens::GwBasicToken::OctDTok) =>
+            (1..=1, |mut args| Box::new(GwNumericFunction::new(GwNumericFunctionKind::OctStr, args.remove(0)))),
+        _ => return None
+    };
+    match parse_args(iterator) {
+        Ok(args) if arg_count_range.contains(&args.len()) =>
+            Some(ParserResult::Success(constructor(args))),
+        Ok(_) => Some(ParserResult::Error(iterator.syntax_error())),
+        Err(err) => Some(ParserResult::Error(err))
     }
 }
 
@@ -550,19 +757,41 @@ pub fn parse_single_expression<'a>(iterator : &mut PushbackTokensIterator<'a>)
                                    -> ParserResult<Box<dyn GwExpression>> {
     if let Some(next_token) = iterator.next() {
         if let GwToken::Identifier(id) = next_token {
+            if is_user_function_name(&id) {
+                return parse_fn_call_expression(iterator, id);
+            }
             return parse_id_expression(iterator, id);
+        } else if let GwToken::Keyword(tokens::GwBasicToken::FnTok) = next_token {
+            if let Some(GwToken::Identifier(id)) = iterator.next() {
+                return parse_fn_call_expression(iterator, format!("FN{}", id));
+            }
+            return ParserResult::Error(iterator.syntax_error());
         } else if let GwToken::Integer(i_val) = next_token {
             return ParserResult::Success(Box::new(GwIntegerLiteral::with_value(i_val)))
-        } else if let GwToken::Double(d_val) = next_token {
-            return ParserResult::Success(Box::new(GwDoubleLiteral::with_value(d_val)))
+        } else if let GwToken::FormattedInteger(i_val, text) = next_token {
+            return ParserResult::Success(Box::new(GwIntegerLiteral::with_text(i_val, text)))
+        } else if let GwToken::Single(s_val, text) = next_token {
+            return ParserResult::Success(Box::new(GwSingleLiteral::with_text(s_val, text)))
+        } else if let GwToken::Double(d_val, text) = next_token {
+            return ParserResult::Success(Box::new(GwDoubleLiteral::with_text(d_val, text)))
         } else if let GwToken::String(str_val) = next_token {
             return ParserResult::Success(Box::new(GwStringLiteral::with_value(str_val)))
+        } else if let GwToken::NumberOverflow(_) = next_token {
+            return ParserResult::Error(ParserError::new(GwError::Overflow, iterator.current_column()));
         } else if let GwToken::Keyword(tokens::GwBasicToken::LparTok) = next_token {            
             return parse_parenthesized_expression(iterator);
         } else if let GwToken::Keyword(tokens::GwBasicToken::MinusTok) = next_token {
             return parse_negation_expression(iterator);
+        } else if let GwToken::Keyword(tokens::GwBasicToken::NotTok) = next_token {
+            return parse_not_expression(iterator);
         } else if let GwToken::Keyword(tokens::GwBasicToken::InkeyDTok) = next_token {
             return ParserResult::Success(Box::new(GwInkey {}));
+        } else if let GwToken::Keyword(tokens::GwBasicToken::ErrTok) = next_token {
+            return ParserResult::Success(Box::new(GwErr {}));
+        } else if let GwToken::Keyword(tokens::GwBasicToken::ErlTok) = next_token {
+            return ParserResult::Success(Box::new(GwErl {}));
+        } else if let GwToken::Keyword(tokens::GwBasicToken::TimerTok) = next_token {
+            return ParserResult::Success(Box::new(GwTimer {}));
         } else if let Some(result) = try_parsing_builtin_function(iterator, &next_token) {
             return result;
         } else {
@@ -572,16 +801,40 @@ pub fn parse_single_expression<'a>(iterator : &mut PushbackTokensIterator<'a>)
     ParserResult::Nothing
 }
 
+/// Names starting with `FN` refer to user defined functions
+fn is_user_function_name(name: &str) -> bool {
+    name.len() > 2 && name.starts_with("FN")
+}
+
+fn parse_fn_call_expression<'a>(iterator : &mut PushbackTokensIterator<'a>,
+                                name : String)
+                                -> ParserResult<Box<dyn GwExpression>> {
+    match iterator.next() {
+        Some(GwToken::Keyword(tokens::GwBasicToken::LparTok)) => {
+            iterator.push_back(GwToken::Keyword(tokens::GwBasicToken::LparTok));
+            match parse_args(iterator) {
+                Ok(args) => ParserResult::Success(Box::new(GwFnCall::new(name, args))),
+                Err(error) => ParserResult::Error(error)
+            }
+        }
+        Some(other) => {
+            iterator.push_back(other);
+            ParserResult::Success(Box::new(GwFnCall::new(name, vec![])))
+        }
+        None => ParserResult::Success(Box::new(GwFnCall::new(name, vec![])))
+    }
+}
+
 fn parse_parenthesized_expression<'a>(iterator : &mut PushbackTokensIterator<'a>)
                                       -> ParserResult<Box<dyn GwExpression>> {
     if let ParserResult::Success(inner_expr) = parse_expression(iterator) {
         if let Some(GwToken::Keyword(tokens::GwBasicToken::RparTok)) = iterator.next() {
             return ParserResult::Success(Box::new(GwParenthesizedExpr::new(inner_expr)));
         } else {
-            return ParserResult::Error(String::from("Expecting right side parenthesis"));
+            return ParserResult::Error(iterator.syntax_error());
         }
     } else {
-        return ParserResult::Error(String::from("Expecting expression on parenthesized expression"));
+        return ParserResult::Error(iterator.syntax_error());
     }
 }
 
@@ -593,10 +846,21 @@ fn parse_negation_expression<'a>(iterator : &mut PushbackTokensIterator<'a>)
                 expr: inner_expr
             } ));
     } else {
-        return ParserResult::Error(String::from("Expecting single expression on negation"));
+        return ParserResult::Error(iterator.syntax_error());
     }
 }
 
+/// `NOT` applies to the whole comparison that follows it, since it has
+/// lower precedence than relational operators
+fn parse_not_expression<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                            -> ParserResult<Box<dyn GwExpression>> {
+    match parse_comparison_expression(iterator) {
+        ParserResult::Success(inner_expr) =>
+            ParserResult::Success(Box::new(GwNotExpr { expr: inner_expr })),
+        ParserResult::Error(error) => ParserResult::Error(error),
+        ParserResult::Nothing => ParserResult::Error(iterator.syntax_error())
+    }
+}
 
 fn one_kw_token_of<'a>(token : &'a GwToken, t1 : &'a tokens::GwBasicToken, t2 : &'a tokens::GwBasicToken) -> Option<&'a tokens::GwBasicToken>{
     match token  {
@@ -631,7 +895,7 @@ pub fn parse_multiplicative_expressions<'a>(iterator : &mut PushbackTokensIterat
                          Box::new(
                             GwBinaryOperation::new(kind, current_expr, right_side_parse_result ));
              } else {
-                 return ParserResult::Error(String::from("Error parsing additive expression, expecting right side operand "));
+                 return ParserResult::Error(iterator.syntax_error());
              }
          } else {
              iterator.push_back(next_token);
@@ -656,7 +920,7 @@ pub fn parse_exponential_expressions<'a>(iterator : &mut PushbackTokensIterator<
                          Box::new(
                             GwBinaryOperation::new(kind, current_expr, right_side_parse_result ));
              } else {
-                 return ParserResult::Error(String::from("Error parsing additive expression, expecting right side operand "));
+                 return ParserResult::Error(iterator.syntax_error());
              }
          } else {
              iterator.push_back(next_token);
@@ -699,6 +963,18 @@ pub fn parse_multiplicative_expression<'a>(iterator : &mut PushbackTokensIterato
     }
 }
 
+// Integer division has lower precedence than multiplication and
+// division, and higher than `MOD`
+fn parse_integer_division_expression<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                                         -> ParserResult<Box<dyn GwExpression>> {
+    parse_operator_sequence(iterator, tokens::GwBasicToken::Div2Tok, parse_multiplicative_expression)
+}
+
+fn parse_mod_expression<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                            -> ParserResult<Box<dyn GwExpression>> {
+    parse_operator_sequence(iterator, tokens::GwBasicToken::ModTok, parse_integer_division_expression)
+}
+
 ////
 fn get_operation_kind_from_token(token : &tokens::GwBasicToken)
                                  -> Option<GwBinaryOperationKind> {
@@ -714,6 +990,13 @@ fn get_operation_kind_from_token(token : &tokens::GwBasicToken)
         tokens::GwBasicToken::GtTok => Some(GwBinaryOperationKind::GreaterThan),
         tokens::GwBasicToken::LteTok => Some(GwBinaryOperationKind::LessEqualThan),
         tokens::GwBasicToken::GteTok => Some(GwBinaryOperationKind::GreaterEqualThan),
+        tokens::GwBasicToken::Div2Tok => Some(GwBinaryOperationKind::IntDiv),
+        tokens::GwBasicToken::ModTok => Some(GwBinaryOperationKind::Mod),
+        tokens::GwBasicToken::AndTok => Some(GwBinaryOperationKind::And),
+        tokens::GwBasicToken::OrTok => Some(GwBinaryOperationKind::Or),
+        tokens::GwBasicToken::XorTok => Some(GwBinaryOperationKind::Xor),
+        tokens::GwBasicToken::EqvTok => Some(GwBinaryOperationKind::Eqv),
+        tokens::GwBasicToken::ImpTok => Some(GwBinaryOperationKind::Implication),
         _ => None
     }
 }
@@ -727,7 +1010,7 @@ pub fn parse_additive_expressions<'a>(iterator : &mut PushbackTokensIterator<'a>
   loop {
      if let Some(next_token) = iterator.next() {
          if let Some(tok) = one_kw_token_of(&next_token, &tokens::GwBasicToken::PlusTok, &tokens::GwBasicToken::MinusTok) {
-             if let ParserResult::Success(right_side_parse_result) = parse_multiplicative_expression(iterator) {
+             if let ParserResult::Success(right_side_parse_result) = parse_mod_expression(iterator) {
 
                 current_expr =
                          Box::new(
@@ -736,7 +1019,7 @@ pub fn parse_additive_expressions<'a>(iterator : &mut PushbackTokensIterator<'a>
                                 current_expr,
                                 right_side_parse_result ));
              } else {
-                 return ParserResult::Error(String::from("Error parsing additive expression, expecting right side operand "));
+                 return ParserResult::Error(iterator.syntax_error());
              }
          } else {
              iterator.push_back(next_token);
@@ -753,7 +1036,7 @@ pub fn parse_additive_expressions<'a>(iterator : &mut PushbackTokensIterator<'a>
 pub fn parse_additive_expression<'a>(iterator : &mut PushbackTokensIterator<'a>)
                                      -> ParserResult<Box<dyn GwExpression>> {
 
-    match parse_multiplicative_expression(iterator) {
+    match parse_mod_expression(iterator) {
         ParserResult::Success(left_side_parse_result) => {
             return parse_additive_expressions(iterator, left_side_parse_result);
         },
@@ -797,7 +1080,7 @@ pub fn parse_comparison_expressions<'a>(iterator : &mut PushbackTokensIterator<'
                                 current_expr,
                                 right_side_parse_result ));
              } else {
-                 return ParserResult::Error(String::from("Error parsing comparison expression, expecting right side operand "));
+                 return ParserResult::Error(iterator.syntax_error());
              }
          } else {
              iterator.push_back(next_token);
@@ -827,9 +1110,65 @@ pub fn parse_comparison_expression<'a>(iterator : &mut PushbackTokensIterator<'a
 }
 
 
+/// Parses a sequence of operands separated by the left associative
+/// operator `operator`
+fn parse_operator_sequence<'a>(iterator : &mut PushbackTokensIterator<'a>,
+                                operator : tokens::GwBasicToken,
+                                parse_operand : fn(&mut PushbackTokensIterator<'a>)
+                                                   -> ParserResult<Box<dyn GwExpression>>)
+                                -> ParserResult<Box<dyn GwExpression>> {
+    let mut current_expr = match parse_operand(iterator) {
+        ParserResult::Success(expr) => expr,
+        other => return other
+    };
+    loop {
+        match iterator.next() {
+            Some(GwToken::Keyword(tok)) if tok == operator => {
+                if let ParserResult::Success(right_side_parse_result) = parse_operand(iterator) {
+                    current_expr =
+                        Box::new(
+                            GwBinaryOperation::new(
+                                get_operation_kind_from_token(&tok).unwrap(),
+                                current_expr,
+                                right_side_parse_result));
+                } else {
+                    return ParserResult::Error(iterator.syntax_error());
+                }
+            }
+            Some(next_token) => {
+                iterator.push_back(next_token);
+                return ParserResult::Success(current_expr);
+            }
+            None => return ParserResult::Success(current_expr)
+        }
+    }
+}
+
+// Logical operators have lower precedence than comparisons, from
+// higher to lower: `AND`, `OR`, `XOR`, `EQV` and `IMP`
+fn parse_and_expression<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                            -> ParserResult<Box<dyn GwExpression>> {
+    parse_operator_sequence(iterator, tokens::GwBasicToken::AndTok, parse_comparison_expression)
+}
+
+fn parse_or_expression<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                           -> ParserResult<Box<dyn GwExpression>> {
+    parse_operator_sequence(iterator, tokens::GwBasicToken::OrTok, parse_and_expression)
+}
+
+fn parse_xor_expression<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                            -> ParserResult<Box<dyn GwExpression>> {
+    parse_operator_sequence(iterator, tokens::GwBasicToken::XorTok, parse_or_expression)
+}
+
+fn parse_eqv_expression<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                            -> ParserResult<Box<dyn GwExpression>> {
+    parse_operator_sequence(iterator, tokens::GwBasicToken::EqvTok, parse_xor_expression)
+}
+
 pub fn parse_expression<'a>(iterator : &mut PushbackTokensIterator<'a>)
                             -> ParserResult<Box<dyn GwExpression>> {
-    return parse_comparison_expression(iterator);
+    parse_operator_sequence(iterator, tokens::GwBasicToken::ImpTok, parse_eqv_expression)
 }
 
 
@@ -841,6 +1180,41 @@ fn parse_list_stat<'a>(_iterator : &mut PushbackTokensIterator<'a>)
 }
 
 
+fn parse_new_stat<'a>(_iterator : &mut PushbackTokensIterator<'a>)
+                      -> ParserResult<Rc<dyn GwInstruction>> {
+    ParserResult::Success(Rc::new(GwNew {}))
+}
+
+/// Parses an optional line number of a `DELETE` range
+fn parse_range_line_number<'a>(iterator : &mut PushbackTokensIterator<'a>) -> Option<i16> {
+    match iterator.next() {
+        Some(GwToken::Integer(line_number)) => Some(line_number),
+        Some(token) => {
+            iterator.push_back(token);
+            None
+        }
+        None => None
+    }
+}
+
+/// Parses `DELETE` with a range written as `10-50`, `-30`, `100-` or `10`
+fn parse_delete_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                         -> ParserResult<Rc<dyn GwInstruction>> {
+    let first = parse_range_line_number(iterator);
+    let last = match iterator.next() {
+        Some(GwToken::Keyword(tokens::GwBasicToken::MinusTok)) => parse_range_line_number(iterator),
+        Some(token) => {
+            iterator.push_back(token);
+            first
+        }
+        None => first
+    };
+    if first.is_none() && last.is_none() {
+        return ParserResult::Error(iterator.syntax_error());
+    }
+    ParserResult::Success(Rc::new(GwDelete::new(LineRange { first, last })))
+}
+
 fn parse_run_stat<'a>(_iterator : &mut PushbackTokensIterator<'a>)
                       -> ParserResult<Rc<dyn GwInstruction>> {
     return ParserResult::Success(Rc::new(
@@ -858,6 +1232,11 @@ fn parse_system_stat<'a>(_iterator : &mut PushbackTokensIterator<'a>)
 
 fn parse_print_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
                         -> ParserResult<Rc<dyn GwInstruction>> {
+    let file_number = match parse_optional_file_number_prefix(iterator) {
+        ParserResult::Success(number) => Some(number),
+        ParserResult::Error(error) => return ParserResult::Error(error),
+        ParserResult::Nothing => None
+    };
     let mut is_using = false;
     if let Some(tok) = iterator.next() {
         if let GwToken::Keyword(tokens::GwBasicToken::UsingTok) = tok {
@@ -867,22 +1246,28 @@ fn parse_print_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
         }
     }
 
-    if let ParserResult::Success(exprs) = parse_with_flexible_separator(iterator) {
+    let parse_result = parse_with_flexible_separator(iterator);
+    if let ParserResult::Error(error) = parse_result {
+        return ParserResult::Error(error);
+    }
+    if let ParserResult::Success(exprs) = parse_result {
         if is_using  {
             return ParserResult::Success(Rc::new(
                 GwPrintUsingStat {
+                    file_number,
                     expressions: exprs
                 }
             ));
         } else {
             return ParserResult::Success(Rc::new(
                 GwPrintStat {
+                    file_number,
                     expressions: exprs
                 }
             ));
         }
     } else {
-        return ParserResult::Error(String::from("Expecting expression as PRINT argument"));
+        return ParserResult::Error(iterator.syntax_error());
     }
 }
 
@@ -895,7 +1280,18 @@ fn parse_load_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
             }
         ));
     } else {
-        return ParserResult::Error(String::from("Expecting expression as LOAD  argument"));
+        return ParserResult::Error(iterator.syntax_error());
+    }
+}
+
+
+/// Parses the `A` and `P` options of `SAVE` which save the program
+/// as text or protected
+fn parse_save_format<'a>(iterator : &mut PushbackTokensIterator<'a>) -> ParserResult<SaveFormat> {
+    match iterator.next() {
+        Some(GwToken::Identifier(option)) if option == "A" => ParserResult::Success(SaveFormat::Ascii),
+        Some(GwToken::Identifier(option)) if option == "P" => ParserResult::Success(SaveFormat::Protected),
+        _ => ParserResult::Error(iterator.syntax_error())
     }
 }
 
@@ -916,7 +1312,7 @@ macro_rules! parse_seq {
 	    Some($token_pattern) =>
 		parse_seq!($iterator, { $($tail)* },$action),
 
-	    _ => {  return ParserResult::Error(String::from($error_message)); }
+	    _ => {  return ParserResult::Error($iterator.syntax_error()); }
 	}
       }
     };
@@ -932,7 +1328,7 @@ macro_rules! parse_seq {
 	    Some(token) => {  $iterator.push_back(token); None }
 	    _ => { None }
 	};
-	    parse_seq!($iterator, { $($tail)* } , $action);
+	    parse_seq!($iterator, { $($tail)* } , $action)
         }
     };
 
@@ -945,7 +1341,7 @@ macro_rules! parse_seq {
             ParserResult::Success(res) => Some(res),
 	    _ => None 
 	};
-	    parse_seq!($iterator, { $($tail)* } , $action);
+	    parse_seq!($iterator, { $($tail)* } , $action)
         }
     };
 
@@ -963,7 +1359,7 @@ macro_rules! parse_seq {
 		return ParserResult::Error(error);
 	    }
 	    ParserResult::Nothing => {
-		return ParserResult::Error(String::from("Nothing!"));
+		return ParserResult::Error($iterator.syntax_error());
 	    }
 	}
         }
@@ -996,24 +1392,42 @@ fn parse_read_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
     parse_seq![
         iterator,
         {
-            parse_success(variable_expression, parse_restrict_identifier_expression(iterator));
+            parse_success(variables,
+                          parse_with_separator(iterator,
+                                               parse_restrict_identifier_expression,
+                                               tokens::GwBasicToken::CommaSeparatorTok));
         },
         {
             return ParserResult::Success(
                 Rc::new(
-                    GwRead::new(variable_expression)))
+                    GwRead::new(variables)))
         }
     ];
 }
 
+fn parse_restore_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
+                          -> ParserResult<Rc<dyn GwInstruction>> {
+    match parse_single_int(iterator) {
+        ParserResult::Success(line) => ParserResult::Success(Rc::new(GwRestore::new(Some(line)))),
+        _ => ParserResult::Success(Rc::new(GwRestore::new(None)))
+    }
+}
+
 fn parse_on_goto_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
                           -> ParserResult<Rc<dyn GwInstruction>> {
+    match iterator.next() {
+        Some(GwToken::Keyword(tokens::GwBasicToken::ErrorTok)) =>
+            return parse_on_error_goto_stat(iterator),
+        Some(other) => iterator.push_back(other),
+        None => {}
+    }
     parse_seq![
         iterator,
         {
             parse_success(goto_expr, parse_expression(iterator));
-            token(GwToken::Keyword(tokens::GwBasicToken::GotoTok),
-                  "Expecting left parenthesis");
+            token(GwToken::Keyword(jump_token@(tokens::GwBasicToken::GotoTok |
+                                               tokens::GwBasicToken::GosubTok)),
+                  "Expecting GOTO or GOSUB");
             parse_success(cases,
                           parse_with_separator(
                               iterator,
@@ -1021,12 +1435,103 @@ fn parse_on_goto_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
                                   tokens::GwBasicToken::CommaSeparatorTok));
         },
         {
+            if jump_token == tokens::GwBasicToken::GosubTok {
+                return ParserResult::Success(
+                         Rc::new(GwOnGoto::new_gosub(goto_expr, cases)))
+            }
             return ParserResult::Success(
                      Rc::new(GwOnGoto::new(goto_expr, cases)))
         }               
     ];
 }
 
+fn parse_on_error_goto_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
+                                -> ParserResult<Rc<dyn GwInstruction>> {
+    parse_seq![
+        iterator,
+        {
+            token(GwToken::Keyword(tokens::GwBasicToken::GotoTok),
+                  "Expecting GOTO");
+            token(GwToken::Integer(line_number), "Expecting line number");
+        },
+        {
+            ParserResult::Success(Rc::new(GwOnErrorGoto::new(line_number)))
+        }
+    ]
+}
+
+fn parse_resume_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
+                         -> ParserResult<Rc<dyn GwInstruction>> {
+    let target = match iterator.next() {
+        Some(GwToken::Keyword(tokens::GwBasicToken::NextTok)) => ResumeTarget::Next,
+        Some(GwToken::Integer(0)) => ResumeTarget::Current,
+        Some(GwToken::Integer(line_number)) => ResumeTarget::Line(line_number),
+        Some(other) => {
+            iterator.push_back(other);
+            ResumeTarget::Current
+        }
+        None => ResumeTarget::Current
+    };
+    ParserResult::Success(Rc::new(GwResume::new(target)))
+}
+
+fn parse_error_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
+                        -> ParserResult<Rc<dyn GwInstruction>> {
+    parse_seq![
+        iterator,
+        {
+            parse_success(code, parse_expression(iterator));
+        },
+        {
+            ParserResult::Success(Rc::new(GwErrorStat::new(code)))
+        }
+    ]
+}
+
+fn parse_def_fn_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
+                         -> ParserResult<Rc<dyn GwInstruction>> {
+    let name = match iterator.next() {
+        Some(GwToken::Identifier(name)) if is_user_function_name(&name) => name,
+        Some(GwToken::Keyword(tokens::GwBasicToken::FnTok)) => {
+            if let Some(GwToken::Identifier(name)) = iterator.next() {
+                format!("FN{}", name)
+            } else {
+                return ParserResult::Error(iterator.syntax_error());
+            }
+        }
+        _ => return ParserResult::Error(iterator.syntax_error())
+    };
+    let mut parameters = vec![];
+    match iterator.next() {
+        Some(GwToken::Keyword(tokens::GwBasicToken::LparTok)) => {
+            loop {
+                match iterator.next() {
+                    Some(GwToken::Identifier(parameter)) => parameters.push(parameter),
+                    _ => return ParserResult::Error(iterator.syntax_error())
+                }
+                match iterator.next() {
+                    Some(GwToken::Keyword(tokens::GwBasicToken::CommaSeparatorTok)) => {}
+                    Some(GwToken::Keyword(tokens::GwBasicToken::RparTok)) => break,
+                    _ => return ParserResult::Error(iterator.syntax_error())
+                }
+            }
+        }
+        Some(other) => iterator.push_back(other),
+        None => {}
+    }
+    parse_seq![
+        iterator,
+        {
+            token(GwToken::Keyword(tokens::GwBasicToken::EqlTok), "Expecting =");
+            parse_success(body, parse_expression(iterator));
+        },
+        {
+            ParserResult::Success(
+                Rc::new(GwDefFn::new(GwUserFunction::new(name, parameters, body))))
+        }
+    ]
+}
+
 fn parse_gosub_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
                         -> ParserResult<Rc<dyn GwInstruction>> {
     parse_seq![
@@ -1066,6 +1571,50 @@ fn parse_swap_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
     ];
 }
 
+fn parse_randomize_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
+                            -> ParserResult<Rc<dyn GwInstruction>> {
+    match parse_expression(iterator) {
+        ParserResult::Success(seed) =>
+            ParserResult::Success(Rc::new(GwRandomize::new(Some(seed)))),
+        ParserResult::Nothing =>
+            ParserResult::Success(Rc::new(GwRandomize::new(None))),
+        ParserResult::Error(error) => ParserResult::Error(error)
+    }
+}
+
+fn parse_mid_assignment_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
+                                 -> ParserResult<Rc<dyn GwInstruction>> {
+    parse_seq![
+        iterator,
+        {
+            token(GwToken::Keyword(tokens::GwBasicToken::LparTok),
+                  "Expecting left parenthesis");
+            parse_success(target, parse_restrict_identifier_expression(iterator));
+            token(GwToken::Keyword(tokens::GwBasicToken::CommaSeparatorTok),
+                  "Expecting comma");
+            parse_success(start, parse_expression(iterator));
+            opt_token(GwToken::Keyword(tokens::GwBasicToken::CommaSeparatorTok),
+                      length = parse_expression(iterator));
+            token(GwToken::Keyword(tokens::GwBasicToken::RparTok),
+                  "Expecting right parenthesis");
+            token(GwToken::Keyword(tokens::GwBasicToken::EqlTok),
+                  "Expecting equal sign");
+            parse_success(value, parse_expression(iterator));
+        },
+        {
+            let length = match length {
+                Some(ParserResult::Success(length)) => Some(length),
+                Some(ParserResult::Error(error)) => return ParserResult::Error(error),
+                Some(ParserResult::Nothing) => return ParserResult::Error(iterator.syntax_error()),
+                None => None
+            };
+            ParserResult::Success(
+                Rc::new(
+                    GwMidStrAssign::new(target, start, length, value)))
+        }
+    ]
+}
+
 fn parse_data_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
                        -> ParserResult<Rc<dyn GwInstruction>> {
     let chars_iterator = iterator.get_internal_iterator();
@@ -1083,16 +1632,26 @@ fn parse_data_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
             }
             break;
         }
+        let mut inside_quotes = false;
         loop {
             if let Some(next_char) = chars_iterator.next() {
-                if next_char == ',' {
-                    contents.push(current);
+                if next_char == '"' {
+                    inside_quotes = !inside_quotes;
+                    current.push(next_char);
+                } else if next_char == ',' && !inside_quotes {
+                    contents.push(current.trim_end().to_string());
+                    break;
+                } else if next_char == ':' && !inside_quotes {
+                    // Leave the separator for the next statement
+                    chars_iterator.push_back(next_char);
+                    contents.push(current.trim_end().to_string());
+                    done = true;
                     break;
                 } else {
                     current.push(next_char);
                 }
             } else {
-                contents.push(current);
+                contents.push(current.trim_end().to_string());
                 done = true;
                 break;
             }
@@ -1126,12 +1685,73 @@ fn parse_dim_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
                                 GwDim::new(decls.remove(0), None)))
                     }
                 } else {
-                    ParserResult::Error("DIM requires at least one declaration".to_string())
+                    ParserResult::Error(iterator.syntax_error())
                 }
         }
         ParserResult::Error(err) => ParserResult::Error(err),
-        ParserResult::Nothing => ParserResult::Error("Dimensions required".to_string())
+        ParserResult::Nothing => ParserResult::Error(iterator.syntax_error())
+    }
+}
+
+fn parse_option_base_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
+                              -> ParserResult<Rc<dyn GwInstruction>> {
+    match (iterator.next(), iterator.next()) {
+        (Some(GwToken::Identifier(base_word)), Some(GwToken::Integer(base)))
+            if base_word == "BASE" && (base == 0 || base == 1) =>
+            ParserResult::Success(Rc::new(GwOptionBase::new(base as usize))),
+        _ => ParserResult::Error(iterator.syntax_error())
+    }
+}
+
+fn parse_identifier_name<'a>(iterator: &mut PushbackTokensIterator<'a>)
+                             -> ParserResult<String> {
+    match iterator.next() {
+        Some(GwToken::Identifier(name)) => ParserResult::Success(name),
+        Some(other) => {
+            iterator.push_back(other);
+            ParserResult::Nothing
+        }
+        None => ParserResult::Nothing
+    }
+}
+
+fn parse_erase_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
+                        -> ParserResult<Rc<dyn GwInstruction>> {
+    match parse_with_separator(iterator,
+                               parse_identifier_name,
+                               tokens::GwBasicToken::CommaSeparatorTok) {
+        ParserResult::Success(names) if !names.is_empty() =>
+            ParserResult::Success(Rc::new(GwErase::new(names))),
+        ParserResult::Error(error) => ParserResult::Error(error),
+        _ => ParserResult::Error(iterator.syntax_error())
+    }
+}
+
+fn parse_clear_stat<'a>(iterator: &mut PushbackTokensIterator<'a>)
+                        -> ParserResult<Rc<dyn GwInstruction>> {
+    let mut arguments = vec![];
+    loop {
+        let argument = match parse_expression(iterator) {
+            ParserResult::Success(expr) => Some(expr),
+            ParserResult::Nothing => None,
+            ParserResult::Error(error) => return ParserResult::Error(error)
+        };
+        match iterator.next() {
+            Some(GwToken::Keyword(tokens::GwBasicToken::CommaSeparatorTok)) => {
+                arguments.push(argument);
+            }
+            other => {
+                if let Some(token) = other {
+                    iterator.push_back(token);
+                }
+                if argument.is_some() || !arguments.is_empty() {
+                    arguments.push(argument);
+                }
+                break;
+            }
+        }
     }
+    ParserResult::Success(Rc::new(GwClear::new(arguments)))
 }
 
 fn parse_dim_decl<'a>(iterator: &mut PushbackTokensIterator<'a>)
@@ -1221,6 +1841,19 @@ fn parse_stop_stat<'a>(_iterator : &mut PushbackTokensIterator<'a>)
 
 fn parse_input_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
                         -> ParserResult<Rc<dyn GwInstruction>> {
+    match parse_optional_file_number_prefix(iterator) {
+        ParserResult::Success(file_number) => {
+            return match parse_with_separator(iterator,
+                                              parse_restrict_identifier_expression,
+                                              tokens::GwBasicToken::CommaSeparatorTok) {
+                ParserResult::Success(variables) =>
+                    ParserResult::Success(Rc::new(GwInputFile::new(file_number, variables))),
+                _ => ParserResult::Error(iterator.syntax_error())
+            };
+        }
+        ParserResult::Error(error) => return ParserResult::Error(error),
+        ParserResult::Nothing => {}
+    }
     parse_seq![
 	iterator,
 	{
@@ -1273,33 +1906,384 @@ fn parse_input_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
     }*/
 }
 
+/// Parses a file number with an optional `#`, as in `CLOSE #1`
+fn parse_file_number<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                         -> ParserResult<Box<dyn GwExpression>> {
+    match iterator.next() {
+        Some(GwToken::Unrecognized('#')) => match parse_expression(iterator) {
+            ParserResult::Nothing => ParserResult::Error(iterator.syntax_error()),
+            result => result
+        },
+        Some(token) => {
+            iterator.push_back(token);
+            parse_expression(iterator)
+        }
+        None => ParserResult::Nothing
+    }
+}
+
+/// Parses the `#n,` prefix of `PRINT #`, `WRITE #` and `INPUT #`
+fn parse_optional_file_number_prefix<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                                         -> ParserResult<Box<dyn GwExpression>> {
+    match iterator.next() {
+        Some(GwToken::Unrecognized('#')) => {
+            parse_seq![
+                iterator,
+                {
+                    parse_success(file_number, parse_expression(iterator));
+                    token(GwToken::Keyword(tokens::GwBasicToken::CommaSeparatorTok),
+                          "Expecting comma");
+                },
+                {
+                    ParserResult::Success(file_number)
+                }
+            ]
+        }
+        Some(token) => {
+            iterator.push_back(token);
+            ParserResult::Nothing
+        }
+        None => ParserResult::Nothing
+    }
+}
+
+fn parse_line_input_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                             -> ParserResult<Rc<dyn GwInstruction>> {
+    parse_seq![
+        iterator,
+        {
+            token(GwToken::Keyword(tokens::GwBasicToken::InpTok), "Expecting INPUT");
+            parse_success(file_number, parse_optional_file_number_prefix(iterator));
+            parse_success(variable, parse_restrict_identifier_expression(iterator));
+        },
+        {
+            ParserResult::Success(Rc::new(GwInputFile::line_input(file_number, variable)))
+        }
+    ]
+}
+
+fn parse_write_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                        -> ParserResult<Rc<dyn GwInstruction>> {
+    let file_number = match parse_optional_file_number_prefix(iterator) {
+        ParserResult::Success(number) => Some(number),
+        ParserResult::Error(error) => return ParserResult::Error(error),
+        ParserResult::Nothing => None
+    };
+    let expressions = match iterator.next() {
+        Some(token@GwToken::Keyword(tokens::GwBasicToken::ColonSeparatorTok |
+                                    tokens::GwBasicToken::ElseTok)) => {
+            iterator.push_back(token);
+            vec![]
+        }
+        Some(token) => {
+            iterator.push_back(token);
+            match parse_with_separator(iterator,
+                                       parse_expression,
+                                       tokens::GwBasicToken::CommaSeparatorTok) {
+                ParserResult::Success(expressions) => expressions,
+                _ => return ParserResult::Error(iterator.syntax_error())
+            }
+        }
+        None => vec![]
+    };
+    ParserResult::Success(Rc::new(GwWrite::new(file_number, expressions)))
+}
+
+/// Parses the `AS #n [LEN = length]` part of `OPEN`
+fn parse_open_as_stat<'a>(iterator : &mut PushbackTokensIterator<'a>,
+                          mode : FileMode,
+                          file_name : Box<dyn GwExpression>)
+                          -> ParserResult<Rc<dyn GwInstruction>> {
+    let file_number = match iterator.next() {
+        Some(GwToken::Identifier(as_word)) if as_word == "AS" => parse_file_number(iterator),
+        // `AS#1` is read as a single word
+        Some(GwToken::Identifier(as_word)) if as_word == "AS#" => parse_expression(iterator),
+        _ => return ParserResult::Error(iterator.syntax_error())
+    };
+    let file_number = match file_number {
+        ParserResult::Success(file_number) => file_number,
+        _ => return ParserResult::Error(iterator.syntax_error())
+    };
+    let record_length = match iterator.next() {
+        Some(GwToken::Keyword(tokens::GwBasicToken::LenTok)) => {
+            if !matches!(iterator.next(), Some(GwToken::Keyword(tokens::GwBasicToken::EqlTok))) {
+                return ParserResult::Error(iterator.syntax_error());
+            }
+            match parse_expression(iterator) {
+                ParserResult::Success(length) => Some(length),
+                _ => return ParserResult::Error(iterator.syntax_error())
+            }
+        }
+        Some(token) => {
+            iterator.push_back(token);
+            None
+        }
+        None => None
+    };
+    ParserResult::Success(Rc::new(
+        GwOpen::new(GwOpenMode::For(mode), file_number, file_name, record_length)))
+}
+
+fn parse_open_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                       -> ParserResult<Rc<dyn GwInstruction>> {
+    let first = match parse_expression(iterator) {
+        ParserResult::Success(expr) => expr,
+        ParserResult::Error(error) => return ParserResult::Error(error),
+        ParserResult::Nothing => return ParserResult::Error(iterator.syntax_error())
+    };
+    match iterator.next() {
+        // OPEN "DATA.TXT" FOR OUTPUT AS #1
+        Some(GwToken::Keyword(tokens::GwBasicToken::ForTok)) => {
+            let mode = match iterator.next() {
+                Some(GwToken::Keyword(tokens::GwBasicToken::InpTok)) => FileMode::Input,
+                Some(GwToken::Identifier(mode)) if mode == "OUTPUT" => FileMode::Output,
+                Some(GwToken::Identifier(mode)) if mode == "APPEND" => FileMode::Append,
+                Some(GwToken::Identifier(mode)) if mode == "RANDOM" => FileMode::Random,
+                _ => return ParserResult::Error(iterator.syntax_error())
+            };
+            parse_open_as_stat(iterator, mode, first)
+        }
+        // Files opened without a mode are random files, OPEN "STOCK.DAT" AS #1 LEN = 32
+        Some(as_word@GwToken::Identifier(_)) => {
+            iterator.push_back(as_word);
+            parse_open_as_stat(iterator, FileMode::Random, first)
+        }
+        // OPEN "O", #1, "DATA.TXT"
+        Some(GwToken::Keyword(tokens::GwBasicToken::CommaSeparatorTok)) => {
+            parse_seq![
+                iterator,
+                {
+                    parse_success(file_number, parse_file_number(iterator));
+                    token(GwToken::Keyword(tokens::GwBasicToken::CommaSeparatorTok),
+                          "Expecting comma");
+                    parse_success(file_name, parse_expression(iterator));
+                    opt_token(GwToken::Keyword(tokens::GwBasicToken::CommaSeparatorTok),
+                              record_length = parse_expression(iterator));
+                },
+                {
+                    let record_length = match record_length {
+                        Some(ParserResult::Success(length)) => Some(length),
+                        Some(ParserResult::Error(error)) => return ParserResult::Error(error),
+                        Some(ParserResult::Nothing) => return ParserResult::Error(iterator.syntax_error()),
+                        None => None
+                    };
+                    ParserResult::Success(Rc::new(
+                        GwOpen::new(GwOpenMode::ModeString(first), file_number, file_name, record_length)))
+                }
+            ]
+        }
+        _ => ParserResult::Error(iterator.syntax_error())
+    }
+}
+
+fn parse_close_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                        -> ParserResult<Rc<dyn GwInstruction>> {
+    match iterator.next() {
+        Some(token@GwToken::Keyword(tokens::GwBasicToken::ColonSeparatorTok |
+                                    tokens::GwBasicToken::ElseTok)) => {
+            iterator.push_back(token);
+            ParserResult::Success(Rc::new(GwClose::new(vec![])))
+        }
+        Some(token) => {
+            iterator.push_back(token);
+            match parse_with_separator(iterator,
+                                       parse_file_number,
+                                       tokens::GwBasicToken::CommaSeparatorTok) {
+                ParserResult::Success(file_numbers) =>
+                    ParserResult::Success(Rc::new(GwClose::new(file_numbers))),
+                _ => ParserResult::Error(iterator.syntax_error())
+            }
+        }
+        None => ParserResult::Success(Rc::new(GwClose::new(vec![])))
+    }
+}
+
+/// Parses a `width AS variable` item of `FIELD`
+fn parse_field_item<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                        -> ParserResult<(Box<dyn GwExpression>, Box<dyn GwAssignableExpression>)> {
+    let width = match parse_expression(iterator) {
+        ParserResult::Success(width) => width,
+        _ => return ParserResult::Error(iterator.syntax_error())
+    };
+    match iterator.next() {
+        Some(GwToken::Identifier(as_word)) if as_word == "AS" => {}
+        _ => return ParserResult::Error(iterator.syntax_error())
+    }
+    match parse_restrict_identifier_expression(iterator) {
+        ParserResult::Success(variable) => ParserResult::Success((width, variable)),
+        _ => ParserResult::Error(iterator.syntax_error())
+    }
+}
+
+fn parse_field_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                        -> ParserResult<Rc<dyn GwInstruction>> {
+    parse_seq![
+        iterator,
+        {
+            parse_success(file_number, parse_file_number(iterator));
+            token(GwToken::Keyword(tokens::GwBasicToken::CommaSeparatorTok),
+                  "Expecting comma");
+            parse_success(fields, parse_with_separator(iterator,
+                                                       parse_field_item,
+                                                       tokens::GwBasicToken::CommaSeparatorTok));
+        },
+        {
+            ParserResult::Success(Rc::new(GwField::new(file_number, fields)))
+        }
+    ]
+}
+
+/// Parses `GET` and `PUT`, which have an optional record number
+fn parse_file_record_stat<'a>(iterator : &mut PushbackTokensIterator<'a>, put : bool)
+                              -> ParserResult<Rc<dyn GwInstruction>> {
+    parse_seq![
+        iterator,
+        {
+            parse_success(file_number, parse_file_number(iterator));
+            opt_token(GwToken::Keyword(tokens::GwBasicToken::CommaSeparatorTok),
+                      record_number = parse_expression(iterator));
+        },
+        {
+            let record_number = match record_number {
+                Some(ParserResult::Success(number)) => Some(number),
+                Some(ParserResult::Error(error)) => return ParserResult::Error(error),
+                Some(ParserResult::Nothing) => return ParserResult::Error(iterator.syntax_error()),
+                None => None
+            };
+            ParserResult::Success(Rc::new(
+                if put {
+                    GwFileRecord::put(file_number, record_number)
+                } else {
+                    GwFileRecord::get(file_number, record_number)
+                }))
+        }
+    ]
+}
+
+/// Parses `LSET` and `RSET`
+fn parse_set_str_stat<'a>(iterator : &mut PushbackTokensIterator<'a>, right : bool)
+                          -> ParserResult<Rc<dyn GwInstruction>> {
+    parse_seq![
+        iterator,
+        {
+            parse_success(target, parse_restrict_identifier_expression(iterator));
+            token(GwToken::Keyword(tokens::GwBasicToken::EqlTok),
+                  "Expecting equal sign");
+            parse_success(value, parse_expression(iterator));
+        },
+        {
+            ParserResult::Success(Rc::new(
+                if right {
+                    GwSetStr::rset(target, value)
+                } else {
+                    GwSetStr::lset(target, value)
+                }))
+        }
+    ]
+}
+
+fn parse_save_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                       -> ParserResult<Rc<dyn GwInstruction>> {
+    parse_seq![
+        iterator,
+        {
+            parse_success(file_name, parse_expression(iterator));
+            opt_token(GwToken::Keyword(tokens::GwBasicToken::CommaSeparatorTok),
+                      format = parse_save_format(iterator));
+        },
+        {
+            let format = match format {
+                Some(ParserResult::Success(format)) => format,
+                Some(ParserResult::Error(error)) => return ParserResult::Error(error),
+                Some(ParserResult::Nothing) => return ParserResult::Error(iterator.syntax_error()),
+                None => SaveFormat::Tokenized
+            };
+            ParserResult::Success(Rc::new(GwSave::new(file_name, format)))
+        }
+    ]
+}
+
+/// Branch of an `IF` statement, a line number or a list of statements
+enum IfBranch {
+    Line(i16),
+    Stats(Vec<Rc<dyn GwInstruction>>)
+}
+
+impl IfBranch {
+    fn into_stats(self) -> Vec<Rc<dyn GwInstruction>> {
+        match self {
+            IfBranch::Line(line) => vec![Rc::new(GwGotoStat { line })],
+            IfBranch::Stats(stats) => stats
+        }
+    }
+}
+
+fn parse_if_branch<'a>(iterator : &mut PushbackTokensIterator<'a>)
+                       -> ParserResult<IfBranch> {
+    match iterator.next() {
+        Some(GwToken::Integer(line_number)) =>
+            ParserResult::Success(IfBranch::Line(line_number)),
+        next_token => {
+            if let Some(token) = next_token {
+                iterator.push_back(token);
+            }
+            match parse_same_line_instruction_sequence(iterator) {
+                ParserResult::Success(stats) => ParserResult::Success(IfBranch::Stats(stats)),
+                ParserResult::Error(error) => ParserResult::Error(error),
+                ParserResult::Nothing => ParserResult::Error(iterator.syntax_error())
+            }
+        }
+    }
+}
+
 fn parse_if_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
                      -> ParserResult<Rc<dyn GwInstruction>> {
-
-    if let ParserResult::Success(expr) =  parse_expression(iterator) {
-        if let Some(GwToken::Keyword(tokens::GwBasicToken::ThenTok)) = iterator.next() {
-            let next_token = iterator.next();
-            if let Some(GwToken::Integer(line_number)) = next_token {
-                return ParserResult::Success(Rc::new(
-                    GwIf::new(expr, line_number)
-                ));
+    let expr = match parse_expression(iterator) {
+        ParserResult::Success(expr) => expr,
+        ParserResult::Error(error) => return ParserResult::Error(error),
+        ParserResult::Nothing => return ParserResult::Error(iterator.syntax_error())
+    };
+    let then_branch = match iterator.next() {
+        Some(GwToken::Keyword(tokens::GwBasicToken::ThenTok)) => {
+            match parse_if_branch(iterator) {
+                ParserResult::Success(branch) => branch,
+                ParserResult::Error(error) => return ParserResult::Error(error),
+                ParserResult::Nothing => return ParserResult::Error(iterator.syntax_error())
+            }
+        }
+        Some(GwToken::Keyword(tokens::GwBasicToken::GotoTok)) => {
+            if let Some(GwToken::Integer(line_number)) = iterator.next() {
+                IfBranch::Line(line_number)
             } else {
-                //TODO: REMOVE UNWRAP IN NEXT LINE
-                iterator.push_back(next_token.unwrap());
-                match parse_same_line_instruction_sequence(iterator) {
-                    ParserResult::Success(then_stats) => {
-                        return ParserResult::Success(Rc::new(
-                                   GwIfWithStats::new(expr, then_stats)));
-                    }
-                    ParserResult::Error(error) => ParserResult::Error(error),
-                    _ => todo!()
-                }
+                return ParserResult::Error(iterator.syntax_error());
             }
-        } else {
-            return ParserResult::Error(String::from("Expecting THEN keyword"));
         }
-    } else {
-        return ParserResult::Error(String::from("Expecting variable as expression as part of IF statement"));
+        _ => return ParserResult::Error(iterator.syntax_error())
+    };
+    let else_branch = match iterator.next() {
+        Some(GwToken::Keyword(tokens::GwBasicToken::ElseTok)) => {
+            match parse_if_branch(iterator) {
+                ParserResult::Success(branch) => Some(branch),
+                ParserResult::Error(error) => return ParserResult::Error(error),
+                ParserResult::Nothing => return ParserResult::Error(iterator.syntax_error())
+            }
+        }
+        Some(token) => {
+            iterator.push_back(token);
+            None
+        }
+        None => None
+    };
+    match (then_branch, else_branch) {
+        (IfBranch::Line(then_line), None) =>
+            ParserResult::Success(Rc::new(GwIf::new(expr, then_line))),
+        (IfBranch::Line(then_line), Some(IfBranch::Line(else_line))) =>
+            ParserResult::Success(Rc::new(GwIf::with_else(expr, then_line, else_line))),
+        (then_branch, else_branch) =>
+            ParserResult::Success(Rc::new(GwIfWithStats::with_else(
+                expr,
+                then_branch.into_stats(),
+                else_branch.map(IfBranch::into_stats))))
     }
 }
 
@@ -1318,7 +2302,7 @@ fn parse_var_range_fragment<'a>(iterator : &mut PushbackTokensIterator<'a>,
                         DefVarRange::Range(start, end.chars().next().unwrap())
                     );
                 } else {
-                    return ParserResult::Error(String::from("Expecting end of range "));
+                    return ParserResult::Error(iterator.syntax_error());
                 }
             }
             _ => {
@@ -1405,7 +2389,7 @@ fn parse_key_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
             ParserResult::Success(Rc::new(GwKeyStat { indicator : SwitchIndicator::On })),
         Some(GwToken::Keyword(tokens::GwBasicToken::OffTok)) =>
             ParserResult::Success(Rc::new(GwKeyStat { indicator : SwitchIndicator::Off })),
-        _ => ParserResult::Error(String::from("Error parsing Key statement"))
+        _ => ParserResult::Error(iterator.syntax_error())
     }
 }
 
@@ -1422,7 +2406,7 @@ fn parse_with_separator<'a, F,T>(
         if let ParserResult::Success(item) = item_result {
             result.push(item);
         } else {
-            return ParserResult::Error(String::from("error parsing sequence"));
+            return ParserResult::Error(iterator.syntax_error());
         }
         let next_token = iterator.next();
         match next_token {
@@ -1442,18 +2426,22 @@ fn parse_optional_tab<'a>(iterator : &mut PushbackTokensIterator<'a>)
                           -> ParserResult<PrintElementWrapper> {
     let next_token_opt = iterator.next();
     if let Some(next_token) = next_token_opt {
-        if let GwToken::Keyword(tokens::GwBasicToken::TabTok) = next_token {
+        if let GwToken::Keyword(function_token@(tokens::GwBasicToken::TabTok |
+                                                tokens::GwBasicToken::SpcTok)) = next_token {
             parse_seq![
 	        iterator,
 	        {
 	            token(GwToken::Keyword(tokens::GwBasicToken::LparTok),
                           "Expecting left parenthesis");
-                    parse_success(tabs, parse_expression(iterator));
+                    parse_success(argument, parse_expression(iterator));
                     token(GwToken::Keyword(tokens::GwBasicToken::RparTok),
                           "Expecting right parenthesis");
 	        },
 	        {
-	            return ParserResult::Success(PrintElementWrapper::Tab(tabs));
+                    if function_token == tokens::GwBasicToken::SpcTok {
+                        return ParserResult::Success(PrintElementWrapper::Spc(argument));
+                    }
+	            return ParserResult::Success(PrintElementWrapper::Tab(argument));
 	        }
             ]
         } else {
@@ -1516,7 +2504,7 @@ fn parse_with_flexible_separator<'a>(
                 iterator.push_back(token_result);
                 break;
             },
-            (_, PrintElementWrapper::Expr(_) | PrintElementWrapper::Tab(_)) => {
+            (_, PrintElementWrapper::Expr(_) | PrintElementWrapper::Tab(_) | PrintElementWrapper::Spc(_)) => {
                 result.push((item_result, None));
                 break;
             }
@@ -1548,10 +2536,10 @@ fn parse_color_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
                 blue: blue_expr
             }));
         } else {
-           return ParserResult::Error(String::from("Error parsing Color color components"));
+           return ParserResult::Error(iterator.syntax_error());
         }
     } else {
-        return ParserResult::Error(String::from("Error recognizing colors"));
+        return ParserResult::Error(iterator.syntax_error());
     }
 }
 
@@ -1564,7 +2552,7 @@ fn parse_goto_stat<'a>(iterator : &mut PushbackTokensIterator<'a>)
             }
         ));
     } else {
-        return ParserResult::Error(String::from("Expecting number at GOTO statement"));
+        return ParserResult::Error(iterator.syntax_error());
     }
 }
 
@@ -1588,16 +2576,16 @@ fn parse_array_assignemnt<'a>(
                                 expression: expr
                             }));
                 } else {
-                    return ParserResult::Error(String::from("Error expression expected"));
+                    return ParserResult::Error(iterator.syntax_error());
                 }
             } else {
-                return ParserResult::Error(String::from("Equal  expected"));
+                return ParserResult::Error(iterator.syntax_error());
             }
         } else {
-            return ParserResult::Error(String::from("Right parenthesis expected"));
+            return ParserResult::Error(iterator.syntax_error());
         }
     } else {
-        return ParserResult::Error(String::from("Error parsing  array indices"));
+        return ParserResult::Error(iterator.syntax_error());
     }
 }
 
@@ -1617,13 +2605,13 @@ fn parse_assignment<'a>(iterator : &mut PushbackTokensIterator<'a>, identifier :
                         expression: expr
                     }));
             } else {
-                return ParserResult::Error(String::from("Error parsing  assignment"));
+                return ParserResult::Error(iterator.syntax_error());
             }
         } else {
-            return ParserResult::Error(String::from("Expecting assignment"));
+            return ParserResult::Error(iterator.syntax_error());
         }
     } else {
-        return ParserResult::Error(String::from("cannot parse assignment"));
+        return ParserResult::Error(iterator.syntax_error());
     }
 }
 
@@ -1644,6 +2632,9 @@ fn parse_instruction<'a>(iterator : &mut PushbackTokensIterator<'a>) -> ParserRe
             GwToken::Keyword(tokens::GwBasicToken::ListTok)  => parse_list_stat(iterator),
             GwToken::Keyword(tokens::GwBasicToken::RunTok)  => parse_run_stat(iterator),
             GwToken::Keyword(tokens::GwBasicToken::LoadTok)  => parse_load_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::SaveTok)  => parse_save_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::NewTok)  => parse_new_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::DeleteTok)  => parse_delete_stat(iterator),
             GwToken::Keyword(tokens::GwBasicToken::IfTok)  => parse_if_stat(iterator),
             GwToken::Keyword(tokens::GwBasicToken::SystemTok)  => parse_system_stat(iterator),
             GwToken::Keyword(tokens::GwBasicToken::InpTok)  => parse_input_stat(iterator),
@@ -1656,15 +2647,30 @@ fn parse_instruction<'a>(iterator : &mut PushbackTokensIterator<'a>) -> ParserRe
             GwToken::Keyword(tokens::GwBasicToken::GosubTok) => parse_gosub_stat(iterator), 
             GwToken::Keyword(tokens::GwBasicToken::ReturnTok) => parse_return_stat(iterator), 
             GwToken::Keyword(tokens::GwBasicToken::ReadTok) => parse_read_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::RestoreTok) => parse_restore_stat(iterator),
             GwToken::Keyword(tokens::GwBasicToken::DataTok) => parse_data_stat(iterator),            
             GwToken::Keyword(tokens::GwBasicToken::NextTok) => parse_next_stat(iterator),
             GwToken::Keyword(tokens::GwBasicToken::OnTok) => parse_on_goto_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::ResumeTok) => parse_resume_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::DefTok) => parse_def_fn_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::ErrorTok) => parse_error_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::MidDTok) => parse_mid_assignment_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::RandomizeTok) => parse_randomize_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::OptionTok) => parse_option_base_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::EraseTok) => parse_erase_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::ClearTok) => parse_clear_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::OpenTok) => parse_open_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::CloseTok) => parse_close_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::WriteTok) => parse_write_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::LineTok) => parse_line_input_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::FieldTok) => parse_field_stat(iterator),
+            GwToken::Keyword(tokens::GwBasicToken::GetTok) => parse_file_record_stat(iterator, false),
+            GwToken::Keyword(tokens::GwBasicToken::PutTok) => parse_file_record_stat(iterator, true),
+            GwToken::Keyword(tokens::GwBasicToken::LsetTok) => parse_set_str_stat(iterator, false),
+            GwToken::Keyword(tokens::GwBasicToken::RsetTok) => parse_set_str_stat(iterator, true),
 
             GwToken::Identifier(var_name) => parse_assignment(iterator, var_name),
-            t => {
-                println!("About to panic: {:?}", t);
-                panic!("Not implemented parsing for non-assigment")
-            }
+            _ => ParserResult::Error(iterator.syntax_error())
 
         }
     } else {
@@ -1726,8 +2732,12 @@ fn continue_parse_same_line_instruction_sequence<'a>(iterator : &mut PushbackTok
         if let GwToken::Keyword(tokens::GwBasicToken::ColonSeparatorTok) = next_tok {
             iterator.push_back(next_tok);
             return parsing_same_line_instruction_sequence(iterator)
+        } else if let GwToken::Keyword(tokens::GwBasicToken::ElseTok) = next_tok {
+            // The sequence ends at the `ELSE` of the enclosing `IF`
+            iterator.push_back(next_tok);
+            return ParserResult::Nothing;
         } else {
-            return ParserResult::Error(String::from("Expecting colon"));
+            return ParserResult::Error(iterator.syntax_error());
         }
     } else {
         return ParserResult::Nothing;
@@ -1738,15 +2748,26 @@ fn parsing_same_line_instruction_sequence<'a>(iterator : &mut PushbackTokensIter
                                               -> ParserResult<Vec<Rc<dyn GwInstruction>>> {
     let mut results = Vec::<Rc<dyn GwInstruction>>::new();
     while let Some(next_tok) = iterator.next() {
-        if let GwToken::Keyword(tokens::GwBasicToken::ColonSeparatorTok) = next_tok {
+        if let GwToken::Keyword(tokens::GwBasicToken::ElseTok) = next_tok {
+            iterator.push_back(next_tok);
+            break;
+        } else if let GwToken::Keyword(tokens::GwBasicToken::ColonSeparatorTok) = next_tok {
+            match iterator.next() {
+                Some(else_tok@GwToken::Keyword(tokens::GwBasicToken::ElseTok)) => {
+                    iterator.push_back(else_tok);
+                    break;
+                }
+                Some(token) => iterator.push_back(token),
+                None => {}
+            }
             let instr_result = parse_instruction(iterator);
             if let ParserResult::Success(parsed_instruction) = instr_result {
                 results.push(parsed_instruction.into());
             } else {
-                return ParserResult::Error(String::from("Error parsing same line statement"));
+                return ParserResult::Error(iterator.syntax_error());
             }
         } else {
-            return ParserResult::Error(String::from("Expecting colon"));
+            return ParserResult::Error(iterator.syntax_error());
         }
     }
     return ParserResult::Success(results);
@@ -1756,45 +2777,59 @@ pub fn parse_instruction_line<'a>(iterator : &mut PushbackTokensIterator<'a>)
                                   -> ParserResult<ProgramLine> {
     if let Some(next_tok) = iterator.next() {
         if let GwToken::Integer(line_number) = next_tok {
-            let parse_result = parse_instruction(iterator);
-            if let ParserResult::Success(instr) = parse_result {
-                match continue_parse_same_line_instruction_sequence(iterator) {
-                 ParserResult::Success(rest_inst) => {
-                     ParserResult::Success(
-                        ProgramLine {
-                            line : line_number,
-                            instruction : instr,
-                            rest_instructions : Some(rest_inst)
-                        }
-                    )
-                 },
-                 ParserResult::Nothing => {
-                     ParserResult::Success(
-                        ProgramLine {
-                            line: line_number,
-                            instruction: instr,
-                            rest_instructions : None
-                        }
-                    )
-                 },
-                 ParserResult::Error(err) =>
-                       ParserResult::Error(err)
-                }
-            } else {
-                if let ParserResult::Error(msg) = parse_result {
-                    ParserResult::Error(msg)
-                } else {
-                    ParserResult::Error(String::from("Error parsing line"))
-                }
+            match parse_numbered_line_instructions(iterator, line_number) {
+                ParserResult::Error(err) => ParserResult::Error(err.at_line(line_number)),
+                result => result
             }
         } else {
-            ParserResult::Error(String::from("line number expected"))
+            ParserResult::Error(iterator.syntax_error())
         }
     } else {
         ParserResult::Nothing
     }
 }
 
+fn parse_numbered_line_instructions<'a>(iterator : &mut PushbackTokensIterator<'a>,
+                                        line_number : i16)
+                                        -> ParserResult<ProgramLine> {
+    let parse_result = parse_instruction(iterator);
+    if let ParserResult::Success(instr) = parse_result {
+        let rest_result = continue_parse_same_line_instruction_sequence(iterator);
+        if !matches!(rest_result, ParserResult::Error(_)) && iterator.next().is_some() {
+            // Only an `ELSE` without a matching `IF` stops the sequence
+            return ParserResult::Error(iterator.syntax_error());
+        }
+        match rest_result {
+            ParserResult::Success(rest_inst) => {
+                ParserResult::Success(
+                    ProgramLine {
+                        line : line_number,
+                        instruction : instr,
+                        rest_instructions : Some(rest_inst)
+                    }
+                )
+            },
+            ParserResult::Nothing => {
+                ParserResult::Success(
+                    ProgramLine {
+                        line: line_number,
+                        instruction: instr,
+                        rest_instructions : None
+                    }
+                )
+            },
+            ParserResult::Error(err) =>
+                ParserResult::Error(err)
+        }
+    } else {
+        if let ParserResult::Error(msg) = parse_result {
+            ParserResult::Error(msg)
+        } else {
+            ParserResult::Error(iterator.syntax_error())
+        }
+    }
+}
+
 
 
 #[cfg(test)]
@@ -1970,7 +3005,7 @@ mod parser_tests {
             ParserResult::Success(instr) => {
                 let mut buf = String::new();
                 instr.fill_structure_string(&mut buf);
-                assert_eq!(buf, String::from("(10 X = AB)"));
+                assert_eq!(buf, String::from("10 X = AB"));
                 Ok(())
             }
             _ => Err("Instruction not parsed")
@@ -1993,6 +3028,44 @@ mod parser_tests {
         }
     }
 
+    #[test]
+    fn it_reports_column_of_unrecognized_chars() -> Result<(), & 'static str>{
+        let str = "10 PRINT 1 ~ 2";
+        let pb = PushbackCharsIterator {
+            chars: str.chars(),
+            pushed_back: None
+        };
+        let mut tokens_iterator = PushbackTokensIterator::create(pb);
+        match parse_instruction_line(&mut tokens_iterator) {
+            ParserResult::Error(error) => {
+                assert_eq!(12, error.column);
+                assert_eq!("Syntax error in 10", error.to_string());
+                Ok(())
+            }
+            _ => Err("Instruction must not be parsed")
+        }
+    }
+
+    #[test]
+    fn it_reports_unsupported_statements_as_errors() -> Result<(), & 'static str>{
+        for str in ["THEN 10", "10 X = 1: TO", "10 IF X THEN"] {
+            let pb = PushbackCharsIterator {
+                chars: str.chars(),
+                pushed_back: None
+            };
+            let mut tokens_iterator = PushbackTokensIterator::create(pb);
+            let is_error = if str.starts_with("10") {
+                matches!(parse_instruction_line(&mut tokens_iterator), ParserResult::Error(_))
+            } else {
+                matches!(parse_instruction(&mut tokens_iterator), ParserResult::Error(_))
+            };
+            if !is_error {
+                return Err("Instruction must not be parsed");
+            }
+        }
+        Ok(())
+    }
+
     #[test]
     fn it_parses_input_no_prompt() -> Result<(), String> {
         let str = "10 INPUT a";
@@ -2005,42 +3078,65 @@ mod parser_tests {
             ParserResult::Success(instr) => {
                 let mut buf = String::new();
                 instr.fill_structure_string(&mut buf);
-                assert_eq!(buf, String::from("(10 INPUT A)"));
-		return Ok(());
+                assert_eq!(buf, String::from("10 INPUT A"));
+		Ok(())
             }
-            ParserResult::Error(error) => { return Err(error); }
-	    ParserResult::Nothing => { return Err("Nothing".to_string()); }
+            ParserResult::Error(error) => Err(error.to_string()),
+	    ParserResult::Nothing => Err("Nothing".to_string())
         }
     }
 
     #[test]
     fn it_parses_if_with_stats() -> Result<(), String>{
         let result = get_parsed_ast_string("10 IF A>1 THEN PRINT \"a\" : PRINT \"b\"")?;
-        assert_eq!("(10 IF (A > 1) THEN PRINT a; : PRINT b;)" ,result);
+        assert_eq!("10 IF (A > 1) THEN PRINT \"a\" : PRINT \"b\"" ,result);
         Ok(())
     }
 
     #[test]
     fn it_parses_simple_data() -> Result<(), String> {
         let result = get_parsed_ast_string("10 DATA 1.23,343,,45")?;
-        assert_eq!("(10 DATA 1.23, 343, , 45)" ,result);
+        assert_eq!("10 DATA 1.23, 343, , 45" ,result);
         Ok(())
     }
 
     #[test]
     fn it_parses_read() -> Result<(), String> {
         let result = get_parsed_ast_string("10 READ x")?;
-        assert_eq!("(10 READ X)" ,result);
+        assert_eq!("10 READ X" ,result);
 
         let result_arr = get_parsed_ast_string("10 READ x(1)")?;
-        assert_eq!("(10 READ X(1))" ,result_arr);
+        assert_eq!("10 READ X(1)" ,result_arr);
         Ok(())
     }
 
     #[test]
     fn it_parses_if_with_stat() -> Result<(), String>{
         let result = get_parsed_ast_string("10 IF A>1 THEN PRINT \"a\"")?;
-        assert_eq!("(10 IF (A > 1) THEN PRINT a;)" ,result);
+        assert_eq!("10 IF (A > 1) THEN PRINT \"a\"" ,result);
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_if_with_else() -> Result<(), String>{
+        let result = get_parsed_ast_string("10 IF A>1 THEN PRINT \"a\" : PRINT \"b\" ELSE PRINT \"c\"")?;
+        assert_eq!("10 IF (A > 1) THEN PRINT \"a\" : PRINT \"b\" ELSE PRINT \"c\"" ,result);
+        let result = get_parsed_ast_string("10 IF A>1 THEN 100 ELSE 200")?;
+        assert_eq!("10 IF (A > 1) THEN 100 ELSE 200" ,result);
+        let result = get_parsed_ast_string("10 IF A>1 GOTO 100")?;
+        assert_eq!("10 IF (A > 1) THEN 100" ,result);
+        let result = get_parsed_ast_string("10 IF A>1 THEN 100 ELSE PRINT \"c\"")?;
+        assert_eq!("10 IF (A > 1) THEN GOTO 100 ELSE PRINT \"c\"" ,result);
+        Ok(())
+    }
+
+    #[test]
+    fn it_binds_else_to_nearest_if() -> Result<(), String>{
+        let result = get_parsed_ast_string("10 IF A THEN IF B THEN 20 ELSE 30 ELSE 40")?;
+        assert_eq!("10 IF A THEN IF B THEN 20 ELSE 30 ELSE GOTO 40" ,result);
+        let result = get_parsed_ast_string("10 IF A THEN IF B THEN 20 ELSE 30")?;
+        assert_eq!("10 IF A THEN IF B THEN 20 ELSE 30" ,result);
+        assert!(get_parsed_ast_string("10 PRINT \"a\" ELSE 30").is_err());
         Ok(())
     }
 
@@ -2056,11 +3152,11 @@ mod parser_tests {
             ParserResult::Success(instr) => {
                 let mut buf = String::new();
                 instr.fill_structure_string(&mut buf);
-                assert_eq!(buf, String::from("(10 INPUT \"hello?\",A,B,C)"));
-		return Ok(());
+                assert_eq!(buf, String::from("10 INPUT \"hello?\",A,B,C"));
+		Ok(())
             }
-            ParserResult::Error(error) => { return Err(error); }
-	    ParserResult::Nothing => { return Err("Nothing".to_string()); }
+            ParserResult::Error(error) => Err(error.to_string()),
+	    ParserResult::Nothing => Err("Nothing".to_string())
         }
     }
 
@@ -2077,11 +3173,11 @@ mod parser_tests {
             ParserResult::Success(instr) => {
                 let mut buf = String::new();
                 instr.fill_structure_string(&mut buf);
-                assert_eq!(buf, String::from("(10 (DIM A(10,(FOO + 1)))"));
-		return Ok(());
+                assert_eq!(buf, String::from("10 DIM A(10,(FOO + 1))"));
+		Ok(())
             }
-            ParserResult::Error(error) => { return Err(error); }
-	    ParserResult::Nothing => { return Err("Nothing".to_string()); }
+            ParserResult::Error(error) => Err(error.to_string()),
+	    ParserResult::Nothing => Err("Nothing".to_string())
         }
     }
 
@@ -2098,7 +3194,7 @@ mod parser_tests {
                 let mut buf = String::new();
                 instr.fill_structure_string(&mut buf);
 
-                assert_eq!(buf, String::from("(10 X = AB :Y = BC:Z = CD)"));
+                assert_eq!(buf, String::from("10 X = AB :Y = BC:Z = CD"));
             }
             _ => panic!("errror")
         }
@@ -2320,7 +3416,7 @@ mod parser_tests {
             ParserResult::Success(expr) => {
                 let mut buf = String::new();
                 expr.fill_structure_string(&mut buf);
-                assert_eq!(buf, String::from("-((1 + 1))"));
+                assert_eq!(buf, String::from("-(1 + 1)"));
             }
             _ => panic!("errror")
         }
@@ -2374,7 +3470,7 @@ mod parser_tests {
 		let mut buf = String::new();
 		instr.fill_structure_string(&mut buf);
 		println!("{}", buf);
-		assert_eq!(buf, "(10 IF (X = 1) 30)");
+		assert_eq!(buf, "10 IF (X = 1) THEN 30");
 	    }
 	    _ => panic!("IF not parsed!")
 	}
@@ -2389,7 +3485,7 @@ mod parser_tests {
 	    ParserResult::Success(instr) => {
 		let mut buf = String::new();
 		instr.fill_structure_string(&mut buf);
-		assert_eq!(buf, "(10 WHILE (X = 1))");
+		assert_eq!(buf, "10 WHILE (X = 1)");
 	    }
 	    _ => panic!("WHILE not parsed!")
 	}
@@ -2405,7 +3501,7 @@ mod parser_tests {
 	    ParserResult::Success(instr) => {
 		let mut buf = String::new();
 		instr.fill_structure_string(&mut buf);
-		assert_eq!(buf, "(10 ON X GOTO 10, 20, 30)");
+		assert_eq!(buf, "10 ON X GOTO 10, 20, 30");
                 Ok(())
 	    }
 	    _ => Err("ON/GOTO not parsed!")
@@ -2421,13 +3517,87 @@ mod parser_tests {
 	    ParserResult::Success(instr) => {
 		let mut buf = String::new();
 		instr.fill_structure_string(&mut buf);
-		assert_eq!(buf, "(10 SWAP X, Y)");
+		assert_eq!(buf, "10 SWAP X, Y");
                 Ok(())
 	    }
 	    _ => Err("SWAP not parsed!")
 	}
     }
 
+    #[test]
+    fn it_parses_logical_operators_with_precedence() -> Result<(), String> {
+        let result = get_parsed_ast_string("10 X = A > 0 AND B$ <> \"\" OR NOT C = 1 AND D")?;
+        assert_eq!("10 X = (((A > 0) AND (B$ <> \"\")) OR (NOT (C = 1) AND D))", result);
+        let result = get_parsed_ast_string("10 X = A IMP B EQV C XOR D")?;
+        assert_eq!("10 X = (A IMP (B EQV (C XOR D)))", result);
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_integer_division_and_mod_with_precedence() -> Result<(), String> {
+        let result = get_parsed_ast_string("10 X = A + B MOD C \\ D * E - F")?;
+        assert_eq!("10 X = ((A + (B MOD (C \\ (D * E)))) - F)", result);
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_def_fn() -> Result<(), String> {
+        let result = get_parsed_ast_string("10 DEF FNAREA(W, H) = W * H")?;
+        assert_eq!("10 DEF FNAREA(W,H) = (W * H)", result);
+        let result = get_parsed_ast_string("10 DEF FN PI = 3.14 : X = FNPI * FN AREA(1, 2)")?;
+        assert_eq!("10 DEF FNPI = 3.14 :X = (FNPI * FNAREA(1,2))", result);
+        assert!(get_parsed_ast_string("10 DEF A(X) = X").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_string_functions() -> Result<(), String> {
+        let result = get_parsed_ast_string("10 X = INSTR(2, A$, \"B\") + LEN(MID$(A$, 2))")?;
+        assert_eq!("10 X = (INSTR(2, A$, \"B\") + LEN(MID$(A$, 2)))", result);
+        let result = get_parsed_ast_string("10 MID$(A$, 2, 1) = CHR$(65)")?;
+        assert_eq!("10 MID$(A$, 2, 1) = CHR$(65)", result);
+        assert!(get_parsed_ast_string("10 X = LEN(A$, 1)").is_err());
+        assert!(get_parsed_ast_string("10 MID$(A$) = B$").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_rnd_and_randomize() -> Result<(), String> {
+        let result = get_parsed_ast_string("10 RANDOMIZE TIMER : X = RND * 6 + RND(-1)")?;
+        assert_eq!("10 RANDOMIZE TIMER :X = ((RND * 6) + RND(-1))", result);
+        let result = get_parsed_ast_string("10 RANDOMIZE")?;
+        assert_eq!("10 RANDOMIZE", result);
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_type_suffixes() -> Result<(), String> {
+        let result = get_parsed_ast_string("10 A% = B! + C# : D$ = \"X\"")?;
+        assert_eq!("10 A% = (B! + C#) :D$ = \"X\"", result);
+        let result = get_parsed_ast_string("10 DEFINT I-N, X")?;
+        assert_eq!("10 DEFINT I-N,X", result);
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_data_read_and_restore() -> Result<(), String> {
+        let result = get_parsed_ast_string("10 DATA 1, \"A: B,C\" , X : READ X, Y$(2) : RESTORE 10")?;
+        assert_eq!("10 DATA 1, \"A: B,C\", X :READ X, Y$(2):RESTORE 10", result);
+        let result = get_parsed_ast_string("10 RESTORE")?;
+        assert_eq!("10 RESTORE", result);
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_erase_and_clear() -> Result<(), String> {
+        let result = get_parsed_ast_string("10 ERASE A, B$ : CLEAR")?;
+        assert_eq!("10 ERASE A, B$ :CLEAR", result);
+        let result = get_parsed_ast_string("10 CLEAR ,32768,1000")?;
+        assert_eq!("10 CLEAR ,32768,1000", result);
+        assert!(get_parsed_ast_string("10 ERASE").is_err());
+        Ok(())
+    }
+
     #[test]
     fn it_parser_division_with_highest_precedence() {
 	let str = "10 x = 2*3/4*5";
@@ -2438,7 +3608,7 @@ mod parser_tests {
 		let mut buf = String::new();
 		instr.fill_structure_string(&mut buf);
 		println!("{}", buf);
-		assert_eq!(buf, "(10 X = (((2 * 3) / 4) * 5))");
+		assert_eq!(buf, "10 X = (((2 * 3) / 4) * 5)");
 	    }
 	    _ => assert!(false)
 	}
@@ -2454,7 +3624,7 @@ mod parser_tests {
 		let mut buf = String::new();
 		instr.fill_structure_string(&mut buf);
 		println!("{}", buf);
-		assert_eq!(buf, "(10 X = (2 * (5 ^ 3)))");
+		assert_eq!(buf, "10 X = (2 * (5 ^ 3))");
 	    }
 	    _ => assert!(false)
 	}
@@ -2497,11 +3667,11 @@ mod parser_tests {
                recognize_float_number_str(&mut pb),
                consume_whitespace(&mut pb),
                recognize_float_number_str(&mut pb)) {
-            (Some(GwToken::Double(d1)),
+            (Some(GwToken::Single(d1, _)),
              _,
-             Some(GwToken::Double(d2)),
+             Some(GwToken::Single(d2, _)),
              _,
-             Some(GwToken::Double(d3))) => {
+             Some(GwToken::Single(d3, _))) => {
                 assert_eq!(10.1, d1);
                 assert_eq!(0.2, d2);
                 assert_eq!(0.32, d3);
@@ -2513,6 +3683,66 @@ mod parser_tests {
         }
     }
 
+    #[test]
+    fn it_identifies_radix_exponent_and_suffixed_numbers() {
+        let mut pb = PushbackCharsIterator::new("&H1F &O17 &17 &HFFFF 100% &Z".chars());
+        let mut tokens = vec![];
+        while let Some(token) = recognize_float_number_str(&mut pb) {
+            tokens.push(format!("{:?}", token));
+            consume_whitespace(&mut pb);
+        }
+        assert_eq!(vec!["FormattedInteger(31, \"&H1F\")",
+                        "FormattedInteger(15, \"&O17\")",
+                        "FormattedInteger(15, \"&17\")",
+                        "FormattedInteger(-1, \"&HFFFF\")",
+                        "FormattedInteger(100, \"100%\")",
+                        "Unrecognized('&')"],
+                   tokens);
+
+        let mut pb = PushbackCharsIterator::new("1.5E+10 2.5d-3 3# 2! 40000 12345678 7".chars());
+        let mut tokens = vec![];
+        while let Some(token) = recognize_float_number_str(&mut pb) {
+            tokens.push(format!("{:?}", token));
+            consume_whitespace(&mut pb);
+        }
+        assert_eq!(vec!["Single(15000000000.0, \"1.5E+10\")",
+                        "Double(0.0025, \"2.5D-3\")",
+                        "Double(3.0, \"3#\")",
+                        "Single(2.0, \"2!\")",
+                        "Single(40000.0, \"40000\")",
+                        "Double(12345678.0, \"12345678\")",
+                        "Integer(7)"],
+                   tokens);
+    }
+
+    #[test]
+    fn it_prints_back_numeric_literals() -> Result<(), String> {
+        let result = get_parsed_ast_string("10 X = &H1F + &O17 + 1.5E+10 + 2.5D-3 + 3# + 100%")?;
+        assert_eq!("10 X = (((((&H1F + &O17) + 1.5E+10) + 2.5D-3) + 3#) + 100%)", result);
+        Ok(())
+    }
+
+    #[test]
+    fn it_keeps_incomplete_and_overflowing_numbers() {
+        let mut pb = PushbackCharsIterator::new("40000% 1E 1E+ &HFFFFF 1E39 .".chars());
+        let mut tokens = vec![];
+        while let Some(token) = recognize_float_number_str(&mut pb) {
+            tokens.push(format!("{:?}", token));
+            consume_whitespace(&mut pb);
+        }
+        assert_eq!(vec!["NumberOverflow(\"40000%\")",
+                        "Single(1.0, \"1E\")",
+                        "Single(1.0, \"1E+\")",
+                        "NumberOverflow(\"&HFFFFF\")",
+                        "NumberOverflow(\"1E39\")",
+                        "Single(0.0, \".\")"],
+                   tokens);
+
+        assert_eq!(Err(String::from("Overflow in 10")), get_parsed_ast_string("10 PRINT 40000%"));
+        assert_eq!(Err(String::from("Overflow in 10")), get_parsed_ast_string("10 PRINT &HFFFFF"));
+        assert_eq!(Ok(String::from("10 PRINT (1E + 1E+)")), get_parsed_ast_string("10 PRINT 1E + 1E+"));
+    }
+
     #[test]
     fn it_identifies_identifiers() {
         let str = "werwe10    Excs    AJLAKJ";
@@ -2535,6 +3765,29 @@ mod parser_tests {
         }
     }
 
+    #[test]
+    fn it_lists_lines_that_parse_back_identically() -> Result<(), String> {
+        let lines = [
+            "10 PRINT \"A\";B,C; : PRINT",
+            "10 PRINT A$ B$;TAB(5);SPC(3),",
+            "10 PRINT #1, USING \"##.##\";A;B",
+            "10 X = -A + NOT B - (C) : Y = (A + B) * ((C))",
+            "10 IF A THEN PRINT \"Y\" ELSE 20",
+            "10 FOR I = 1 TO 10 STEP 2 : NEXT I",
+            "10 GOSUB 100 : ON X GOSUB 10, 20 : RETURN",
+            "10 DIM A(10), B$(2, N + 1)",
+            "10 DEFINT A-C, X : DEFSTR S",
+            "10 REM  two spaces",
+            "10 OPEN \"F\" AS 1 LEN = 20 : FIELD #1, 20 AS A$ : LSET A$ = \"(X\"",
+            "10 DELETE 10-50 : DELETE -30 : DELETE 100- : DELETE 20 : NEW",
+        ];
+        for line in lines {
+            let listed = get_parsed_ast_string(line)?;
+            assert_eq!(Ok(listed.clone()), get_parsed_ast_string(&listed));
+        }
+        Ok(())
+    }
+
     fn get_parsed_ast_string(a_str: &str) -> Result<String, String> {
         let pb = PushbackCharsIterator {
             chars: a_str.chars(),
@@ -2545,10 +3798,10 @@ mod parser_tests {
             ParserResult::Success(instr) => {
                 let mut buf = String::new();
                 instr.fill_structure_string(&mut buf);
-		return Ok(buf);
+		Ok(buf)
             }
-            ParserResult::Error(error) => { return Err(error); }
-	    ParserResult::Nothing => { return Err("Nothing".to_string()); }
+            ParserResult::Error(error) => Err(error.to_string()),
+	    ParserResult::Nothing => Err("Nothing".to_string())
         }
     }
 
@@ -204,6 +204,221 @@ pub enum GwBasicToken {
 }
 
 
+/// Codes of the tokens in the programs saved by GW-BASIC and the
+/// text of their keywords, codes of two bytes start with `0xFD`,
+/// `0xFE` or `0xFF`
+const TOKEN_CODES: [(u16, GwBasicToken, &str); 184] = [
+    (0x81, GwBasicToken::EndTok, "END"),
+    (0x82, GwBasicToken::ForTok, "FOR"),
+    (0x83, GwBasicToken::NextTok, "NEXT"),
+    (0x84, GwBasicToken::DataTok, "DATA"),
+    (0x85, GwBasicToken::InputTok, "INPUT"),
+    (0x86, GwBasicToken::DimTok, "DIM"),
+    (0x87, GwBasicToken::ReadTok, "READ"),
+    (0x88, GwBasicToken::LetTok, "LET"),
+    (0x89, GwBasicToken::GotoTok, "GOTO"),
+    (0x8A, GwBasicToken::RunTok, "RUN"),
+    (0x8B, GwBasicToken::IfTok, "IF"),
+    (0x8C, GwBasicToken::RestoreTok, "RESTORE"),
+    (0x8D, GwBasicToken::GosubTok, "GOSUB"),
+    (0x8E, GwBasicToken::ReturnTok, "RETURN"),
+    (0x8F, GwBasicToken::RemTok, "REM"),
+    (0x90, GwBasicToken::StopTok, "STOP"),
+    (0x91, GwBasicToken::PrintTok, "PRINT"),
+    (0x92, GwBasicToken::ClearTok, "CLEAR"),
+    (0x93, GwBasicToken::ListTok, "LIST"),
+    (0x94, GwBasicToken::NewTok, "NEW"),
+    (0x95, GwBasicToken::OnTok, "ON"),
+    (0x96, GwBasicToken::WaitTok, "WAIT"),
+    (0x97, GwBasicToken::DefTok, "DEF"),
+    (0x98, GwBasicToken::PokeTok, "POKE"),
+    (0x99, GwBasicToken::ContTok, "CONT"),
+    (0x9C, GwBasicToken::OutTok, "OUT"),
+    (0x9D, GwBasicToken::LprintTok, "LPRINT"),
+    (0x9E, GwBasicToken::LlistTok, "LLIST"),
+    (0xA0, GwBasicToken::WidthTok, "WIDTH"),
+    (0xA1, GwBasicToken::ElseTok, "ELSE"),
+    (0xA2, GwBasicToken::TronTok, "TRON"),
+    (0xA3, GwBasicToken::TroffTok, "TROFF"),
+    (0xA4, GwBasicToken::SwapTok, "SWAP"),
+    (0xA5, GwBasicToken::EraseTok, "ERASE"),
+    (0xA6, GwBasicToken::EditTok, "EDIT"),
+    (0xA7, GwBasicToken::ErrorTok, "ERROR"),
+    (0xA8, GwBasicToken::ResumeTok, "RESUME"),
+    (0xA9, GwBasicToken::DeleteTok, "DELETE"),
+    (0xAA, GwBasicToken::AutoTok, "AUTO"),
+    (0xAB, GwBasicToken::RenumTok, "RENUM"),
+    (0xAC, GwBasicToken::DefstrTok, "DEFSTR"),
+    (0xAD, GwBasicToken::DefintTok, "DEFINT"),
+    (0xAE, GwBasicToken::DefsngTok, "DEFSNG"),
+    (0xAF, GwBasicToken::DefdblTok, "DEFDBL"),
+    (0xB0, GwBasicToken::LineTok, "LINE"),
+    (0xB1, GwBasicToken::WhileTok, "WHILE"),
+    (0xB2, GwBasicToken::WendTok, "WEND"),
+    (0xB3, GwBasicToken::CallTok, "CALL"),
+    (0xB7, GwBasicToken::WriteTok, "WRITE"),
+    (0xB8, GwBasicToken::OptionTok, "OPTION"),
+    (0xB9, GwBasicToken::RandomizeTok, "RANDOMIZE"),
+    (0xBA, GwBasicToken::OpenTok, "OPEN"),
+    (0xBB, GwBasicToken::CloseTok, "CLOSE"),
+    (0xBC, GwBasicToken::LoadTok, "LOAD"),
+    (0xBD, GwBasicToken::MergeTok, "MERGE"),
+    (0xBE, GwBasicToken::SaveTok, "SAVE"),
+    (0xBF, GwBasicToken::ColorTok, "COLOR"),
+    (0xC0, GwBasicToken::ClsTok, "CLS"),
+    (0xC1, GwBasicToken::MotorTok, "MOTOR"),
+    (0xC2, GwBasicToken::BsaveTok, "BSAVE"),
+    (0xC3, GwBasicToken::BloadTok, "BLOAD"),
+    (0xC4, GwBasicToken::SoundTok, "SOUND"),
+    (0xC5, GwBasicToken::BeepTok, "BEEP"),
+    (0xC6, GwBasicToken::PsetTok, "PSET"),
+    (0xC7, GwBasicToken::PresetTok, "PRESET"),
+    (0xC8, GwBasicToken::ScreenTok, "SCREEN"),
+    (0xC9, GwBasicToken::KeyTok, "KEY"),
+    (0xCA, GwBasicToken::LocateTok, "LOCATE"),
+    (0xCC, GwBasicToken::ToTok, "TO"),
+    (0xCD, GwBasicToken::ThenTok, "THEN"),
+    (0xCE, GwBasicToken::TabTok, "TAB("),
+    (0xCF, GwBasicToken::StepTok, "STEP"),
+    (0xD0, GwBasicToken::UsrTok, "USR"),
+    (0xD1, GwBasicToken::FnTok, "FN"),
+    (0xD2, GwBasicToken::SpcTok, "SPC("),
+    (0xD3, GwBasicToken::NotTok, "NOT"),
+    (0xD4, GwBasicToken::ErlTok, "ERL"),
+    (0xD5, GwBasicToken::ErrTok, "ERR"),
+    (0xD6, GwBasicToken::StringDTok, "STRING$"),
+    (0xD7, GwBasicToken::UsingTok, "USING"),
+    (0xD8, GwBasicToken::InstrTok, "INSTR"),
+    (0xD9, GwBasicToken::SingleQuoteTok, "'"),
+    (0xDA, GwBasicToken::VarptrTok, "VARPTR"),
+    (0xDB, GwBasicToken::CsrlinTok, "CSRLIN"),
+    (0xDC, GwBasicToken::PointTok, "POINT"),
+    (0xDD, GwBasicToken::OffTok, "OFF"),
+    (0xDE, GwBasicToken::InkeyDTok, "INKEY$"),
+    (0xE6, GwBasicToken::GtTok, ">"),
+    (0xE7, GwBasicToken::EqlTok, "="),
+    (0xE8, GwBasicToken::LtTok, "<"),
+    (0xE9, GwBasicToken::PlusTok, "+"),
+    (0xEA, GwBasicToken::MinusTok, "-"),
+    (0xEB, GwBasicToken::TimesTok, "*"),
+    (0xEC, GwBasicToken::DivTok, "/"),
+    (0xED, GwBasicToken::PowOperatorTok, "^"),
+    (0xEE, GwBasicToken::AndTok, "AND"),
+    (0xEF, GwBasicToken::OrTok, "OR"),
+    (0xF0, GwBasicToken::XorTok, "XOR"),
+    (0xF1, GwBasicToken::EqvTok, "EQV"),
+    (0xF2, GwBasicToken::ImpTok, "IMP"),
+    (0xF3, GwBasicToken::ModTok, "MOD"),
+    (0xF4, GwBasicToken::Div2Tok, "\\"),
+    (0xFD81, GwBasicToken::CviTok, "CVI"),
+    (0xFD82, GwBasicToken::CvsTok, "CVS"),
+    (0xFD83, GwBasicToken::CvdTok, "CVD"),
+    (0xFD84, GwBasicToken::MkiDTok, "MKI$"),
+    (0xFD85, GwBasicToken::MksDTok, "MKS$"),
+    (0xFD86, GwBasicToken::MkdDTok, "MKD$"),
+    (0xFD8B, GwBasicToken::ExterrTok, "EXTERR"),
+    (0xFE81, GwBasicToken::FilesTok, "FILES"),
+    (0xFE82, GwBasicToken::FieldTok, "FIELD"),
+    (0xFE83, GwBasicToken::SystemTok, "SYSTEM"),
+    (0xFE84, GwBasicToken::NameTok, "NAME"),
+    (0xFE85, GwBasicToken::LsetTok, "LSET"),
+    (0xFE86, GwBasicToken::RsetTok, "RSET"),
+    (0xFE87, GwBasicToken::KillTok, "KILL"),
+    (0xFE88, GwBasicToken::PutTok, "PUT"),
+    (0xFE89, GwBasicToken::GetTok, "GET"),
+    (0xFE8A, GwBasicToken::ResetTok, "RESET"),
+    (0xFE8B, GwBasicToken::CommonTok, "COMMON"),
+    (0xFE8C, GwBasicToken::ChainTok, "CHAIN"),
+    (0xFE8D, GwBasicToken::DateDTok, "DATE$"),
+    (0xFE8E, GwBasicToken::TimeDTok, "TIME$"),
+    (0xFE8F, GwBasicToken::PaintTok, "PAINT"),
+    (0xFE90, GwBasicToken::ComTok, "COM"),
+    (0xFE91, GwBasicToken::CircleTok, "CIRCLE"),
+    (0xFE92, GwBasicToken::DrawTok, "DRAW"),
+    (0xFE93, GwBasicToken::PlayTok, "PLAY"),
+    (0xFE94, GwBasicToken::TimerTok, "TIMER"),
+    (0xFE95, GwBasicToken::ErdevTok, "ERDEV"),
+    (0xFE96, GwBasicToken::IoctlTok, "IOCTL"),
+    (0xFE97, GwBasicToken::ChdirTok, "CHDIR"),
+    (0xFE98, GwBasicToken::MkdirTok, "MKDIR"),
+    (0xFE99, GwBasicToken::RmdirTok, "RMDIR"),
+    (0xFE9A, GwBasicToken::ShellTok, "SHELL"),
+    (0xFE9B, GwBasicToken::EnvironTok, "ENVIRON"),
+    (0xFE9C, GwBasicToken::ViewTok, "VIEW"),
+    (0xFE9D, GwBasicToken::WindowTok, "WINDOW"),
+    (0xFE9E, GwBasicToken::PmapTok, "PMAP"),
+    (0xFE9F, GwBasicToken::PaletteTok, "PALETTE"),
+    (0xFEA0, GwBasicToken::LcopyTok, "LCOPY"),
+    (0xFEA1, GwBasicToken::CallsTok, "CALLS"),
+    (0xFEA4, GwBasicToken::NoiseTok, "NOISE"),
+    (0xFEA5, GwBasicToken::PcopyTok, "PCOPY"),
+    (0xFEA6, GwBasicToken::TermTok, "TERM"),
+    (0xFEA7, GwBasicToken::LockTok, "LOCK"),
+    (0xFEA8, GwBasicToken::UnlockTok, "UNLOCK"),
+    (0xFF81, GwBasicToken::LeftDTok, "LEFT$"),
+    (0xFF82, GwBasicToken::RightDTok, "RIGHT$"),
+    (0xFF83, GwBasicToken::MidDTok, "MID$"),
+    (0xFF84, GwBasicToken::SgnTok, "SGN"),
+    (0xFF85, GwBasicToken::IntTok, "INT"),
+    (0xFF86, GwBasicToken::AbsTok, "ABS"),
+    (0xFF87, GwBasicToken::SqrTok, "SQR"),
+    (0xFF88, GwBasicToken::RndTok, "RND"),
+    (0xFF89, GwBasicToken::SinTok, "SIN"),
+    (0xFF8A, GwBasicToken::LogTok, "LOG"),
+    (0xFF8B, GwBasicToken::ExpTok, "EXP"),
+    (0xFF8C, GwBasicToken::CosTok, "COS"),
+    (0xFF8D, GwBasicToken::TanTok, "TAN"),
+    (0xFF8E, GwBasicToken::AtnTok, "ATN"),
+    (0xFF8F, GwBasicToken::FreTok, "FRE"),
+    (0xFF90, GwBasicToken::InpTok, "INP"),
+    (0xFF91, GwBasicToken::PosTok, "POS"),
+    (0xFF92, GwBasicToken::LenTok, "LEN"),
+    (0xFF93, GwBasicToken::StrDTok, "STR$"),
+    (0xFF94, GwBasicToken::ValTok, "VAL"),
+    (0xFF95, GwBasicToken::AscTok, "ASC"),
+    (0xFF96, GwBasicToken::ChrDTok, "CHR$"),
+    (0xFF97, GwBasicToken::PeekTok, "PEEK"),
+    (0xFF98, GwBasicToken::SpaceDTok, "SPACE$"),
+    (0xFF99, GwBasicToken::OctDTok, "OCT$"),
+    (0xFF9A, GwBasicToken::HexDTok, "HEX$"),
+    (0xFF9B, GwBasicToken::LposTok, "LPOS"),
+    (0xFF9C, GwBasicToken::CintTok, "CINT"),
+    (0xFF9D, GwBasicToken::CsngTok, "CSNG"),
+    (0xFF9E, GwBasicToken::CdblTok, "CDBL"),
+    (0xFF9F, GwBasicToken::FixTok, "FIX"),
+    (0xFFA0, GwBasicToken::PenTok, "PEN"),
+    (0xFFA1, GwBasicToken::StickTok, "STICK"),
+    (0xFFA2, GwBasicToken::StrigTok, "STRIG"),
+    (0xFFA3, GwBasicToken::EofTok, "EOF"),
+    (0xFFA4, GwBasicToken::LocTok, "LOC"),
+    (0xFFA5, GwBasicToken::LofTok, "LOF"),
+];
+
+impl GwBasicToken {
+    /// Token stored with the given code in a tokenized program and
+    /// the text of its keyword
+    pub fn from_code(code: u16) -> Option<(GwBasicToken, &'static str)> {
+        TOKEN_CODES.iter()
+            .find(|(token_code, _, _)| *token_code == code)
+            .map(|(_, token, text)| (token.clone(), *text))
+    }
+
+    /// Token of a keyword as written in a listing, such as `LEFT$` or `TAB(`
+    pub fn from_keyword_text(text: &str) -> Option<GwBasicToken> {
+        TOKEN_CODES.iter()
+            .find(|(_, _, keyword)| *keyword == text)
+            .map(|(_, token, _)| token.clone())
+    }
+
+    /// Code of the token in a tokenized program
+    pub fn code(&self) -> Option<u16> {
+        TOKEN_CODES.iter()
+            .find(|(_, token, _)| token == self)
+            .map(|(code, _, _)| *code)
+    }
+}
+
+
 pub struct GwTokenInfo {
     token_text : HashMap<String, GwBasicToken>,
 //    token_vs_text : HashMap<GwBasicToken, String>
@@ -231,11 +446,14 @@ impl GwTokenInfo {
         GwTokenInfo::add_token("KEY", GwBasicToken::KeyTok, &mut dict, &mut dict2);
         GwTokenInfo::add_token("LIST", GwBasicToken::ListTok, &mut dict, &mut dict2);
         GwTokenInfo::add_token("LOAD", GwBasicToken::LoadTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("NEW", GwBasicToken::NewTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("DELETE", GwBasicToken::DeleteTok, &mut dict, &mut dict2);
         GwTokenInfo::add_token("OFF", GwBasicToken::OffTok, &mut dict, &mut dict2);
         GwTokenInfo::add_token("ON", GwBasicToken::OnTok, &mut dict, &mut dict2);
         GwTokenInfo::add_token("PRINT", GwBasicToken::PrintTok, &mut dict, &mut dict2);
         GwTokenInfo::add_token("REM", GwBasicToken::RemTok, &mut dict, &mut dict2);
         GwTokenInfo::add_token("RUN", GwBasicToken::RunTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("SAVE", GwBasicToken::SaveTok, &mut dict, &mut dict2);
         GwTokenInfo::add_token("SYSTEM", GwBasicToken::SystemTok, &mut dict, &mut dict2);
         GwTokenInfo::add_token("TAB", GwBasicToken::TabTok, &mut dict, &mut dict2);        
         GwTokenInfo::add_token("THEN", GwBasicToken::ThenTok, &mut dict, &mut dict2);
@@ -250,6 +468,72 @@ impl GwTokenInfo {
         GwTokenInfo::add_token("STEP", GwBasicToken::StepTok, &mut dict, &mut dict2);
         GwTokenInfo::add_token("STOP", GwBasicToken::StopTok, &mut dict, &mut dict2);
         GwTokenInfo::add_token("RETURN", GwBasicToken::ReturnTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("DEF", GwBasicToken::DefTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("FN", GwBasicToken::FnTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("ELSE", GwBasicToken::ElseTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("MOD", GwBasicToken::ModTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("NOT", GwBasicToken::NotTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("AND", GwBasicToken::AndTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("OR", GwBasicToken::OrTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("XOR", GwBasicToken::XorTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("EQV", GwBasicToken::EqvTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("IMP", GwBasicToken::ImpTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("ERROR", GwBasicToken::ErrorTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("RESUME", GwBasicToken::ResumeTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("ERR", GwBasicToken::ErrTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("ERL", GwBasicToken::ErlTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("MID$", GwBasicToken::MidDTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("RIGHT$", GwBasicToken::RightDTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("LEN", GwBasicToken::LenTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("INSTR", GwBasicToken::InstrTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("CHR$", GwBasicToken::ChrDTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("ASC", GwBasicToken::AscTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("STR$", GwBasicToken::StrDTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("VAL", GwBasicToken::ValTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("STRING$", GwBasicToken::StringDTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("SPACE$", GwBasicToken::SpaceDTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("RANDOMIZE", GwBasicToken::RandomizeTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("TIMER", GwBasicToken::TimerTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("OPTION", GwBasicToken::OptionTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("ERASE", GwBasicToken::EraseTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("CLEAR", GwBasicToken::ClearTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("RESTORE", GwBasicToken::RestoreTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("SPC", GwBasicToken::SpcTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("OPEN", GwBasicToken::OpenTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("CLOSE", GwBasicToken::CloseTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("WRITE", GwBasicToken::WriteTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("LINE", GwBasicToken::LineTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("EOF", GwBasicToken::EofTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("LOF", GwBasicToken::LofTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("LOC", GwBasicToken::LocTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("FIELD", GwBasicToken::FieldTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("GET", GwBasicToken::GetTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("PUT", GwBasicToken::PutTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("LSET", GwBasicToken::LsetTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("RSET", GwBasicToken::RsetTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("MKI$", GwBasicToken::MkiDTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("MKS$", GwBasicToken::MksDTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("MKD$", GwBasicToken::MkdDTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("CVI", GwBasicToken::CviTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("CVS", GwBasicToken::CvsTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("CVD", GwBasicToken::CvdTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("SQR", GwBasicToken::SqrTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("TAN", GwBasicToken::TanTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("ATN", GwBasicToken::AtnTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("EXP", GwBasicToken::ExpTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("SGN", GwBasicToken::SgnTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("FIX", GwBasicToken::FixTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("CINT", GwBasicToken::CintTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("CSNG", GwBasicToken::CsngTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("CDBL", GwBasicToken::CdblTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("HEX$", GwBasicToken::HexDTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("OCT$", GwBasicToken::OctDTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("ABS", GwBasicToken::AbsTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("LOG", GwBasicToken::LogTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("INT", GwBasicToken::IntTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("SIN", GwBasicToken::SinTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("COS", GwBasicToken::CosTok, &mut dict, &mut dict2);
+        GwTokenInfo::add_token("RND", GwBasicToken::RndTok, &mut dict, &mut dict2);
 
         GwTokenInfo::add_token("*", GwBasicToken::TimesTok, &mut dict, &mut dict2);
         GwTokenInfo::add_token("/", GwBasicToken::DivTok, &mut dict, &mut dict2);